@@ -0,0 +1,113 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use gpui_hn_app::api::{ApiService, NetworkConfig, init_tokio_handle};
+use gpui_hn_app::internal::models::Comment;
+use serde_json::json;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Simulated round trip to the API
+const LATENCY: Duration = Duration::from_millis(5);
+const ROOTS: u32 = 10;
+const FANOUT: u32 = 3;
+const MAX_DEPTH: u32 = 3;
+
+/// Serve a thread of `ROOTS` top-level comments, each with `FANOUT` replies per
+/// level down to `MAX_DEPTH`. Returns the ids of the top-level comments.
+fn mock_thread(server: &mut mockito::Server) -> Vec<u32> {
+    let mut next_id = 1;
+    let mut level: Vec<u32> = (0..ROOTS)
+        .map(|_| {
+            next_id += 1;
+            next_id
+        })
+        .collect();
+    let roots = level.clone();
+
+    for depth in 0..=MAX_DEPTH {
+        let mut next_level = Vec::new();
+        for &id in &level {
+            let kids: Vec<u32> = match depth < MAX_DEPTH {
+                true => (0..FANOUT)
+                    .map(|_| {
+                        next_id += 1;
+                        next_id
+                    })
+                    .collect(),
+                false => Vec::new(),
+            };
+            let body =
+                json!({ "id": id, "type": "comment", "text": "reply", "kids": kids }).to_string();
+            server
+                .mock("GET", format!("/item/{}.json", id).as_str())
+                .with_chunked_body(move |w| {
+                    std::thread::sleep(LATENCY);
+                    w.write_all(body.as_bytes())
+                })
+                .create();
+            next_level.extend(kids);
+        }
+        level = next_level;
+    }
+
+    roots
+}
+
+/// A service with an empty cache that isn't held back by rate limiting.
+fn service(server: &mockito::Server) -> ApiService {
+    ApiService::with_config(NetworkConfig {
+        api_base_url: Some(format!("{}/", server.url())),
+        rate_limit_per_second: 10_000.0,
+        rate_limit_burst: 10_000,
+        ..NetworkConfig::default()
+    })
+}
+
+/// The previous approach: fetch a level of siblings, then each child subtree in turn.
+async fn fetch_depth_first(api: &ApiService, ids: Vec<u32>, depth: u32) -> Vec<Comment> {
+    let mut results = Vec::new();
+    for comment in api.fetch_comments_concurrent(ids, None).await {
+        let kids = comment.kids.clone();
+        results.push(comment);
+        if depth < MAX_DEPTH
+            && let Some(kids) = kids
+        {
+            results.extend(Box::pin(fetch_depth_first(api, kids, depth + 1)).await);
+        }
+    }
+    results
+}
+
+fn bench_comment_tree(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    init_tokio_handle(runtime.handle().clone());
+
+    let mut server = mockito::Server::new();
+    let roots = mock_thread(&mut server);
+
+    let mut group = c.benchmark_group("comment_tree");
+    group.sample_size(10);
+
+    group.bench_function("depth_first_sequential", |b| {
+        b.iter(|| {
+            let api = service(&server);
+            runtime.block_on(fetch_depth_first(&api, roots.clone(), 0))
+        })
+    });
+
+    group.bench_function("breadth_first_levels", |b| {
+        b.iter(|| {
+            let api = service(&server);
+            runtime.block_on(api.fetch_comment_tree(
+                roots.clone(),
+                0,
+                MAX_DEPTH,
+                CancellationToken::new(),
+            ))
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_comment_tree);
+criterion_main!(benches);
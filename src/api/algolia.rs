@@ -0,0 +1,542 @@
+//! Full-text search and whole-thread loading backed by the HN Algolia API.
+//!
+//! Algolia indexes every story and comment ever posted, so unlike the Firebase
+//! story lists it can find items that have long dropped off the front page. Its
+//! `items` endpoint also returns a story with its entire comment tree at once.
+
+use super::{ApiError, ApiService};
+use crate::internal::models::{Comment, CommentViewModel, Story};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use strum_macros::Display;
+use tokio_util::sync::CancellationToken;
+
+pub const HN_ALGOLIA_BASE_URL: &str = "https://hn.algolia.com/api/v1/";
+
+/// Default number of hits per page, matching the story list batch size.
+const DEFAULT_HITS_PER_PAGE: u32 = 20;
+
+/// Item type filters supported by the Algolia `tags` parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display)]
+pub enum SearchTag {
+    Story,
+    Comment,
+    #[strum(to_string = "Ask HN")]
+    AskHn,
+    #[strum(to_string = "Show HN")]
+    ShowHn,
+}
+
+impl SearchTag {
+    pub const ALL: [SearchTag; 4] = [Self::Story, Self::Comment, Self::AskHn, Self::ShowHn];
+
+    fn as_api_str(&self) -> &str {
+        match self {
+            Self::Story => "story",
+            Self::Comment => "comment",
+            Self::AskHn => "ask_hn",
+            Self::ShowHn => "show_hn",
+        }
+    }
+}
+
+/// Result ordering: `search` ranks by relevance, `search_by_date` by recency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display)]
+pub enum SearchSort {
+    #[default]
+    Relevance,
+    Date,
+}
+
+impl SearchSort {
+    fn as_api_str(&self) -> &str {
+        match self {
+            Self::Relevance => "search",
+            Self::Date => "search_by_date",
+        }
+    }
+}
+
+/// A full-text search request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchQuery {
+    /// Free text to match against titles, urls, authors and comment bodies
+    pub text: String,
+    /// Item types to include; several tags are OR-ed together
+    pub tags: Vec<SearchTag>,
+    /// Only return items posted by this user
+    pub author: Option<String>,
+    /// Only return items created at or after this unix timestamp (seconds)
+    pub created_after: Option<i64>,
+    /// Only return items created before this unix timestamp (seconds)
+    pub created_before: Option<i64>,
+    /// Zero-based result page
+    pub page: u32,
+    pub hits_per_page: u32,
+}
+
+impl Default for SearchQuery {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            tags: Vec::new(),
+            author: None,
+            created_after: None,
+            created_before: None,
+            page: 0,
+            hits_per_page: DEFAULT_HITS_PER_PAGE,
+        }
+    }
+}
+
+impl SearchQuery {
+    /// Parse search bar input into a query.
+    ///
+    /// Recognises `author:<name>`, `after:<YYYY-MM-DD>` and `before:<YYYY-MM-DD>`
+    /// filter tokens; everything else becomes the free-text part of the query.
+    /// Tokens with an unparseable date are kept as plain text.
+    pub fn parse(input: &str) -> Self {
+        let mut query = Self::default();
+        let mut words = Vec::new();
+
+        for word in input.split_whitespace() {
+            match word.split_once(':') {
+                Some(("author", name)) if !name.is_empty() => {
+                    query.author = Some(name.to_string());
+                }
+                Some(("after", date)) if parse_date(date).is_some() => {
+                    query.created_after = parse_date(date);
+                }
+                Some(("before", date)) if parse_date(date).is_some() => {
+                    query.created_before = parse_date(date);
+                }
+                _ => words.push(word),
+            }
+        }
+
+        query.text = words.join(" ");
+        query
+    }
+
+    /// Build the query-string parameters understood by the Algolia endpoints.
+    fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![("query", self.text.clone())];
+
+        let mut tag_filters = Vec::new();
+        match self.tags.len() {
+            0 => {}
+            1 => tag_filters.push(self.tags[0].as_api_str().to_string()),
+            _ => tag_filters.push(format!(
+                "({})",
+                self.tags
+                    .iter()
+                    .map(|t| t.as_api_str())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )),
+        }
+        if let Some(author) = &self.author {
+            tag_filters.push(format!("author_{}", author));
+        }
+        if !tag_filters.is_empty() {
+            params.push(("tags", tag_filters.join(",")));
+        }
+
+        let mut numeric_filters = Vec::new();
+        if let Some(after) = self.created_after {
+            numeric_filters.push(format!("created_at_i>={}", after));
+        }
+        if let Some(before) = self.created_before {
+            numeric_filters.push(format!("created_at_i<{}", before));
+        }
+        if !numeric_filters.is_empty() {
+            params.push(("numericFilters", numeric_filters.join(",")));
+        }
+
+        params.push(("page", self.page.to_string()));
+        params.push(("hitsPerPage", self.hits_per_page.to_string()));
+        params
+    }
+
+    /// Full request URL for the given endpoint ordering.
+    fn url(&self, base_url: &str, sort: SearchSort) -> Result<String> {
+        let endpoint = format!("{}{}", base_url, sort.as_api_str());
+        let url = reqwest::Url::parse_with_params(&endpoint, self.to_params())
+            .with_context(|| format!("invalid search endpoint {}", endpoint))?;
+        Ok(url.to_string())
+    }
+}
+
+/// Parse a `YYYY-MM-DD` date into a unix timestamp at UTC midnight.
+fn parse_date(date: &str) -> Option<i64> {
+    let date: jiff::civil::Date = date.parse().ok()?;
+    let zoned = date.to_zoned(jiff::tz::TimeZone::UTC).ok()?;
+    Some(zoned.timestamp().as_second())
+}
+
+/// A single story or comment returned by Algolia.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct SearchHit {
+    #[serde(rename = "objectID")]
+    pub object_id: String,
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub author: Option<String>,
+    pub points: Option<u32>,
+    pub num_comments: Option<u32>,
+    pub created_at_i: Option<i64>,
+    pub story_id: Option<u32>,
+    pub story_title: Option<String>,
+    pub story_url: Option<String>,
+    pub comment_text: Option<String>,
+    #[serde(default, rename = "_tags")]
+    pub tags: Vec<String>,
+}
+
+impl SearchHit {
+    pub fn is_comment(&self) -> bool {
+        self.tags.iter().any(|t| t == "comment")
+    }
+
+    /// Convert the hit into a `Story` so it can be shown with the story list rows.
+    ///
+    /// Comment hits map to the story they were posted on, so opening the row
+    /// leads to the containing thread. Returns `None` for hits without a usable id.
+    pub fn to_story(&self) -> Option<Story> {
+        match self.is_comment() {
+            true => Some(Story {
+                id: self.story_id?,
+                title: self.story_title.clone(),
+                url: self.story_url.clone(),
+                by: self.author.clone(),
+                score: None,
+                time: self.created_at_i,
+                descendants: None,
+                kids: None,
+                ..Default::default()
+            }),
+            false => Some(Story {
+                id: self.object_id.parse().ok()?,
+                title: self.title.clone(),
+                url: self.url.clone(),
+                by: self.author.clone(),
+                score: self.points,
+                time: self.created_at_i,
+                descendants: self.num_comments,
+                kids: None,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+/// One page of search results.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    #[serde(rename = "nbHits")]
+    pub total_hits: u32,
+    pub page: u32,
+    #[serde(rename = "nbPages")]
+    pub total_pages: u32,
+    #[serde(rename = "hitsPerPage")]
+    pub hits_per_page: u32,
+}
+
+impl SearchResults {
+    /// Whether another page can be requested after this one.
+    pub fn has_more(&self) -> bool {
+        self.page + 1 < self.total_pages
+    }
+
+    /// Stories for every hit that maps to one, in result order.
+    pub fn stories(&self) -> Vec<Story> {
+        self.hits.iter().filter_map(SearchHit::to_story).collect()
+    }
+}
+
+/// An item with its whole reply tree, from the Algolia `items/{id}` endpoint.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ThreadItem {
+    pub id: u32,
+    pub author: Option<String>,
+    pub text: Option<String>,
+    pub created_at_i: Option<i64>,
+    pub parent_id: Option<u32>,
+    #[serde(default)]
+    pub children: Vec<ThreadItem>,
+}
+
+impl ThreadItem {
+    /// The item as a Firebase-style comment. Algolia keeps deleted comments that
+    /// have replies, without author or text.
+    pub fn to_comment(&self) -> Comment {
+        Comment {
+            id: self.id,
+            by: self.author.clone(),
+            text: self.text.clone(),
+            time: self.created_at_i,
+            kids: Some(self.children.iter().map(|c| c.id).collect()),
+            deleted: self.author.is_none() && self.text.is_none(),
+            dead: false,
+            parent: self.parent_id,
+        }
+    }
+
+    /// All replies in depth-first display order, with the top-level ones at depth 0.
+    ///
+    /// Algolia orders replies by age, not by HN rank, so top-level replies are put in
+    /// the order of `ranked`, normally the story's `kids`. Deeper replies keep
+    /// Algolia's order.
+    pub fn flatten_replies(&self, ranked: &[u32]) -> Vec<CommentViewModel> {
+        let mut top_level: Vec<&ThreadItem> = self.children.iter().collect();
+        top_level.sort_by_key(|c| {
+            ranked
+                .iter()
+                .position(|&id| id == c.id)
+                .unwrap_or(usize::MAX)
+        });
+
+        let mut flat = Vec::new();
+        let mut stack: Vec<(&ThreadItem, u32)> =
+            top_level.into_iter().rev().map(|c| (c, 0)).collect();
+        while let Some((item, depth)) = stack.pop() {
+            flat.push(CommentViewModel {
+                id: item.id,
+                comment: item.to_comment(),
+                depth,
+                collapsed: false,
+                loading: false,
+                replies_loaded: true,
+            });
+            stack.extend(item.children.iter().rev().map(|c| (c, depth + 1)));
+        }
+        flat
+    }
+}
+
+impl ApiService {
+    /// Fetch every comment of a story in one request to the Algolia `items`
+    /// endpoint, in display order. See [`ThreadItem::flatten_replies`] for `ranked`.
+    #[tracing::instrument(skip(self, ranked, token), fields(id = %story_id))]
+    pub async fn fetch_thread_comments(
+        &self,
+        story_id: u32,
+        ranked: &[u32],
+        token: Option<CancellationToken>,
+    ) -> Result<Vec<CommentViewModel>> {
+        let start = std::time::Instant::now();
+        let url = format!("{}items/{}", self.get_algolia_base_url(), story_id);
+
+        let thread: ThreadItem = match token {
+            Some(token) => token
+                .run_until_cancelled(self.get_json(&url))
+                .await
+                .unwrap_or_else(|| Err(ApiError::Cancelled.into()))?,
+            None => self.get_json(&url).await?,
+        };
+        let comments = thread.flatten_replies(ranked);
+
+        if self.enable_metrics {
+            tracing::debug!(
+                elapsed = ?start.elapsed(),
+                comments = comments.len(),
+                "Fetched whole thread from Algolia"
+            );
+        }
+
+        Ok(comments)
+    }
+
+    /// Search all of Hacker News, ranked by relevance.
+    #[tracing::instrument(skip(self, token), fields(text = %query.text, page = query.page))]
+    pub async fn search(
+        &self,
+        query: &SearchQuery,
+        token: Option<CancellationToken>,
+    ) -> Result<SearchResults> {
+        self.run_search(query, SearchSort::Relevance, token).await
+    }
+
+    /// Search all of Hacker News, most recent first.
+    #[tracing::instrument(skip(self, token), fields(text = %query.text, page = query.page))]
+    pub async fn search_by_date(
+        &self,
+        query: &SearchQuery,
+        token: Option<CancellationToken>,
+    ) -> Result<SearchResults> {
+        self.run_search(query, SearchSort::Date, token).await
+    }
+
+    /// Search with the given ordering.
+    pub async fn search_sorted(
+        &self,
+        query: &SearchQuery,
+        sort: SearchSort,
+        token: Option<CancellationToken>,
+    ) -> Result<SearchResults> {
+        match sort {
+            SearchSort::Relevance => self.search(query, token).await,
+            SearchSort::Date => self.search_by_date(query, token).await,
+        }
+    }
+
+    async fn run_search(
+        &self,
+        query: &SearchQuery,
+        sort: SearchSort,
+        token: Option<CancellationToken>,
+    ) -> Result<SearchResults> {
+        let start = std::time::Instant::now();
+
+        if let Some(token) = &token
+            && token.is_cancelled()
+        {
+            return Err(ApiError::Cancelled.into());
+        }
+
+        let url = query.url(self.get_algolia_base_url(), sort)?;
+
+        let results: SearchResults = if let Some(token) = token {
+            tokio::select! {
+                res = self.get_json(&url) => {
+                    res.with_context(|| format!("{} failed for query {:?}", sort.as_api_str(), query.text))?
+                }
+                _ = token.cancelled() => {
+                    return Err(ApiError::Cancelled.into());
+                }
+            }
+        } else {
+            self.get_json(&url).await.with_context(|| {
+                format!("{} failed for query {:?}", sort.as_api_str(), query.text)
+            })?
+        };
+
+        if self.enable_metrics {
+            tracing::debug!(
+                elapsed = ?start.elapsed(),
+                hits = results.hits.len(),
+                total = results.total_hits,
+                "Search completed"
+            );
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plain_text() {
+        let query = SearchQuery::parse("  rust   async ");
+        assert_eq!(query.text, "rust async");
+        assert_eq!(query.author, None);
+        assert_eq!(query.created_after, None);
+    }
+
+    #[test]
+    fn test_parse_filters() {
+        let query = SearchQuery::parse("author:pg lisp after:2020-01-01 before:2020-01-02");
+        assert_eq!(query.text, "lisp");
+        assert_eq!(query.author, Some("pg".to_string()));
+        assert_eq!(query.created_after, Some(1_577_836_800));
+        assert_eq!(query.created_before, Some(1_577_923_200));
+    }
+
+    #[test]
+    fn test_parse_invalid_date_kept_as_text() {
+        let query = SearchQuery::parse("after:yesterday");
+        assert_eq!(query.text, "after:yesterday");
+        assert_eq!(query.created_after, None);
+    }
+
+    #[test]
+    fn test_query_params() {
+        let query = SearchQuery {
+            text: "rust".to_string(),
+            tags: vec![SearchTag::Story, SearchTag::ShowHn],
+            author: Some("dang".to_string()),
+            created_after: Some(100),
+            created_before: Some(200),
+            page: 2,
+            hits_per_page: 50,
+        };
+
+        let params = query.to_params();
+        assert!(params.contains(&("query", "rust".to_string())));
+        assert!(params.contains(&("tags", "(story,show_hn),author_dang".to_string())));
+        assert!(params.contains(&(
+            "numericFilters",
+            "created_at_i>=100,created_at_i<200".to_string()
+        )));
+        assert!(params.contains(&("page", "2".to_string())));
+        assert!(params.contains(&("hitsPerPage", "50".to_string())));
+    }
+
+    #[test]
+    fn test_query_url() {
+        let query = SearchQuery {
+            text: "hello world".to_string(),
+            tags: vec![SearchTag::Comment],
+            ..Default::default()
+        };
+
+        let url = query.url(HN_ALGOLIA_BASE_URL, SearchSort::Date).unwrap();
+        assert!(url.starts_with("https://hn.algolia.com/api/v1/search_by_date?"));
+        assert!(url.contains("query=hello+world"));
+        assert!(url.contains("tags=comment"));
+    }
+
+    #[test]
+    fn test_comment_hit_maps_to_parent_story() {
+        let hit: SearchHit = serde_json::from_str(
+            r#"{
+                "objectID": "500",
+                "author": "commenter",
+                "comment_text": "Nice",
+                "story_id": 42,
+                "story_title": "Parent",
+                "story_url": "https://example.com",
+                "created_at_i": 1000,
+                "_tags": ["comment", "author_commenter", "story_42"]
+            }"#,
+        )
+        .unwrap();
+
+        let story = hit.to_story().unwrap();
+        assert_eq!(story.id, 42);
+        assert_eq!(story.title, Some("Parent".to_string()));
+        assert_eq!(story.by, Some("commenter".to_string()));
+    }
+
+    #[test]
+    fn test_thread_flattens_in_rank_order_with_depths() {
+        let thread: ThreadItem = serde_json::from_str(
+            r#"{
+                "id": 1, "author": "op", "text": null, "created_at_i": 100, "parent_id": null,
+                "children": [
+                    {"id": 2, "author": "a", "text": "old", "created_at_i": 110, "parent_id": 1,
+                     "children": [
+                        {"id": 4, "author": null, "text": null, "created_at_i": 130, "parent_id": 2,
+                         "children": [
+                            {"id": 5, "author": "c", "text": "deep", "created_at_i": 140, "parent_id": 4, "children": []}
+                         ]}
+                     ]},
+                    {"id": 3, "author": "b", "text": "top", "created_at_i": 120, "parent_id": 1, "children": []}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let comments = thread.flatten_replies(&[3, 2]);
+        let order: Vec<(u32, u32)> = comments.iter().map(|vm| (vm.id, vm.depth)).collect();
+        assert_eq!(order, vec![(3, 0), (2, 0), (4, 1), (5, 2)]);
+
+        assert_eq!(comments[1].comment.kids, Some(vec![4]));
+        assert!(comments[2].comment.deleted);
+        assert_eq!(comments[3].comment.parent, Some(4));
+    }
+}
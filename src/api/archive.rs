@@ -0,0 +1,169 @@
+//! Archived copies of linked pages that can't be read directly.
+//!
+//! Dead links, error pages and paywall stubs can often still be read from a web
+//! archive. Archives are tried in the order of `network.archive_sources`. The
+//! Wayback Machine has an availability API naming its closest snapshot of a page,
+//! while archive.today redirects `newest/<url>` to its latest copy, if it has one.
+
+use super::{ApiError, ApiService, Article};
+use crate::config::ArchiveSource;
+use anyhow::Result;
+use jiff::Timestamp;
+use jiff::civil::DateTime;
+use jiff::tz::TimeZone;
+use reqwest::Url;
+use serde::Deserialize;
+
+pub const WAYBACK_BASE_URL: &str = "https://archive.org/";
+pub const ARCHIVE_TODAY_BASE_URL: &str = "https://archive.ph/";
+
+/// A copy of a page kept by a web archive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub source: ArchiveSource,
+    /// Where the copy can be viewed in a browser
+    pub url: String,
+    /// When the page was captured, if the archive says
+    pub captured: Option<Timestamp>,
+}
+
+/// An article read from a web archive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchivedArticle {
+    pub snapshot: Snapshot,
+    pub article: Article,
+}
+
+/// Response of the Wayback Machine availability API.
+#[derive(Debug, Deserialize)]
+struct Availability {
+    #[serde(default)]
+    archived_snapshots: ArchivedSnapshots,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ArchivedSnapshots {
+    closest: Option<ClosestSnapshot>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClosestSnapshot {
+    available: bool,
+    url: String,
+    /// Capture time as `YYYYMMDDhhmmss` in UTC
+    timestamp: String,
+    /// HTTP status the page was captured with
+    #[serde(default)]
+    status: String,
+}
+
+impl ApiService {
+    /// Use custom Wayback Machine and archive.today base URLs (for testing).
+    #[allow(dead_code)]
+    pub fn with_archive_base_urls(mut self, wayback: String, archive_today: String) -> Self {
+        self.wayback_base_url = Some(wayback);
+        self.archive_today_base_url = Some(archive_today);
+        self
+    }
+
+    fn get_wayback_base_url(&self) -> &str {
+        self.wayback_base_url.as_deref().unwrap_or(WAYBACK_BASE_URL)
+    }
+
+    fn get_archive_today_base_url(&self) -> &str {
+        self.archive_today_base_url
+            .as_deref()
+            .unwrap_or(ARCHIVE_TODAY_BASE_URL)
+    }
+
+    /// Read `url` from the first archive in `archive_sources` that has a copy of it.
+    /// Fails with `ApiError::NotFound` when none has one.
+    pub async fn fetch_archived_article(&self, url: &str) -> Result<ArchivedArticle> {
+        for &source in &self.network_config.archive_sources {
+            let found = match source {
+                ArchiveSource::Wayback => self.fetch_from_wayback(url).await,
+                ArchiveSource::ArchiveToday => self.fetch_from_archive_today(url).await,
+            };
+            match found {
+                Ok(Some(archived)) => return Ok(archived),
+                Ok(None) => tracing::debug!("{} has no copy of {}", source, url),
+                Err(e) => tracing::warn!("Failed to read {} from {}: {:#}", url, source, e),
+            }
+        }
+        Err(ApiError::NotFound(format!("an archived copy of {}", url)).into())
+    }
+
+    async fn fetch_from_wayback(&self, url: &str) -> Result<Option<ArchivedArticle>> {
+        let lookup = Url::parse_with_params(
+            &format!("{}wayback/available", self.get_wayback_base_url()),
+            [("url", url)],
+        )?;
+        let availability: Availability = self.get_json(lookup.as_str()).await?;
+        // Captures of error pages are no better than the original
+        let Some(closest) = availability
+            .archived_snapshots
+            .closest
+            .filter(|closest| closest.available && !closest.status.starts_with(['4', '5']))
+        else {
+            return Ok(None);
+        };
+
+        let view_url = match closest.url.strip_prefix("http://web.archive.org/") {
+            Some(path) => format!("https://web.archive.org/{}", path),
+            None => closest.url,
+        };
+        // `id_` after the timestamp asks for the page as captured, without the
+        // archive's toolbar and rewritten links
+        let raw_url = view_url.replacen(
+            &format!("/{}/", closest.timestamp),
+            &format!("/{}id_/", closest.timestamp),
+            1,
+        );
+        let article = self.fetch_article(&raw_url).await?;
+
+        Ok(Some(ArchivedArticle {
+            snapshot: Snapshot {
+                source: ArchiveSource::Wayback,
+                url: view_url,
+                captured: parse_wayback_timestamp(&closest.timestamp),
+            },
+            article,
+        }))
+    }
+
+    async fn fetch_from_archive_today(&self, url: &str) -> Result<Option<ArchivedArticle>> {
+        let newest = format!("{}newest/{}", self.get_archive_today_base_url(), url);
+        match self.fetch_article(&newest).await {
+            // The redirect leads to the snapshot's own URL
+            Ok(article) => Ok(Some(ArchivedArticle {
+                snapshot: Snapshot {
+                    source: ArchiveSource::ArchiveToday,
+                    url: article.url.clone(),
+                    captured: None,
+                },
+                article,
+            })),
+            Err(e) if ApiError::find(&e) == Some(&ApiError::Http { status: 404 }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+fn parse_wayback_timestamp(timestamp: &str) -> Option<Timestamp> {
+    let captured = DateTime::strptime("%Y%m%d%H%M%S", timestamp).ok()?;
+    Some(captured.to_zoned(TimeZone::UTC).ok()?.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wayback_timestamps_are_utc() {
+        assert_eq!(
+            parse_wayback_timestamp("20240305120000"),
+            Some("2024-03-05T12:00:00Z".parse().unwrap())
+        );
+        assert_eq!(parse_wayback_timestamp("2024"), None);
+    }
+}
@@ -0,0 +1,357 @@
+//! Fetching and decoding the pages stories link to.
+//!
+//! Articles come from arbitrary sites, so they go through their own client that
+//! follows a limited number of redirects and stops reading at `article_max_bytes`.
+//! The body is decoded with the charset named by a byte order mark, the
+//! `Content-Type` header or a `<meta>` tag, and its type is sniffed from the first
+//! bytes when the server doesn't say. PDFs are converted to Markdown as they are
+//! decoded, so the caches keep their text rather than the document.
+
+use super::scheduler::RequestPriority;
+use super::{ApiError, ApiService, RawDocument, revalidate};
+use crate::disk_cache::EntryKind;
+use crate::utils::pdf::extract_pdf;
+use anyhow::{Context, Result};
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use once_cell::sync::Lazy;
+use regex::bytes::Regex;
+use serde::{Deserialize, Serialize};
+
+/// How much of an HTML page is searched for a `<meta>` charset
+const META_CHARSET_SCAN_BYTES: usize = 1024;
+
+static META_CHARSET: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?\s*([a-z0-9_:.-]+)"#)
+        .expect("valid meta charset regex")
+});
+
+/// How an article body is meant to be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArticleKind {
+    Html,
+    /// Plain text, Markdown and other `text/*` content, shown as is
+    Text,
+    /// A PDF, whose `body` is the Markdown of its extracted text
+    Pdf,
+}
+
+impl ArticleKind {
+    /// Classify a response by its `Content-Type`, or by its first bytes when the
+    /// header is missing or generic. A `.pdf` `url` is trusted over a generic header,
+    /// as some file hosts send PDFs as `application/octet-stream`.
+    pub fn sniff(url: &str, content_type: Option<&str>, body: &[u8]) -> Result<Self, ApiError> {
+        let mime = content_type
+            .map(|ct| {
+                ct.split(';')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_ascii_lowercase()
+            })
+            .filter(|mime| !mime.is_empty() && mime != "application/octet-stream");
+
+        match mime.as_deref() {
+            Some("text/html" | "application/xhtml+xml") => Ok(ArticleKind::Html),
+            Some("application/pdf" | "application/x-pdf") => Ok(ArticleKind::Pdf),
+            Some(mime) if mime.starts_with("text/") => Ok(ArticleKind::Text),
+            Some(mime) => Err(ApiError::UnsupportedContent(mime.to_string())),
+            None if has_pdf_path(url) => Ok(ArticleKind::Pdf),
+            None => Self::sniff_body(body),
+        }
+    }
+
+    /// A simplified version of the WHATWG sniffing rules for unlabeled responses.
+    fn sniff_body(body: &[u8]) -> Result<Self, ApiError> {
+        let start = body
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(body.len());
+        let head = &body[start..body.len().min(start + 16)];
+
+        if head.starts_with(b"%PDF-") {
+            return Ok(ArticleKind::Pdf);
+        }
+
+        const HTML_TAGS: [&[u8]; 17] = [
+            b"<!doctype html",
+            b"<html",
+            b"<head",
+            b"<script",
+            b"<iframe",
+            b"<h1",
+            b"<div",
+            b"<font",
+            b"<table",
+            b"<a",
+            b"<style",
+            b"<title",
+            b"<b",
+            b"<body",
+            b"<br",
+            b"<p",
+            b"<!--",
+        ];
+        let lower = head.to_ascii_lowercase();
+        let is_html = HTML_TAGS.iter().any(|tag| {
+            lower.starts_with(tag)
+                && lower
+                    .get(tag.len())
+                    .is_none_or(|&b| b == b' ' || b == b'>' || *tag == b"<!--")
+        });
+        if is_html {
+            return Ok(ArticleKind::Html);
+        }
+
+        // Binary data has control bytes that text doesn't
+        let binary = body
+            .iter()
+            .take(512)
+            .any(|&b| b < 0x20 && !b.is_ascii_whitespace() && b != 0x1b);
+        match binary {
+            true => Err(ApiError::UnsupportedContent(
+                "application/octet-stream".to_string(),
+            )),
+            false => Ok(ArticleKind::Text),
+        }
+    }
+}
+
+/// Whether the path of `url` names a `.pdf` file.
+fn has_pdf_path(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    path.len() > 4
+        && path
+            .get(path.len() - 4..)
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(".pdf"))
+}
+
+/// A decoded article.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Article {
+    /// Where the page was found, after redirects
+    pub url: String,
+    pub kind: ArticleKind,
+    pub body: String,
+}
+
+impl Article {
+    /// Check the content type of `document` and decode its body.
+    pub fn decode(document: RawDocument) -> Result<Self, ApiError> {
+        let kind = ArticleKind::sniff(
+            &document.url,
+            document.content_type.as_deref(),
+            &document.body,
+        )?;
+        if kind == ArticleKind::Pdf {
+            return Self::decode_pdf(document);
+        }
+
+        let encoding = detect_encoding(document.content_type.as_deref(), kind, &document.body);
+        // `decode` also honours a byte order mark over `encoding`
+        let (body, used, had_errors) = encoding.decode(&document.body);
+        if had_errors {
+            tracing::debug!("{} isn't valid {}", document.url, used.name());
+        }
+
+        Ok(Self {
+            url: document.url,
+            kind,
+            body: body.into_owned(),
+        })
+    }
+
+    fn decode_pdf(document: RawDocument) -> Result<Self, ApiError> {
+        let pdf = extract_pdf(&document.body).map_err(|e| ApiError::Decode(format!("{:#}", e)))?;
+        if pdf.is_empty() {
+            return Err(ApiError::Decode(
+                "the PDF has no text, it may be scanned".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            url: document.url,
+            kind: ArticleKind::Pdf,
+            body: pdf.markdown(),
+        })
+    }
+}
+
+/// The charset from the `Content-Type` header or, for HTML, a `<meta>` tag near
+/// the top. Unlabeled bodies are read as UTF-8 if valid, else as Windows-1252.
+fn detect_encoding(
+    content_type: Option<&str>,
+    kind: ArticleKind,
+    body: &[u8],
+) -> &'static Encoding {
+    let from_header = content_type.and_then(|ct| {
+        ct.split(';')
+            .skip(1)
+            .filter_map(|param| param.trim().split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+            .and_then(|(_, label)| Encoding::for_label(label.trim().trim_matches('"').as_bytes()))
+    });
+    if let Some(encoding) = from_header {
+        return encoding;
+    }
+
+    if kind == ArticleKind::Html {
+        let head = &body[..body.len().min(META_CHARSET_SCAN_BYTES)];
+        if let Some(encoding) = META_CHARSET
+            .captures(head)
+            .and_then(|c| Encoding::for_label(&c[1]))
+        {
+            return encoding;
+        }
+    }
+
+    match std::str::from_utf8(body) {
+        Ok(_) => UTF_8,
+        Err(_) => WINDOWS_1252,
+    }
+}
+
+impl ApiService {
+    /// Fetch the page a story links to. Uses cache with 15 min TTL, then the disk cache.
+    pub async fn fetch_article(&self, url: &str) -> Result<Article> {
+        if let Some(cached) = self.article_cache.get(url) {
+            tracing::trace!("Cache hit for article {}", url);
+            return Ok(cached);
+        }
+
+        if let Some(article) = self.disk_lookup::<Article>(&format!("article_{}", url), || {
+            revalidate::Target::Article(url.to_string())
+        }) {
+            self.article_cache.insert(url.to_string(), article.clone());
+            return Ok(article);
+        }
+
+        self.refresh_article(url).await
+    }
+
+    /// Fetch an article, bypassing the caches, and store the result.
+    /// Shares the request queue and rate limiter with API requests.
+    pub async fn refresh_article(&self, url: &str) -> Result<Article> {
+        let max_bytes = self.network_config.article_max_bytes;
+        let document = self
+            .send_with(url, &RequestPriority::new(self.priority), || {
+                self.backend.get_document(url, max_bytes)
+            })
+            .await
+            .with_context(|| format!("GET {} failed", url))?;
+        let article =
+            Article::decode(document).with_context(|| format!("can't show article {}", url))?;
+
+        self.article_cache.insert(url.to_string(), article.clone());
+        if let Some(disk_cache) = &self.disk_cache
+            && let Ok(entry) = serde_json::to_string(&article)
+        {
+            disk_cache.insert(EntryKind::Article, &format!("article_{}", url), &entry);
+        }
+        Ok(article)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://example.com/";
+
+    fn document(content_type: Option<&str>, body: &[u8]) -> RawDocument {
+        RawDocument {
+            url: URL.to_string(),
+            content_type: content_type.map(str::to_string),
+            body: body.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_content_type_header_decides_kind() {
+        assert_eq!(
+            ArticleKind::sniff(URL, Some("text/html; charset=utf-8"), b""),
+            Ok(ArticleKind::Html)
+        );
+        assert_eq!(
+            ArticleKind::sniff(URL, Some("text/plain"), b"<html>"),
+            Ok(ArticleKind::Text)
+        );
+        assert_eq!(
+            ArticleKind::sniff(URL, Some("image/png"), b""),
+            Err(ApiError::UnsupportedContent("image/png".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_unlabeled_body_is_sniffed() {
+        assert_eq!(
+            ArticleKind::sniff(URL, None, b"\n  <!DOCTYPE html><html>"),
+            Ok(ArticleKind::Html)
+        );
+        assert_eq!(
+            ArticleKind::sniff(URL, Some("application/octet-stream"), b"<p>Hi</p>"),
+            Ok(ArticleKind::Html)
+        );
+        assert_eq!(
+            ArticleKind::sniff(URL, None, b"# Release notes"),
+            Ok(ArticleKind::Text)
+        );
+        assert_eq!(
+            ArticleKind::sniff(URL, None, b"%PDF-1.7\n"),
+            Ok(ArticleKind::Pdf)
+        );
+        assert!(ArticleKind::sniff(URL, None, b"\x89PNG\r\n\x1a\n\0\0").is_err());
+    }
+
+    #[test]
+    fn test_charset_from_header_meta_or_fallback() {
+        // "café" in Latin-1
+        let latin1 = b"caf\xe9";
+        let article = Article::decode(document(Some("text/plain; charset=ISO-8859-1"), latin1));
+        assert_eq!(article.unwrap().body, "café");
+
+        let html = b"<html><head><meta charset=\"windows-1252\"></head><body>caf\xe9</body></html>";
+        let article = Article::decode(document(Some("text/html"), html)).unwrap();
+        assert!(article.body.contains("café"));
+
+        let article = Article::decode(document(None, "café".as_bytes())).unwrap();
+        assert_eq!(article.body, "café");
+        let article = Article::decode(document(None, latin1)).unwrap();
+        assert_eq!(article.body, "café");
+    }
+
+    #[test]
+    fn test_pdfs_are_recognised_by_type_or_url() {
+        assert_eq!(
+            ArticleKind::sniff(URL, Some("application/pdf"), b""),
+            Ok(ArticleKind::Pdf)
+        );
+        assert_eq!(
+            ArticleKind::sniff(
+                "https://example.com/paper.PDF?download=1",
+                Some("application/octet-stream"),
+                b"\0\0"
+            ),
+            Ok(ArticleKind::Pdf)
+        );
+        assert_eq!(
+            ArticleKind::sniff("https://example.com/pdf", None, b"\0\0").ok(),
+            None
+        );
+        // The last four bytes fall inside a character
+        assert!(!has_pdf_path("https://example.com/論文"));
+        assert!(has_pdf_path("https://example.com/論文.pdf"));
+    }
+
+    #[test]
+    fn test_pdf_is_decoded_to_markdown() {
+        let pdf = include_bytes!("../../tests/fixtures/pdf/slides.pdf");
+        let article = Article::decode(document(Some("application/pdf"), pdf)).unwrap();
+        assert_eq!(article.kind, ArticleKind::Pdf);
+        assert!(article.body.starts_with("*Page 1 of 2*\n\nRust at Scale"));
+
+        assert!(matches!(
+            Article::decode(document(Some("application/pdf"), b"%PDF-1.4 truncated")),
+            Err(ApiError::Decode(_))
+        ));
+    }
+}
@@ -0,0 +1,472 @@
+//! Transports that `ApiService` fetches raw responses from.
+//!
+//! `ApiService` keeps caching, deduplication, retries and rate limiting; a backend
+//! only turns a URL into a body. Besides the live HTTP backend there is an in-memory
+//! fixture store and a record/replay backend, so views can run without the network.
+
+use super::{ApiError, HN_API_BASE_URL, StoryListType, tokio_handle};
+use dashmap::DashMap;
+use futures::future::{BoxFuture, FutureExt};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio_util::task::AbortOnDropHandle;
+
+/// A page fetched from outside the API, before decoding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawDocument {
+    /// Where the page was found, after redirects
+    pub url: String,
+    /// The `Content-Type` header, if any
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// Source of raw API responses.
+pub trait HnBackend: Send + Sync {
+    /// GET `url` and return the body of a successful response.
+    fn get(&self, url: &str) -> BoxFuture<'static, Result<String, ApiError>>;
+
+    /// GET a page outside the API, such as a linked article, failing once the body
+    /// grows past `max_bytes`. Offline backends serve it like any other response.
+    fn get_document(
+        &self,
+        url: &str,
+        max_bytes: usize,
+    ) -> BoxFuture<'static, Result<RawDocument, ApiError>> {
+        let request = self.get(url);
+        let url = url.to_string();
+        async move {
+            let body = request.await?.into_bytes();
+            if body.len() > max_bytes {
+                return Err(ApiError::TooLarge { limit: max_bytes });
+            }
+            Ok(RawDocument {
+                url,
+                content_type: None,
+                body,
+            })
+        }
+        .boxed()
+    }
+
+    /// Whether requests go over the network, and so need rate limiting.
+    /// Event streams are only opened for live backends.
+    fn is_live(&self) -> bool {
+        true
+    }
+}
+
+/// Path and query of `url`, which identify a response independently of the host.
+/// `https://hacker-news.firebaseio.com/v0/item/1.json` becomes `/v0/item/1.json`.
+pub fn fixture_key(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(url) => match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Fetches over HTTP with `reqwest`.
+pub struct LiveBackend {
+    client: Client,
+    /// Used for pages outside the API, which may redirect and compress
+    document_client: Client,
+}
+
+impl LiveBackend {
+    pub fn new(client: Client) -> Self {
+        Self {
+            document_client: client.clone(),
+            client,
+        }
+    }
+
+    /// Fetch pages outside the API with `client`.
+    pub fn with_document_client(mut self, client: Client) -> Self {
+        self.document_client = client;
+        self
+    }
+}
+
+impl HnBackend for LiveBackend {
+    fn get(&self, url: &str) -> BoxFuture<'static, Result<String, ApiError>> {
+        let client = self.client.clone();
+        let url = url.to_string();
+
+        // Enter tokio runtime context for reqwest. Dropping the handle aborts the
+        // request, so a cancelled caller doesn't leave it running.
+        let request = AbortOnDropHandle::new(tokio_handle().spawn(async move {
+            let resp = client
+                .get(&url)
+                .send()
+                .await
+                .map_err(|e| ApiError::from_reqwest(&e))?;
+
+            let status = resp.status();
+            if !status.is_success() {
+                return Err(ApiError::from_status(status, resp.headers()));
+            }
+
+            resp.text().await.map_err(|e| ApiError::from_reqwest(&e))
+        }));
+
+        async move { request.await.expect("Tokio task panicked") }.boxed()
+    }
+
+    fn get_document(
+        &self,
+        url: &str,
+        max_bytes: usize,
+    ) -> BoxFuture<'static, Result<RawDocument, ApiError>> {
+        let client = self.document_client.clone();
+        let url = url.to_string();
+
+        let request = AbortOnDropHandle::new(tokio_handle().spawn(async move {
+            let mut resp = client
+                .get(&url)
+                .send()
+                .await
+                .map_err(|e| ApiError::from_reqwest(&e))?;
+
+            let status = resp.status();
+            if !status.is_success() {
+                return Err(ApiError::from_status(status, resp.headers()));
+            }
+            // Refuse early when the size is announced; the body is checked as it arrives
+            if resp
+                .content_length()
+                .is_some_and(|len| len > max_bytes as u64)
+            {
+                return Err(ApiError::TooLarge { limit: max_bytes });
+            }
+
+            let url = resp.url().to_string();
+            let content_type = resp
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+
+            let mut body = Vec::new();
+            while let Some(chunk) = resp.chunk().await.map_err(|e| ApiError::from_reqwest(&e))? {
+                if body.len() + chunk.len() > max_bytes {
+                    return Err(ApiError::TooLarge { limit: max_bytes });
+                }
+                body.extend_from_slice(&chunk);
+            }
+
+            Ok(RawDocument {
+                url,
+                content_type,
+                body,
+            })
+        }));
+
+        async move { request.await.expect("Tokio task panicked") }.boxed()
+    }
+}
+
+/// Serves canned responses from memory. Unknown URLs answer 404.
+///
+/// API responses are keyed by their path below the API base URL, such as
+/// `item/1.json`, so the same fixtures serve a mirror configured with
+/// `api_base_url`. Any other URL is keyed by its [`fixture_key`].
+#[derive(Clone)]
+pub struct FixtureBackend {
+    responses: Arc<DashMap<String, String>>,
+    /// API base URL stripped from requests, ending in `/`
+    base_url: String,
+}
+
+impl Default for FixtureBackend {
+    fn default() -> Self {
+        Self {
+            responses: Arc::default(),
+            base_url: HN_API_BASE_URL.to_string(),
+        }
+    }
+}
+
+impl FixtureBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve API responses for a service whose `api_base_url` is `base_url`.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = format!("{}/", base_url.trim_end_matches('/'));
+        self
+    }
+
+    /// Serve `body` for `key`: a path below the API base such as `item/1.json`,
+    /// or the path and query of any other URL, e.g. `/api/v1/search?query=rust`.
+    pub fn insert(&self, key: impl Into<String>, body: impl Into<String>) {
+        self.responses.insert(key.into(), body.into());
+    }
+
+    /// Serve `item` from its `item/{id}.json` path.
+    pub fn with_item(self, item: serde_json::Value) -> Self {
+        let id = item["id"].as_u64().unwrap_or_default();
+        self.insert(format!("item/{}.json", id), item.to_string());
+        self
+    }
+
+    /// Serve `ids` as the given story list.
+    pub fn with_story_list(self, list_type: StoryListType, ids: &[u32]) -> Self {
+        self.insert(
+            format!("{}.json", list_type.as_api_str()),
+            serde_json::to_string(ids).unwrap_or_default(),
+        );
+        self
+    }
+
+    /// Serve `user` from its `user/{id}.json` path.
+    pub fn with_user(self, user: serde_json::Value) -> Self {
+        let id = user["id"].as_str().unwrap_or_default().to_string();
+        self.insert(format!("user/{}.json", id), user.to_string());
+        self
+    }
+
+    fn key(&self, url: &str) -> String {
+        match url.strip_prefix(&self.base_url) {
+            Some(path) => path.to_string(),
+            None => fixture_key(url),
+        }
+    }
+}
+
+impl HnBackend for FixtureBackend {
+    fn get(&self, url: &str) -> BoxFuture<'static, Result<String, ApiError>> {
+        let response = self
+            .responses
+            .get(&self.key(url))
+            .map(|body| body.clone())
+            .ok_or(ApiError::Http { status: 404 });
+
+        futures::future::ready(response).boxed()
+    }
+
+    fn is_live(&self) -> bool {
+        false
+    }
+}
+
+/// Records responses of another backend to a directory, or serves them back.
+///
+/// Each response is stored as one file named after its [`fixture_key`], so a
+/// recorded session can be inspected and edited by hand. Pages outside the API
+/// keep their raw bytes, with the final URL and content type in a `.meta` file
+/// next to them.
+pub struct ReplayBackend {
+    dir: PathBuf,
+    /// The backend being recorded; `None` when replaying
+    recording: Option<Arc<dyn HnBackend>>,
+}
+
+/// Where a recorded page was found and what it was, saved beside its body.
+#[derive(Debug, Serialize, Deserialize)]
+struct DocumentMeta {
+    url: String,
+    content_type: Option<String>,
+}
+
+impl ReplayBackend {
+    /// Fetch through `inner` and save every successful response under `dir`.
+    pub fn record(inner: Arc<dyn HnBackend>, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            recording: Some(inner),
+        }
+    }
+
+    /// Serve responses previously saved under `dir`. Anything not recorded answers 404.
+    pub fn replay(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            recording: None,
+        }
+    }
+
+    /// File for `url`: its key with every other byte than letters, digits, `.`
+    /// and `-` written as `_` and two hex digits, so distinct keys never share a file.
+    fn path_for(&self, url: &str) -> PathBuf {
+        let mut name = String::new();
+        for byte in fixture_key(url).trim_start_matches('/').bytes() {
+            match byte {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'-' => name.push(byte as char),
+                _ => name.push_str(&format!("_{:02X}", byte)),
+            }
+        }
+        self.dir.join(name)
+    }
+}
+
+/// Path of the file describing the recorded page at `path`.
+fn meta_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".meta");
+    PathBuf::from(name)
+}
+
+/// Run file operations on the runtime's blocking pool rather than the caller's executor.
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    tokio_handle()
+        .spawn_blocking(f)
+        .await
+        .expect("Tokio task panicked")
+}
+
+fn save(dir: &Path, path: &Path, body: &[u8]) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(path, body)
+}
+
+impl HnBackend for ReplayBackend {
+    fn get(&self, url: &str) -> BoxFuture<'static, Result<String, ApiError>> {
+        let path = self.path_for(url);
+
+        match &self.recording {
+            Some(inner) => {
+                let request = inner.get(url);
+                let dir = self.dir.clone();
+                async move {
+                    let body = request.await?;
+                    let saved = body.clone();
+                    if let Err(e) = blocking(move || save(&dir, &path, saved.as_bytes())).await {
+                        tracing::warn!("Failed to record response: {}", e);
+                    }
+                    Ok(body)
+                }
+                .boxed()
+            }
+            None => async move {
+                blocking(move || std::fs::read_to_string(&path))
+                    .await
+                    .map_err(|_| ApiError::Http { status: 404 })
+            }
+            .boxed(),
+        }
+    }
+
+    fn get_document(
+        &self,
+        url: &str,
+        max_bytes: usize,
+    ) -> BoxFuture<'static, Result<RawDocument, ApiError>> {
+        let path = self.path_for(url);
+
+        match &self.recording {
+            Some(inner) => {
+                let request = inner.get_document(url, max_bytes);
+                let dir = self.dir.clone();
+                async move {
+                    let document = request.await?;
+                    let meta = DocumentMeta {
+                        url: document.url.clone(),
+                        content_type: document.content_type.clone(),
+                    };
+                    let body = document.body.clone();
+                    let saved = blocking(move || {
+                        let meta = serde_json::to_vec(&meta).map_err(std::io::Error::other)?;
+                        save(&dir, &meta_path(&path), &meta)?;
+                        save(&dir, &path, &body)
+                    })
+                    .await;
+                    if let Err(e) = saved {
+                        tracing::warn!("Failed to record page: {}", e);
+                    }
+                    Ok(document)
+                }
+                .boxed()
+            }
+            None => {
+                let url = url.to_string();
+                async move {
+                    let (body, meta) = blocking(move || {
+                        let body = std::fs::read(&path)?;
+                        // Responses recorded through `get` have no description
+                        let meta = std::fs::read(meta_path(&path))
+                            .ok()
+                            .and_then(|meta| serde_json::from_slice::<DocumentMeta>(&meta).ok());
+                        Ok::<_, std::io::Error>((body, meta))
+                    })
+                    .await
+                    .map_err(|_| ApiError::Http { status: 404 })?;
+
+                    if body.len() > max_bytes {
+                        return Err(ApiError::TooLarge { limit: max_bytes });
+                    }
+                    let meta = meta.unwrap_or(DocumentMeta {
+                        url,
+                        content_type: None,
+                    });
+                    Ok(RawDocument {
+                        url: meta.url,
+                        content_type: meta.content_type,
+                        body,
+                    })
+                }
+                .boxed()
+            }
+        }
+    }
+
+    fn is_live(&self) -> bool {
+        self.recording.as_ref().is_some_and(|inner| inner.is_live())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixture_key_ignores_host() {
+        assert_eq!(
+            fixture_key("https://hacker-news.firebaseio.com/v0/item/1.json"),
+            "/v0/item/1.json"
+        );
+        assert_eq!(
+            fixture_key("http://127.0.0.1:1234/v0/item/1.json"),
+            "/v0/item/1.json"
+        );
+        assert_eq!(
+            fixture_key("https://hn.algolia.com/api/v1/search?query=rust&page=0"),
+            "/api/v1/search?query=rust&page=0"
+        );
+    }
+
+    #[test]
+    fn test_fixtures_follow_the_configured_base_url() {
+        let backend = FixtureBackend::new()
+            .with_base_url("http://127.0.0.1:8080/hn/v0")
+            .with_item(serde_json::json!({ "id": 1, "type": "story" }));
+        backend.insert("/api/v1/items/1", "{}");
+
+        let get = |url: &str| futures::executor::block_on(backend.get(url));
+        assert!(get("http://127.0.0.1:8080/hn/v0/item/1.json").is_ok());
+        assert!(get("https://hn.algolia.com/api/v1/items/1").is_ok());
+        assert_eq!(
+            get("https://hacker-news.firebaseio.com/v0/item/1.json"),
+            Err(ApiError::Http { status: 404 })
+        );
+    }
+
+    #[test]
+    fn test_replay_file_names_are_flat() {
+        let backend = ReplayBackend::replay("/tmp/hn");
+        assert_eq!(
+            backend.path_for("https://hn.algolia.com/api/v1/search?query=a b"),
+            PathBuf::from("/tmp/hn/api_2Fv1_2Fsearch_3Fquery_3Da_2520b")
+        );
+        assert_ne!(
+            backend.path_for("https://hn.algolia.com/api/v1/search?query=a_b"),
+            backend.path_for("https://hn.algolia.com/api/v1/search?query=a b")
+        );
+    }
+}
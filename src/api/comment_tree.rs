@@ -0,0 +1,225 @@
+//! Breadth-first fetching of comment trees.
+//!
+//! A thread is fetched one level at a time, with every comment of a level requested
+//! concurrently, so a tree of depth `n` takes `n` rounds of requests rather than one
+//! per comment. After each level the tree fetched so far is flattened into display
+//! order and handed out, so the first replies show while deeper ones still load.
+
+use super::ApiService;
+use crate::internal::models::{Comment, CommentViewModel};
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::HashMap;
+use tokio_util::sync::CancellationToken;
+
+/// The part of a comment tree fetched so far.
+#[derive(Debug, Clone)]
+pub struct CommentTree {
+    roots: Vec<u32>,
+    comments: HashMap<u32, Comment>,
+    /// Depth of the root comments; 0 for top-level comments of a story
+    root_depth: u32,
+    /// Deepest level whose replies are fetched
+    max_depth: u32,
+}
+
+impl CommentTree {
+    pub fn new(roots: Vec<u32>, root_depth: u32, max_depth: u32) -> Self {
+        Self {
+            roots,
+            comments: HashMap::new(),
+            root_depth,
+            max_depth,
+        }
+    }
+
+    pub fn insert(&mut self, comment: Comment) {
+        self.comments.insert(comment.id, comment);
+    }
+
+    /// Fetched comments in depth-first display order: each comment is followed by its
+    /// replies in the order of `kids`. Comments that are missing, either not fetched
+    /// yet or failed, are left out together with their replies.
+    pub fn flatten(&self) -> Vec<CommentViewModel> {
+        let mut flat = Vec::with_capacity(self.comments.len());
+        // Reversed, so the first id is popped first
+        let mut stack: Vec<(u32, u32)> = self
+            .roots
+            .iter()
+            .rev()
+            .map(|&id| (id, self.root_depth))
+            .collect();
+
+        while let Some((id, depth)) = stack.pop() {
+            let Some(comment) = self.comments.get(&id) else {
+                continue;
+            };
+            let kids = comment.kids.as_deref().unwrap_or_default();
+
+            flat.push(CommentViewModel {
+                id,
+                comment: comment.clone(),
+                depth,
+                collapsed: false,
+                loading: false,
+                replies_loaded: depth < self.max_depth || kids.is_empty(),
+            });
+
+            if depth < self.max_depth {
+                stack.extend(kids.iter().rev().map(|&kid| (kid, depth + 1)));
+            }
+        }
+
+        flat
+    }
+}
+
+impl ApiService {
+    /// Fetch the comments `ids` at `depth` and their replies down to `max_depth`,
+    /// one level at a time.
+    ///
+    /// Yields the flattened tree after every level, so the last item is the whole
+    /// tree. Ends early, without yielding a partial level, once `token` is cancelled.
+    pub fn stream_comment_tree(
+        &self,
+        ids: Vec<u32>,
+        depth: u32,
+        max_depth: u32,
+        token: CancellationToken,
+    ) -> impl Stream<Item = Vec<CommentViewModel>> + Send + 'static {
+        let tree = CommentTree::new(ids.clone(), depth, max_depth);
+
+        stream::unfold(
+            (self.clone(), tree, ids, depth),
+            move |(api, mut tree, level, level_depth)| {
+                let token = token.clone();
+                async move {
+                    if level.is_empty() || token.is_cancelled() {
+                        return None;
+                    }
+
+                    let start = std::time::Instant::now();
+                    let comments = api
+                        .fetch_comments_concurrent(level, Some(token.clone()))
+                        .await;
+                    if token.is_cancelled() {
+                        return None;
+                    }
+
+                    if api.enable_metrics {
+                        tracing::debug!(
+                            elapsed = ?start.elapsed(),
+                            depth = level_depth,
+                            count = comments.len(),
+                            "Fetched comment level"
+                        );
+                    }
+
+                    let next: Vec<u32> = match level_depth < max_depth {
+                        true => comments
+                            .iter()
+                            .flat_map(|c| c.kids.iter().flatten().copied())
+                            .collect(),
+                        false => Vec::new(),
+                    };
+                    for comment in comments {
+                        tree.insert(comment);
+                    }
+
+                    Some((tree.flatten(), (api, tree, next, level_depth + 1)))
+                }
+            },
+        )
+    }
+
+    /// Fetch a whole comment tree breadth-first; see [`ApiService::stream_comment_tree`].
+    pub async fn fetch_comment_tree(
+        &self,
+        ids: Vec<u32>,
+        depth: u32,
+        max_depth: u32,
+        token: CancellationToken,
+    ) -> Vec<CommentViewModel> {
+        let mut levels = Box::pin(self.stream_comment_tree(ids, depth, max_depth, token));
+        let mut tree = Vec::new();
+        while let Some(level) = levels.next().await {
+            tree = level;
+        }
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{FixtureBackend, NetworkConfig};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn comment(id: u32, kids: &[u32]) -> serde_json::Value {
+        json!({ "id": id, "type": "comment", "text": format!("c{}", id), "kids": kids })
+    }
+
+    /// 1 ─┬─ 3 ── 5 ── 7
+    ///    └─ 4
+    /// 2 ──── 6
+    fn service() -> ApiService {
+        let backend = FixtureBackend::new()
+            .with_item(comment(1, &[3, 4]))
+            .with_item(comment(2, &[6]))
+            .with_item(comment(3, &[5]))
+            .with_item(comment(4, &[]))
+            .with_item(comment(5, &[7]))
+            .with_item(comment(6, &[]))
+            .with_item(comment(7, &[]));
+        ApiService::with_backend(NetworkConfig::default(), Arc::new(backend))
+    }
+
+    fn ids_and_depths(comments: &[CommentViewModel]) -> Vec<(u32, u32)> {
+        comments.iter().map(|vm| (vm.id, vm.depth)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_levels_stream_in_display_order() {
+        let levels: Vec<_> = service()
+            .stream_comment_tree(vec![1, 2], 0, 3, CancellationToken::new())
+            .collect()
+            .await;
+
+        assert_eq!(levels.len(), 4);
+        assert_eq!(ids_and_depths(&levels[0]), vec![(1, 0), (2, 0)]);
+        assert_eq!(
+            ids_and_depths(&levels[1]),
+            vec![(1, 0), (3, 1), (4, 1), (2, 0), (6, 1)]
+        );
+        assert_eq!(
+            ids_and_depths(&levels[3]),
+            vec![(1, 0), (3, 1), (5, 2), (7, 3), (4, 1), (2, 0), (6, 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_max_depth_leaves_replies_unloaded() {
+        let tree = service()
+            .fetch_comment_tree(vec![1], 0, 1, CancellationToken::new())
+            .await;
+
+        assert_eq!(ids_and_depths(&tree), vec![(1, 0), (3, 1), (4, 1)]);
+        assert_eq!(tree[1].unloaded_replies(), 1);
+        assert_eq!(tree[2].unloaded_replies(), 0);
+
+        // Loading the cut-off subtree continues at the next depth
+        let subtree = service()
+            .fetch_comment_tree(vec![5], 2, 3, CancellationToken::new())
+            .await;
+        assert_eq!(ids_and_depths(&subtree), vec![(5, 2), (7, 3)]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_tree_yields_nothing() {
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let tree = service().fetch_comment_tree(vec![1, 2], 0, 3, token).await;
+        assert!(tree.is_empty());
+    }
+}
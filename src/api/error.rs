@@ -0,0 +1,173 @@
+//! Typed errors for Hacker News API requests.
+//!
+//! Public `ApiService` methods return `anyhow::Result`; the underlying `ApiError`
+//! stays in the error chain so callers can recover it with [`ApiError::find`].
+
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    /// The request never got a response (DNS, connection refused, reset, ...)
+    Network(String),
+    /// The request timed out
+    Timeout,
+    /// The server answered with a non-success status
+    Http { status: u16 },
+    /// The server answered 429; `retry_after` is its `Retry-After` hint
+    RateLimited { retry_after: Option<Duration> },
+    /// The body was not the JSON, or the PDF, we expected
+    Decode(String),
+    /// The request was cancelled by the caller
+    Cancelled,
+    /// The API answered `null`: the item or user doesn't exist
+    NotFound(String),
+    /// The body is larger than the `limit` in bytes we are willing to download
+    TooLarge { limit: usize },
+    /// The response is of a content type that can't be shown as text
+    UnsupportedContent(String),
+}
+
+impl ApiError {
+    /// Find the `ApiError` that caused `err`, if any.
+    pub fn find(err: &anyhow::Error) -> Option<&ApiError> {
+        err.chain().find_map(|e| e.downcast_ref::<ApiError>())
+    }
+
+    /// Classify a transport-level reqwest error.
+    pub(crate) fn from_reqwest(err: &reqwest::Error) -> Self {
+        match err.is_timeout() {
+            true => ApiError::Timeout,
+            false => ApiError::Network(err.to_string()),
+        }
+    }
+
+    /// Classify a non-success response.
+    pub(crate) fn from_status(status: StatusCode, headers: &HeaderMap) -> Self {
+        match status {
+            StatusCode::TOO_MANY_REQUESTS => ApiError::RateLimited {
+                retry_after: parse_retry_after(headers),
+            },
+            status => ApiError::Http {
+                status: status.as_u16(),
+            },
+        }
+    }
+
+    /// Whether the server could not be reached, as opposed to answering with an error.
+    pub fn is_offline(&self) -> bool {
+        matches!(self, ApiError::Network(_) | ApiError::Timeout)
+    }
+
+    /// Whether the request is worth repeating. Other 4xx responses will fail the same way.
+    pub fn is_retryable(&self, retry_on_timeout: bool) -> bool {
+        match self {
+            ApiError::Network(_) => true,
+            ApiError::Timeout => retry_on_timeout,
+            ApiError::Http { status } => *status >= 500,
+            ApiError::RateLimited { .. } => true,
+            ApiError::Decode(_)
+            | ApiError::Cancelled
+            | ApiError::NotFound(_)
+            | ApiError::TooLarge { .. }
+            | ApiError::UnsupportedContent(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Network(e) => write!(f, "network error: {}", e),
+            ApiError::Timeout => write!(f, "request timed out"),
+            ApiError::Http { status } => write!(f, "HTTP status {}", status),
+            ApiError::RateLimited {
+                retry_after: Some(after),
+            } => write!(f, "rate limited, retry after {}s", after.as_secs()),
+            ApiError::RateLimited { retry_after: None } => write!(f, "rate limited"),
+            ApiError::Decode(e) => write!(f, "invalid response: {}", e),
+            ApiError::Cancelled => write!(f, "request cancelled"),
+            ApiError::NotFound(what) => write!(f, "{} not found", what),
+            ApiError::TooLarge { limit } => {
+                write!(f, "response is larger than {} KiB", limit / 1024)
+            }
+            ApiError::UnsupportedContent(content_type) => {
+                write!(f, "unsupported content type {}", content_type)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Parse a `Retry-After` header given either as delay-seconds or as an HTTP date.
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let at = jiff::fmt::rfc2822::DateTimeParser::new()
+        .parse_timestamp(value)
+        .ok()?;
+    // A date in the past means "now"
+    Some(Duration::try_from(at.duration_since(jiff::Timestamp::now())).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(retry_after: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(retry_after).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_retry_after_seconds_and_dates() {
+        assert_eq!(
+            parse_retry_after(&headers("120")),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after(&headers("Wed, 21 Oct 2015 07:28:00 GMT")),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after(&headers("soon")), None);
+        assert_eq!(parse_retry_after(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_only_server_errors_and_rate_limits_are_retried() {
+        let none = HeaderMap::new();
+        let retryable = |status: u16| {
+            ApiError::from_status(StatusCode::from_u16(status).unwrap(), &none).is_retryable(true)
+        };
+
+        assert!(retryable(500));
+        assert!(retryable(503));
+        assert!(retryable(429));
+        assert!(!retryable(400));
+        assert!(!retryable(403));
+        assert!(!retryable(404));
+        assert!(!ApiError::Timeout.is_retryable(false));
+    }
+
+    #[test]
+    fn test_find_through_context() {
+        let err = anyhow::Error::new(ApiError::NotFound("item 1".to_string()))
+            .context("refresh_item failed")
+            .context("fetch_story_content failed");
+
+        assert_eq!(
+            ApiError::find(&err),
+            Some(&ApiError::NotFound("item 1".to_string()))
+        );
+        assert!(!ApiError::find(&err).unwrap().is_offline());
+    }
+}
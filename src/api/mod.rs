@@ -1,5 +1,6 @@
-use crate::cache::Cache;
-use crate::internal::models::{Comment, Story};
+use crate::cache::{Cache, CacheStats};
+use crate::disk_cache::{DiskCache, EntryKind};
+use crate::internal::models::{Comment, Item, PollOpt, Story, User};
 use anyhow::{Context, Result};
 use dashmap::DashMap;
 use futures::future::{BoxFuture, FutureExt, Shared};
@@ -7,13 +8,42 @@ use futures::stream::{self, StreamExt};
 use once_cell::sync::OnceCell;
 use reqwest::Client;
 use serde::de::DeserializeOwned;
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 use strum_macros::Display;
 use tokio::runtime::Handle;
-use tokio::sync::Semaphore;
 use tokio_util::sync::CancellationToken;
 
+pub mod algolia;
+pub mod archive;
+pub mod article;
+pub mod backend;
+pub mod comment_tree;
+pub mod error;
+pub mod prefetch;
+pub mod rate_limit;
+pub mod revalidate;
+pub mod scheduler;
+pub mod sse;
+pub mod updates;
+
+use algolia::HN_ALGOLIA_BASE_URL;
+pub use algolia::{SearchQuery, SearchResults, SearchSort, SearchTag};
+pub use archive::{ArchivedArticle, Snapshot};
+pub use article::{Article, ArticleKind};
+pub use backend::{FixtureBackend, HnBackend, LiveBackend, RawDocument, ReplayBackend};
+pub use comment_tree::CommentTree;
+pub use error::ApiError;
+pub use prefetch::{PrefetchStats, Prefetcher};
+pub use rate_limit::RateLimiter;
+pub use revalidate::Revalidated;
+use revalidate::RevalidatedSenders;
+use scheduler::RequestPriority;
+pub use scheduler::{Priority, QueueDepths, Scheduler};
+pub use updates::ChangeSet;
+
 // Global tokio runtime handle for use in GPUI tasks
 static TOKIO_HANDLE: OnceCell<Handle> = OnceCell::new();
 
@@ -44,20 +74,14 @@ fn tokio_handle() -> &'static Handle {
         .expect("Tokio runtime handle not initialized")
 }
 
-/// Helper function to make an HTTP GET request in tokio context
-/// This should be used by other modules that need to make HTTP requests
-pub async fn http_get(url: &str) -> Result<String> {
-    let client = Client::new();
-    let url = url.to_string();
-    let result = tokio_handle()
-        .spawn(async move {
-            let resp = client.get(&url).send().await?;
-            resp.text().await
-        })
-        .await
-        .expect("Tokio task panicked");
-
-    result.map_err(anyhow::Error::new)
+/// Sleep on the tokio timer, even when polled from a non-tokio executor such as GPUI's.
+pub(crate) async fn sleep(duration: Duration) {
+    match Handle::try_current() {
+        Ok(_) => tokio::time::sleep(duration).await,
+        Err(_) => {
+            let _ = tokio_handle().spawn(tokio::time::sleep(duration)).await;
+        }
+    }
 }
 
 /// Types of Hacker News story lists we can fetch.
@@ -86,9 +110,102 @@ impl StoryListType {
 
 const HN_API_BASE_URL: &str = "https://hacker-news.firebaseio.com/v0/";
 
+/// How often pending disk cache changes are written out
+const DISK_CACHE_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often expired entries are dropped from the in-memory caches
+const CACHE_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long expired items and profiles are kept past their TTL, to be served
+/// stale when a refresh fails
+const STALE_GRACE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Most parents followed up from a comment to its story. Real threads are far
+/// shallower; this stops a bad or looping `parent` chain.
+const MAX_THREAD_HOPS: usize = 1000;
+
+/// Sizes and counters of the in-memory caches, see [`ApiService::cache_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ApiCacheStats {
+    pub story_ids: CacheStats,
+    pub items: CacheStats,
+    pub users: CacheStats,
+    pub articles: CacheStats,
+}
+
+impl ApiCacheStats {
+    /// Each cache with a display name.
+    pub fn named(&self) -> [(&'static str, CacheStats); 4] {
+        [
+            ("Story lists", self.story_ids),
+            ("Items", self.items),
+            ("Users", self.users),
+            ("Articles", self.articles),
+        ]
+    }
+}
+
+/// A request that several callers can await together
+type SharedFetch = Shared<BoxFuture<'static, Result<Arc<String>, ApiError>>>;
+
+/// A deduplicated request and the priority it is queued at
+struct InflightRequest {
+    future: SharedFetch,
+    /// Raised to the most urgent priority among its callers
+    priority: RequestPriority,
+    /// Callers awaiting the request. Only changed under the map's lock on the entry,
+    /// so no caller can join between the last one leaving and the entry's removal.
+    waiters: AtomicUsize,
+}
+
+impl InflightRequest {
+    /// Count a caller out, returning how many still wait.
+    fn leave(&self) -> usize {
+        self.waiters.fetch_sub(1, Ordering::AcqRel) - 1
+    }
+}
+
 /// Type alias for in-flight request tracking map
-type InflightRequestMap =
-    Arc<DashMap<String, Shared<BoxFuture<'static, Result<Arc<String>, String>>>>>;
+type InflightRequestMap = Arc<DashMap<String, InflightRequest>>;
+
+/// One caller's claim on a shared in-flight request.
+///
+/// On drop, the map entry is removed once the request has finished or when this was
+/// its last waiter. A request abandoned by every caller is then dropped, which aborts
+/// the HTTP exchange, instead of lingering in the map for later callers to join.
+struct InflightGuard<'a> {
+    map: &'a InflightRequestMap,
+    url: &'a str,
+    /// Identifies the map entry; never polled, since a `Shared` that completes
+    /// lets go of its state
+    entry: SharedFetch,
+    /// The handle this caller awaits
+    waiting: SharedFetch,
+}
+
+impl InflightGuard<'_> {
+    fn new<'a>(
+        map: &'a InflightRequestMap,
+        url: &'a str,
+        future: SharedFetch,
+    ) -> InflightGuard<'a> {
+        InflightGuard {
+            map,
+            url,
+            entry: future.clone(),
+            waiting: future,
+        }
+    }
+}
+
+impl Drop for InflightGuard<'_> {
+    fn drop(&mut self) {
+        let finished = self.entry.peek().is_some();
+        self.map.remove_if(self.url, |_, request| {
+            request.future.ptr_eq(&self.entry) && (request.leave() == 0 || finished)
+        });
+    }
+}
 
 #[cfg(test)]
 pub fn hn_item_url(id: u32) -> String {
@@ -100,51 +217,88 @@ pub fn get_story_list_url(list_type: StoryListType) -> String {
     format!("{}{}.json", HN_API_BASE_URL, list_type.as_api_str())
 }
 
-/// Network configuration for API requests
-#[derive(Debug, Clone)]
-pub struct NetworkConfig {
-    /// Maximum number of retry attempts (0 = no retries)
-    pub max_retries: u32,
-    /// Initial retry delay in milliseconds
-    pub initial_retry_delay_ms: u64,
-    /// Maximum retry delay in milliseconds (caps exponential backoff)
-    pub max_retry_delay_ms: u64,
-    /// Whether to retry on timeout errors
-    pub retry_on_timeout: bool,
-    /// Maximum number of concurrent requests
-    pub concurrent_requests: usize,
-    /// Rate limit in requests per second
-    pub rate_limit_per_second: f64,
+pub use crate::config::{ArchiveSource, CommentSource, NetworkConfig};
+
+/// Build an HTTP client from the timeout, proxy and User-Agent settings.
+///
+/// Long-lived event streams pass `streaming` to skip the whole-request timeout.
+/// An unusable proxy is logged and ignored rather than failing startup.
+fn build_client(config: &NetworkConfig, streaming: bool) -> Client {
+    finish_client(client_builder(config, streaming))
 }
 
-impl Default for NetworkConfig {
-    fn default() -> Self {
-        Self {
-            max_retries: 3,
-            initial_retry_delay_ms: 500,
-            max_retry_delay_ms: 5000,
-            retry_on_timeout: true,
-            concurrent_requests: 10,
-            rate_limit_per_second: 3.0,
+/// Build the client for linked articles, which follows at most
+/// `article_max_redirects` redirects. Compressed bodies are decoded by reqwest.
+fn build_article_client(config: &NetworkConfig) -> Client {
+    let policy = match config.article_max_redirects {
+        0 => reqwest::redirect::Policy::none(),
+        max => reqwest::redirect::Policy::limited(max),
+    };
+    finish_client(client_builder(config, false).redirect(policy))
+}
+
+fn client_builder(config: &NetworkConfig, streaming: bool) -> reqwest::ClientBuilder {
+    let mut builder = Client::builder().user_agent(config.user_agent.clone());
+
+    if config.connect_timeout_secs > 0 {
+        builder = builder.connect_timeout(Duration::from_secs(config.connect_timeout_secs));
+    }
+    if config.request_timeout_secs > 0 && !streaming {
+        builder = builder.timeout(Duration::from_secs(config.request_timeout_secs));
+    }
+    if let Some(proxy) = config.proxy.as_deref().filter(|p| !p.is_empty()) {
+        match reqwest::Proxy::all(proxy) {
+            Ok(proxy) => builder = builder.proxy(proxy),
+            Err(e) => tracing::error!("Ignoring invalid proxy {:?}: {}", proxy, e),
         }
     }
+
+    builder
+}
+
+fn finish_client(builder: reqwest::ClientBuilder) -> Client {
+    builder.build().unwrap_or_else(|e| {
+        tracing::error!("Failed to build HTTP client, using defaults: {}", e);
+        Client::new()
+    })
+}
+
+/// Deserialize a JSON response `body` fetched from `url`.
+fn parse_json<T: DeserializeOwned>(url: &str, body: &str) -> Result<T> {
+    serde_json::from_str::<T>(body)
+        .map_err(|e| ApiError::Decode(e.to_string()))
+        .with_context(|| format!("failed to parse JSON response from {}", url))
 }
 
 /// HTTP API service for fetching Hacker News data with caching, rate limiting,
 /// request deduplication, and exponential backoff retries.
 ///
-/// This service uses async `reqwest::Client` with in-memory caching (TTL-based),
-/// semaphore-based rate limiting, and request deduplication to prevent duplicate
-/// in-flight requests.
+/// Responses come from an [`HnBackend`] (live HTTP unless configured otherwise),
+/// with in-memory caching (TTL-based), token-bucket rate limiting, and request
+/// deduplication to prevent duplicate in-flight requests. Requests queue by
+/// [`Priority`]; use [`ApiService::at_priority`] for work the user didn't ask for.
 #[derive(Clone)]
 pub struct ApiService {
-    client: Client,
+    backend: Arc<dyn HnBackend>,
+    stream_client: Client,
     story_ids_cache: Cache<Vec<u32>>,
-    story_cache: Cache<Story>,
-    comment_cache: Cache<Comment>,
-    rate_limiter: Arc<Semaphore>,
+    item_cache: Cache<Item>,
+    user_cache: Cache<User>,
+    /// Linked article pages, keyed by URL
+    article_cache: Cache<Article>,
+    /// Responses kept between launches, consulted when the memory caches miss
+    disk_cache: Option<DiskCache>,
+    /// Subscribers to data refreshed after a stale disk cache hit
+    revalidated: RevalidatedSenders,
+    /// Caps requests in flight at `concurrent_requests` and hands out slots by priority
+    scheduler: Scheduler,
+    /// Priority of requests made through this handle
+    priority: Priority,
     network_config: NetworkConfig,
     base_url: Option<String>,
+    algolia_base_url: Option<String>,
+    wayback_base_url: Option<String>,
+    archive_today_base_url: Option<String>,
     // Request deduplication map
     inflight_requests: InflightRequestMap,
     enable_metrics: bool,
@@ -157,42 +311,96 @@ impl ApiService {
     }
 
     /// Create a new `ApiService` with custom network configuration.
+    ///
+    /// With `replay_dir` set, responses are replayed from that directory, or recorded
+    /// there when `record_responses` is also set.
     pub fn with_config(network_config: NetworkConfig) -> Self {
-        let permits = network_config.rate_limit_per_second.ceil() as usize;
-        let rate_limiter = Arc::new(Semaphore::new(permits));
-        let client = Client::new();
+        let live: Arc<dyn HnBackend> = Arc::new(
+            LiveBackend::new(build_client(&network_config, false))
+                .with_document_client(build_article_client(&network_config)),
+        );
 
-        Self {
-            client,
-            story_ids_cache: Cache::new(300), // 5 min TTL for story lists
-            story_cache: Cache::new(300),     // 5 min TTL for stories
-            comment_cache: Cache::new(300),   // 5 min TTL for comments
-            rate_limiter,
+        let backend: Arc<dyn HnBackend> = match network_config.replay_dir.as_deref() {
+            Some(dir) if network_config.record_responses => {
+                tracing::info!("Recording API responses to {}", dir);
+                Arc::new(ReplayBackend::record(live, dir))
+            }
+            Some(dir) => {
+                tracing::info!("Replaying API responses from {}", dir);
+                Arc::new(ReplayBackend::replay(dir))
+            }
+            None => live,
+        };
+
+        Self::with_backend(network_config, backend)
+    }
+
+    /// Create a service that gets its responses from `backend`, e.g. a
+    /// [`FixtureBackend`] for running without the network.
+    pub fn with_backend(network_config: NetworkConfig, backend: Arc<dyn HnBackend>) -> Self {
+        let rate_limiter = RateLimiter::new(
+            network_config.rate_limit_per_second,
+            network_config.rate_limit_burst,
+        );
+        // Endpoint paths are appended directly to the base URL
+        let base_url = network_config
+            .api_base_url
+            .as_deref()
+            .filter(|url| !url.is_empty())
+            .map(|url| format!("{}/", url.trim_end_matches('/')));
+
+        let service = Self {
+            backend,
+            stream_client: build_client(&network_config, true),
+            story_ids_cache: Cache::with_capacity(300, 16), // 5 min TTL for story lists
+            item_cache: Cache::with_capacity(300, 5000),    // 5 min TTL for stories and comments
+            user_cache: Cache::with_capacity(300, 200),     // 5 min TTL for user profiles
+            article_cache: Cache::with_capacity(900, 50),   // 15 min TTL for linked articles
+            disk_cache: None,
+            revalidated: RevalidatedSenders::default(),
+            scheduler: Scheduler::new(network_config.concurrent_requests, rate_limiter),
+            priority: Priority::Interactive,
             network_config,
-            base_url: None,
+            base_url,
+            algolia_base_url: None,
+            wayback_base_url: None,
+            archive_today_base_url: None,
             inflight_requests: Arc::new(DashMap::new()),
             enable_metrics: false,
-        }
+        };
+
+        let handle = tokio_handle();
+        handle.spawn(
+            service
+                .story_ids_cache
+                .sweep_periodically(CACHE_SWEEP_INTERVAL, Duration::ZERO),
+        );
+        handle.spawn(
+            service
+                .item_cache
+                .sweep_periodically(CACHE_SWEEP_INTERVAL, STALE_GRACE),
+        );
+        handle.spawn(
+            service
+                .user_cache
+                .sweep_periodically(CACHE_SWEEP_INTERVAL, STALE_GRACE),
+        );
+        handle.spawn(
+            service
+                .article_cache
+                .sweep_periodically(CACHE_SWEEP_INTERVAL, Duration::ZERO),
+        );
+
+        service
     }
 
     /// Create a service with a custom base URL (for testing).
     #[allow(dead_code)]
     pub fn with_base_url(base_url: String) -> Self {
-        let network_config = NetworkConfig::default();
-        let permits = network_config.rate_limit_per_second.ceil() as usize;
-        let client = Client::new();
-
-        Self {
-            client,
-            story_ids_cache: Cache::new(300),
-            story_cache: Cache::new(300),
-            comment_cache: Cache::new(300),
-            rate_limiter: Arc::new(Semaphore::new(permits)),
-            network_config,
-            base_url: Some(base_url),
-            inflight_requests: Arc::new(DashMap::new()),
-            enable_metrics: false,
-        }
+        Self::with_config(NetworkConfig {
+            api_base_url: Some(base_url),
+            ..NetworkConfig::default()
+        })
     }
 
     /// Enable performance metrics logging.
@@ -202,13 +410,124 @@ impl ApiService {
         self
     }
 
+    /// Keep responses in `disk_cache` between launches. Stale entries are served
+    /// at once and refreshed in the background; see [`ApiService::subscribe_revalidated`].
+    pub fn with_disk_cache(mut self, disk_cache: DiskCache) -> Self {
+        tokio_handle().spawn(disk_cache.flush_periodically(DISK_CACHE_FLUSH_INTERVAL));
+        self.disk_cache = Some(disk_cache);
+        self
+    }
+
+    /// Write pending disk cache changes now, e.g. before the app exits.
+    pub fn flush_disk_cache(&self) {
+        if let Some(disk_cache) = &self.disk_cache
+            && let Err(e) = disk_cache.flush()
+        {
+            tracing::warn!("Failed to save disk cache: {:#}", e);
+        }
+    }
+
+    /// Sizes and hit, miss, stale hit and eviction counts of the in-memory caches.
+    pub fn cache_stats(&self) -> ApiCacheStats {
+        ApiCacheStats {
+            story_ids: self.story_ids_cache.stats(),
+            items: self.item_cache.stats(),
+            users: self.user_cache.stats(),
+            articles: self.article_cache.stats(),
+        }
+    }
+
+    /// Empty the memory caches and the disk cache.
+    pub fn clear_cache(&self) -> Result<()> {
+        self.story_ids_cache.clear();
+        self.item_cache.clear();
+        self.user_cache.clear();
+        self.article_cache.clear();
+        match &self.disk_cache {
+            Some(disk_cache) => disk_cache.clear().context("failed to clear disk cache"),
+            None => Ok(()),
+        }
+    }
+
+    /// A handle sharing this service's caches and queue whose requests are made at
+    /// `priority`. Requests default to `Priority::Interactive`.
+    pub fn at_priority(&self, priority: Priority) -> Self {
+        Self {
+            priority,
+            ..self.clone()
+        }
+    }
+
+    /// Number of queued and running requests per priority.
+    pub fn queue_depths(&self) -> QueueDepths {
+        self.scheduler.depths()
+    }
+
+    /// Use a custom Algolia search base URL (for testing).
+    #[allow(dead_code)]
+    pub fn with_algolia_base_url(mut self, algolia_base_url: String) -> Self {
+        self.algolia_base_url = Some(algolia_base_url);
+        self
+    }
+
     fn get_base_url(&self) -> &str {
         self.base_url.as_deref().unwrap_or(HN_API_BASE_URL)
     }
 
+    fn get_algolia_base_url(&self) -> &str {
+        self.algolia_base_url
+            .as_deref()
+            .unwrap_or(HN_ALGOLIA_BASE_URL)
+    }
+
+    /// Queue for a request slot at `priority`, then send one GET through the backend.
+    /// A pre-empted request goes back into the queue and is sent again. Offline
+    /// backends are not throttled.
+    async fn send(&self, url: &str, priority: &RequestPriority) -> Result<String, ApiError> {
+        self.send_with(url, priority, || self.backend.get(url))
+            .await
+    }
+
+    /// Like [`ApiService::send`], with `request` making the backend call.
+    async fn send_with<T, F>(
+        &self,
+        url: &str,
+        priority: &RequestPriority,
+        request: impl Fn() -> F,
+    ) -> Result<T, ApiError>
+    where
+        F: Future<Output = Result<T, ApiError>>,
+    {
+        if !self.backend.is_live() {
+            return request().await;
+        }
+
+        loop {
+            let slot = self.scheduler.acquire(priority).await;
+            if self.enable_metrics {
+                tracing::debug!(
+                    url = %url,
+                    priority = ?priority.get(),
+                    depths = ?self.scheduler.depths(),
+                    "Sending request"
+                );
+            }
+
+            match slot.preempted().run_until_cancelled(request()).await {
+                Some(result) => return result,
+                None => tracing::debug!(url = %url, "Request pre-empted, queueing again"),
+            }
+        }
+    }
+
     /// Fetch raw text from URL with retries and exponential backoff.
+    /// Retries transport errors, 5xx and 429 responses; other 4xx responses fail at once.
     #[tracing::instrument(skip(self), fields(url = %url))]
-    async fn fetch_raw(&self, url: String) -> Result<Arc<String>> {
+    async fn fetch_raw(
+        &self,
+        url: String,
+        priority: RequestPriority,
+    ) -> Result<Arc<String>, ApiError> {
         let start = std::time::Instant::now();
         let mut attempt = 0;
         let mut delay = self.network_config.initial_retry_delay_ms;
@@ -216,28 +535,11 @@ impl ApiService {
         loop {
             attempt += 1;
 
-            // Acquire rate limiting permit
-            let _permit = self
-                .rate_limiter
-                .acquire()
-                .await
-                .expect("Semaphore should never be closed");
-
-            // Enter tokio runtime context for reqwest
-            let client = self.client.clone();
-            let url_clone = url.clone();
-            let resp_result = tokio_handle()
-                .spawn(async move { client.get(&url_clone).send().await })
-                .await
-                .expect("Tokio task panicked");
-
-            match resp_result {
-                Ok(resp) => {
-                    let text = resp
-                        .text()
-                        .await
-                        .with_context(|| format!("failed to get response text from {}", url))?;
+            // The slot is given back before backing off
+            let result = self.send(&url, &priority).await;
 
+            let err = match result {
+                Ok(text) => {
                     if self.enable_metrics {
                         tracing::debug!(
                             elapsed = ?start.elapsed(),
@@ -248,100 +550,110 @@ impl ApiService {
                     }
                     return Ok(Arc::new(text));
                 }
-                Err(e) => {
-                    let is_timeout = e.is_timeout();
-                    let is_connect = e.is_connect();
-                    let should_retry =
-                        (is_timeout && self.network_config.retry_on_timeout) || is_connect;
-
-                    if !should_retry || attempt > self.network_config.max_retries {
-                        if self.enable_metrics {
-                            tracing::debug!(
-                                elapsed = ?start.elapsed(),
-                                url = %url,
-                                attempt = attempt,
-                                error = %e,
-                                "GET failed (final)"
-                            );
-                        }
-                        return Err(anyhow::Error::new(e))
-                            .with_context(|| format!("failed to send GET request to {}", url));
+                Err(e) => e,
+            };
+
+            // The server's Retry-After hint replaces our own backoff, unless it asks
+            // for a longer wait than we are willing to block for
+            let wait_ms = match &err {
+                ApiError::RateLimited {
+                    retry_after: Some(after),
+                } => Some(after.as_millis() as u64)
+                    .filter(|ms| *ms <= self.network_config.max_retry_delay_ms),
+                _ => Some(delay),
+            };
+
+            let should_retry = err.is_retryable(self.network_config.retry_on_timeout);
+            let wait_ms = match wait_ms {
+                Some(ms) if should_retry && attempt <= self.network_config.max_retries => ms,
+                _ => {
+                    if self.enable_metrics {
+                        tracing::debug!(
+                            elapsed = ?start.elapsed(),
+                            url = %url,
+                            attempt = attempt,
+                            error = %err,
+                            "GET failed (final)"
+                        );
                     }
-
-                    tracing::warn!(
-                        "Request to {} failed (attempt {}/{}): {}. Retrying in {}ms...",
-                        url,
-                        attempt,
-                        self.network_config.max_retries + 1,
-                        e,
-                        delay
-                    );
-
-                    tokio::time::sleep(Duration::from_millis(delay)).await;
-                    delay = (delay * 2).min(self.network_config.max_retry_delay_ms);
+                    return Err(err);
                 }
-            }
+            };
+
+            tracing::warn!(
+                "Request to {} failed (attempt {}/{}): {}. Retrying in {}ms...",
+                url,
+                attempt,
+                self.network_config.max_retries + 1,
+                err,
+                wait_ms
+            );
+
+            sleep(Duration::from_millis(wait_ms)).await;
+            delay = (delay * 2).min(self.network_config.max_retry_delay_ms);
         }
     }
 
     /// Generic helper to GET a URL and deserialize the JSON body into `T`.
     /// Uses request deduplication to prevent duplicate in-flight requests.
-    #[tracing::instrument(skip(self), fields(url = %url))]
     async fn get_json<T>(&self, url: &str) -> Result<T>
     where
         T: DeserializeOwned,
     {
-        // Request deduplication logic
-        let future = {
-            if let Some(future) = self.inflight_requests.get(url) {
+        let body = self.get_body(url).await?;
+        parse_json(url, &body)
+    }
+
+    /// GET a URL and return the raw body, joining an identical request in flight.
+    #[tracing::instrument(skip(self), fields(url = %url))]
+    async fn get_body(&self, url: &str) -> Result<Arc<String>> {
+        // Request deduplication logic. Joining a queued request at a higher
+        // priority moves it forward.
+        let future = self
+            .inflight_requests
+            .entry(url.to_string())
+            .and_modify(|request| {
                 if self.enable_metrics {
                     tracing::debug!(url = %url, "Deduplicated request joined");
                 }
-                future.clone()
-            } else {
+                request.waiters.fetch_add(1, Ordering::AcqRel);
+                if request.priority.raise(self.priority) {
+                    self.scheduler.reprioritized();
+                }
+            })
+            .or_insert_with(|| {
                 let url_owned = url.to_string();
                 let self_clone = self.clone();
-
-                let future = async move {
-                    self_clone
-                        .fetch_raw(url_owned)
-                        .await
-                        .map_err(|e| e.to_string())
+                let priority = RequestPriority::new(self.priority);
+                let queued_at = priority.clone();
+                InflightRequest {
+                    future: async move { self_clone.fetch_raw(url_owned, queued_at).await }
+                        .boxed()
+                        .shared(),
+                    priority,
+                    waiters: AtomicUsize::new(1),
                 }
-                .boxed()
-                .shared();
-
-                self.inflight_requests
-                    .insert(url.to_string(), future.clone());
-                future
-            }
-        };
-
-        // Wait for the request to complete
-        let result = future.await;
+            })
+            .future
+            .clone();
 
-        // Clean up the map entry after request completes
-        self.inflight_requests.remove(url);
+        // Wait for the request to complete. The guard cleans up the map entry, even
+        // if this caller is cancelled mid-request.
+        let mut guard = InflightGuard::new(&self.inflight_requests, url, future);
+        let result = (&mut guard.waiting).await;
+        drop(guard);
 
-        match result {
-            Ok(body) => {
-                let parsed = serde_json::from_str::<T>(&body)
-                    .with_context(|| format!("failed to parse JSON response from {}", url))?;
-                Ok(parsed)
-            }
-            Err(e_str) => Err(anyhow::anyhow!(e_str)),
-        }
+        result.with_context(|| format!("GET {} failed", url))
     }
 
     /// Fetch a list of story IDs for the given list type (e.g., top, new).
-    /// Uses cache with 5 min TTL. Supports cancellation.
+    /// Uses cache with 5 min TTL, then the disk cache. Supports cancellation.
     #[tracing::instrument(skip(self, token), fields(list_type = ?list_type))]
     pub async fn fetch_story_ids(
         &self,
         list_type: StoryListType,
         token: Option<CancellationToken>,
     ) -> Result<Vec<u32>> {
-        let start = std::time::Instant::now();
         let cache_key = format!("story_ids_{}", list_type.as_api_str());
 
         // Check cache first
@@ -349,32 +661,56 @@ impl ApiService {
             return Ok(cached);
         }
 
+        if let Some(ids) =
+            self.disk_lookup::<Vec<u32>>(&cache_key, || revalidate::Target::StoryIds(list_type))
+        {
+            self.story_ids_cache.insert(cache_key, ids.clone());
+            return Ok(ids);
+        }
+
+        self.refresh_story_ids(list_type, token).await
+    }
+
+    /// Fetch a list of story IDs from the API, bypassing the caches, and store the result.
+    #[tracing::instrument(skip(self, token), fields(list_type = ?list_type))]
+    pub async fn refresh_story_ids(
+        &self,
+        list_type: StoryListType,
+        token: Option<CancellationToken>,
+    ) -> Result<Vec<u32>> {
+        let start = std::time::Instant::now();
+        let cache_key = format!("story_ids_{}", list_type.as_api_str());
+
         // Check cancellation before request
         if let Some(token) = &token
             && token.is_cancelled()
         {
-            return Err(anyhow::anyhow!("Request cancelled"));
+            return Err(ApiError::Cancelled.into());
         }
 
         // Cache miss - fetch from API
         let url = format!("{}{}.json", self.get_base_url(), list_type.as_api_str());
 
-        let result: Vec<u32> = if let Some(token) = token {
+        let body = if let Some(token) = token {
             tokio::select! {
-                res = self.get_json(&url) => {
+                res = self.get_body(&url) => {
                     res.with_context(|| format!("fetch_story_ids failed for list {:?}", list_type))?
                 }
                 _ = token.cancelled() => {
-                    return Err(anyhow::anyhow!("Request cancelled"));
+                    return Err(ApiError::Cancelled.into());
                 }
             }
         } else {
-            self.get_json(&url)
+            self.get_body(&url)
                 .await
                 .with_context(|| format!("fetch_story_ids failed for list {:?}", list_type))?
         };
+        let result: Vec<u32> = parse_json(&url, &body)?;
 
         // Store in cache
+        if let Some(disk_cache) = &self.disk_cache {
+            disk_cache.insert(EntryKind::StoryIds, &cache_key, &body);
+        }
         self.story_ids_cache.insert(cache_key, result.clone());
 
         if self.enable_metrics {
@@ -384,139 +720,265 @@ impl ApiService {
         Ok(result)
     }
 
-    /// Fetch a single story item by id.
-    /// Uses cache with 5 min TTL. Falls back to stale cache on network errors.
+    /// Fetch any HN item by id.
+    /// Uses cache with 5 min TTL, then the disk cache. Falls back to stale cache on
+    /// network errors.
     #[tracing::instrument(skip(self), fields(id = %id))]
-    pub async fn fetch_story_content(&self, id: u32) -> Result<Story> {
-        let cache_key = format!("story_{}", id);
+    pub async fn fetch_item(&self, id: u32) -> Result<Item> {
+        let cache_key = format!("item_{}", id);
 
         // Check cache first
-        if let Some(cached) = self.story_cache.get(&cache_key) {
-            tracing::trace!("Cache hit for story {}", id);
+        if let Some(cached) = self.item_cache.get(&cache_key) {
+            tracing::trace!("Cache hit for item {}", id);
             return Ok(cached);
         }
 
+        if let Some(item) = self.disk_lookup::<Item>(&cache_key, || revalidate::Target::Item(id)) {
+            self.item_cache.insert(cache_key, item.clone());
+            return Ok(item);
+        }
+
         if self.enable_metrics {
-            tracing::trace!("Cache miss for story {}", id);
+            tracing::trace!("Cache miss for item {}", id);
         }
 
+        self.refresh_item(id).await
+    }
+
+    /// Fetch an item from the API, bypassing the cache, and store the result.
+    /// Falls back to stale cache on network errors.
+    #[tracing::instrument(skip(self), fields(id = %id))]
+    pub async fn refresh_item(&self, id: u32) -> Result<Item> {
+        let cache_key = format!("item_{}", id);
         let start = std::time::Instant::now();
 
-        // If not in cache or expired, fetch from API
         let url = format!("{}item/{}.json", self.get_base_url(), id);
 
-        let story = match self.get_json::<Story>(&url).await {
-            Ok(s) => s,
+        // The API answers `null` for ids that don't exist
+        let fetched = self
+            .get_body(&url)
+            .await
+            .and_then(|body| Ok((parse_json::<Option<Item>>(&url, &body)?, body)));
+        let item = match fetched {
+            Ok((Some(item), body)) => {
+                if let Some(disk_cache) = &self.disk_cache {
+                    disk_cache.insert(EntryKind::Item, &cache_key, &body);
+                }
+                item
+            }
+            Ok((None, _)) => return Err(ApiError::NotFound(format!("item {}", id)).into()),
             Err(e) => {
                 // Try to get stale data from cache as fallback
-                if let Some(stale_story) = self.story_cache.get_stale(&cache_key) {
-                    tracing::warn!("Using stale cache for story {}: {}", id, e);
-                    return Ok(stale_story);
+                if let Some(stale_item) = self.item_cache.get_stale(&cache_key) {
+                    tracing::warn!("Using stale cache for item {}: {}", id, e);
+                    return Ok(stale_item);
                 }
-                return Err(e.context(format!("fetch_story_content failed for id {}", id)));
+                return Err(e.context(format!("refresh_item failed for id {}", id)));
             }
         };
 
-        self.story_cache.insert(cache_key, story.clone());
+        self.item_cache.insert(cache_key, item.clone());
 
         if self.enable_metrics {
-            tracing::debug!(elapsed = ?start.elapsed(), "Fetched and cached story content");
+            tracing::debug!(elapsed = ?start.elapsed(), "Fetched and cached item");
         }
 
-        Ok(story)
+        Ok(item)
     }
 
-    /// Fetch a single comment item by id.
-    /// Uses cache with 5 min TTL. Falls back to stale cache on network errors.
+    /// Fetch a single story, job or poll by id.
+    #[tracing::instrument(skip(self), fields(id = %id))]
+    pub async fn fetch_story_content(&self, id: u32) -> Result<Story> {
+        self.fetch_item(id)
+            .await
+            .with_context(|| format!("fetch_story_content failed for id {}", id))?
+            .into_story()
+            .with_context(|| format!("item {} is not a story", id))
+    }
+
+    /// Fetch a single comment by id.
     #[tracing::instrument(skip(self), fields(id = %id))]
     pub async fn fetch_comment_content(&self, id: u32) -> Result<Comment> {
-        let cache_key = format!("comment_{}", id);
+        self.fetch_item(id)
+            .await
+            .with_context(|| format!("fetch_comment_content failed for id {}", id))?
+            .into_comment()
+            .with_context(|| format!("item {} is not a comment", id))
+    }
+
+    /// Fetch the story, job or poll item `id` is part of: the item itself, the
+    /// story at the top of a comment's thread, or the poll of a poll option.
+    #[tracing::instrument(skip(self), fields(id = %id))]
+    pub async fn fetch_item_story(&self, id: u32) -> Result<Story> {
+        let mut current = id;
+        let mut visited = HashSet::new();
+        loop {
+            if !visited.insert(current) {
+                return Err(anyhow::anyhow!("item {} is its own ancestor", current));
+            }
+            if visited.len() > MAX_THREAD_HOPS {
+                return Err(anyhow::anyhow!(
+                    "no story within {} parents of item {}",
+                    MAX_THREAD_HOPS,
+                    id
+                ));
+            }
+            current = match self
+                .fetch_item(current)
+                .await
+                .with_context(|| format!("fetch_item_story failed for id {}", id))?
+            {
+                Item::Comment(comment) => comment
+                    .parent
+                    .with_context(|| format!("comment {} has no parent", comment.id))?,
+                Item::PollOpt(option) => option.poll,
+                item => {
+                    return item
+                        .into_story()
+                        .with_context(|| format!("item {} is not a story", current));
+                }
+            };
+        }
+    }
+
+    /// Fetch the options of a poll, preserving the order of `parts`.
+    /// Options that fail to load are skipped.
+    #[tracing::instrument(skip(self, parts), fields(count = parts.len()))]
+    pub async fn fetch_poll_options(&self, parts: Vec<u32>) -> Vec<PollOpt> {
+        let limit = self.network_config.concurrent_requests;
+
+        let results: Vec<Result<PollOpt>> = stream::iter(parts.into_iter())
+            .map(|id| {
+                let api = self.clone();
+                async move { api.fetch_poll_option(id, false).await }
+            })
+            // `buffered` (unlike `buffer_unordered`) yields results in input order
+            .buffered(limit)
+            .collect()
+            .await;
+
+        results.into_iter().filter_map(|r| r.ok()).collect()
+    }
+
+    /// Fetch a single poll option. With `refresh`, bypasses the cache to get a fresh score.
+    #[tracing::instrument(skip(self), fields(id = %id))]
+    pub async fn fetch_poll_option(&self, id: u32, refresh: bool) -> Result<PollOpt> {
+        let item = match refresh {
+            true => self.refresh_item(id).await,
+            false => self.fetch_item(id).await,
+        };
+
+        item.with_context(|| format!("fetch_poll_option failed for id {}", id))?
+            .into_poll_opt()
+            .with_context(|| format!("item {} is not a poll option", id))
+    }
+
+    /// Fetch a user profile by username.
+    /// Uses cache with 5 min TTL. Falls back to stale cache on network errors.
+    #[tracing::instrument(skip(self), fields(id = %id))]
+    pub async fn fetch_user(&self, id: &str) -> Result<User> {
+        let cache_key = format!("user_{}", id);
 
         // Check cache first
-        if let Some(cached) = self.comment_cache.get(&cache_key) {
-            tracing::trace!("Cache hit for comment {}", id);
+        if let Some(cached) = self.user_cache.get(&cache_key) {
+            tracing::trace!("Cache hit for user {}", id);
             return Ok(cached);
         }
 
         if self.enable_metrics {
-            tracing::trace!("Cache miss for comment {}", id);
+            tracing::trace!("Cache miss for user {}", id);
         }
 
         let start = std::time::Instant::now();
 
-        // If not in cache or expired, fetch from API
-        let url = format!("{}item/{}.json", self.get_base_url(), id);
+        let url = format!("{}user/{}.json", self.get_base_url(), id);
 
-        let comment = match self.get_json::<Comment>(&url).await {
-            Ok(c) => c,
+        // The API answers `null` for unknown users
+        let user = match self.get_json::<Option<User>>(&url).await {
+            Ok(Some(u)) => u,
+            Ok(None) => return Err(ApiError::NotFound(format!("user {}", id)).into()),
             Err(e) => {
-                // Try to get stale data from cache as fallback
-                if let Some(stale_comment) = self.comment_cache.get_stale(&cache_key) {
-                    tracing::warn!("Using stale cache for comment {}: {}", id, e);
-                    return Ok(stale_comment);
+                if let Some(stale_user) = self.user_cache.get_stale(&cache_key) {
+                    tracing::warn!("Using stale cache for user {}: {}", id, e);
+                    return Ok(stale_user);
                 }
-                return Err(e.context(format!("fetch_comment_content failed for id {}", id)));
+                return Err(e.context(format!("fetch_user failed for id {}", id)));
             }
         };
 
-        self.comment_cache.insert(cache_key, comment.clone());
+        self.user_cache.insert(cache_key, user.clone());
 
         if self.enable_metrics {
-            tracing::debug!(elapsed = ?start.elapsed(), "Fetched and cached comment content");
+            tracing::debug!(elapsed = ?start.elapsed(), "Fetched and cached user profile");
         }
 
-        Ok(comment)
+        Ok(user)
     }
 
-    /// Fetch multiple stories concurrently.
-    /// Uses `buffer_unordered` to limit concurrency based on network config.
-    /// Supports cancellation via token.
-    #[tracing::instrument(skip(self, ids, token), fields(count = ids.len()))]
+    /// Fetch multiple stories concurrently, skipping ids that aren't stories, jobs
+    /// or polls. Supports cancellation via token.
     pub async fn fetch_stories_concurrent(
         &self,
         ids: Vec<u32>,
         token: Option<CancellationToken>,
     ) -> Vec<Story> {
+        self.fetch_items_concurrent(ids, token)
+            .await
+            .into_iter()
+            .filter_map(Item::into_story)
+            .collect()
+    }
+
+    /// Fetch multiple items of any type concurrently.
+    /// Uses `buffer_unordered` to limit concurrency based on network config, so the
+    /// items come back in completion order. Supports cancellation via token.
+    #[tracing::instrument(skip(self, ids, token), fields(count = ids.len()))]
+    pub async fn fetch_items_concurrent(
+        &self,
+        ids: Vec<u32>,
+        token: Option<CancellationToken>,
+    ) -> Vec<Item> {
         let start = std::time::Instant::now();
 
         // Check cancellation before starting
         if let Some(token) = &token
             && token.is_cancelled()
         {
-            tracing::warn!("Request cancelled before starting story fetch");
+            tracing::warn!("Request cancelled before starting item fetch");
             return Vec::new();
         }
 
         let limit = self.network_config.concurrent_requests;
 
-        let results: Vec<Result<Story>> = stream::iter(ids.into_iter())
+        let results: Vec<Result<Item>> = stream::iter(ids.into_iter())
             .map(|id| {
                 let api = self.clone();
                 let token = token.clone();
                 async move {
-                    if let Some(token) = &token
-                        && token.is_cancelled()
-                    {
-                        return Err(anyhow::anyhow!("Request cancelled"));
+                    match token {
+                        Some(token) => token
+                            .run_until_cancelled(api.fetch_item(id))
+                            .await
+                            .unwrap_or_else(|| Err(ApiError::Cancelled.into())),
+                        None => api.fetch_item(id).await,
                     }
-                    api.fetch_story_content(id).await
                 }
             })
             .buffer_unordered(limit)
             .collect()
             .await;
 
-        let stories: Vec<Story> = results.into_iter().filter_map(|r| r.ok()).collect();
+        let items: Vec<Item> = results.into_iter().filter_map(|r| r.ok()).collect();
 
         if self.enable_metrics {
             tracing::debug!(
                 elapsed = ?start.elapsed(),
-                successful = stories.len(),
-                "Fetched stories concurrently"
+                successful = items.len(),
+                "Fetched items concurrently"
             );
         }
 
-        stories
+        items
     }
 
     /// Fetch multiple comments concurrently.
@@ -545,12 +1007,13 @@ impl ApiService {
                 let api = self.clone();
                 let token = token.clone();
                 async move {
-                    if let Some(token) = &token
-                        && token.is_cancelled()
-                    {
-                        return Err(anyhow::anyhow!("Request cancelled"));
+                    match token {
+                        Some(token) => token
+                            .run_until_cancelled(api.fetch_comment_content(id))
+                            .await
+                            .unwrap_or_else(|| Err(ApiError::Cancelled.into())),
+                        None => api.fetch_comment_content(id).await,
                     }
-                    api.fetch_comment_content(id).await
                 }
             })
             .buffer_unordered(limit)
@@ -580,6 +1043,7 @@ impl Default for ApiService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::internal::models::StoryKind;
 
     #[test]
     fn test_story_list_type_as_api_str() {
@@ -755,7 +1219,277 @@ mod tests {
         let result = service.fetch_comment_content(99999).await;
 
         mock.assert();
-        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert_eq!(ApiError::find(&err), Some(&ApiError::Http { status: 404 }));
+    }
+
+    fn fast_retry_service(base_url: String) -> ApiService {
+        ApiService {
+            network_config: NetworkConfig {
+                initial_retry_delay_ms: 1,
+                max_retry_delay_ms: 1000,
+                ..NetworkConfig::default()
+            },
+            ..ApiService::with_base_url(base_url)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_server_errors_are_retried() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/item/7.json")
+            .with_status(503)
+            .with_body("<html>Service Unavailable</html>")
+            .expect(4) // first attempt + max_retries
+            .create();
+
+        let service = fast_retry_service(format!("{}/", server.url()));
+        let err = service.fetch_item(7).await.unwrap_err();
+
+        mock.assert();
+        // The HTML error page is never handed to serde_json
+        assert_eq!(ApiError::find(&err), Some(&ApiError::Http { status: 503 }));
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_are_not_retried() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/item/8.json")
+            .with_status(403)
+            .expect(1)
+            .create();
+
+        let service = fast_retry_service(format!("{}/", server.url()));
+        let err = service.fetch_item(8).await.unwrap_err();
+
+        mock.assert();
+        assert!(!ApiError::find(&err).unwrap().is_offline());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_honours_retry_after() {
+        let mut server = mockito::Server::new_async().await;
+        let short = server
+            .mock("GET", "/item/9.json")
+            .with_status(429)
+            .with_header("retry-after", "0")
+            .expect(4)
+            .create();
+        let long = server
+            .mock("GET", "/item/10.json")
+            .with_status(429)
+            .with_header("retry-after", "3600")
+            .expect(1)
+            .create();
+
+        let service = fast_retry_service(format!("{}/", server.url()));
+        service.fetch_item(9).await.unwrap_err();
+        // A wait beyond max_retry_delay_ms is reported instead of slept through
+        let err = service.fetch_item(10).await.unwrap_err();
+
+        short.assert();
+        long.assert();
+        assert_eq!(
+            ApiError::find(&err),
+            Some(&ApiError::RateLimited {
+                retry_after: Some(Duration::from_secs(3600))
+            })
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_concurrent_fetches_respect_rate_limit() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock(
+                "GET",
+                mockito::Matcher::Regex(r"^/item/\d+\.json$".to_string()),
+            )
+            .with_status(200)
+            .with_body(r#"{"id": 1, "type": "story", "title": "Story"}"#)
+            .expect(10)
+            .create();
+
+        let service = ApiService {
+            network_config: NetworkConfig {
+                concurrent_requests: 10,
+                ..NetworkConfig::default()
+            },
+            ..ApiService::with_base_url(format!("{}/", server.url()))
+        };
+        let start = tokio::time::Instant::now();
+        service
+            .fetch_stories_concurrent((1..=10).collect(), None)
+            .await;
+
+        mock.assert();
+        // 3 burst tokens, then 7 more at 3/s
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(2300), "{:?}", elapsed);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server_is_offline() {
+        let service = fast_retry_service("http://localhost:1/".to_string());
+        let err = service.fetch_item(1).await.unwrap_err();
+
+        assert!(ApiError::find(&err).unwrap().is_offline());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_item_variants() {
+        let mut server = mockito::Server::new_async().await;
+        let ask_mock = server
+            .mock("GET", "/item/1.json")
+            .with_status(200)
+            .with_body(
+                r#"{"id": 1, "type": "story", "title": "Ask HN: Why?", "text": "<p>Body</p>"}"#,
+            )
+            .create();
+        let poll_mock = server
+            .mock("GET", "/item/2.json")
+            .with_status(200)
+            .with_body(r#"{"id": 2, "type": "poll", "title": "Poll", "parts": [3, 4]}"#)
+            .create();
+        let opt_mock = server
+            .mock("GET", "/item/3.json")
+            .with_status(200)
+            .with_body(r#"{"id": 3, "type": "pollopt", "poll": 2, "score": 7, "text": "Yes"}"#)
+            .create();
+        let job_mock = server
+            .mock("GET", "/item/5.json")
+            .with_status(200)
+            .with_body(r#"{"id": 5, "type": "job", "title": "Hiring", "dead": true}"#)
+            .create();
+
+        let service = ApiService::with_base_url(format!("{}/", server.url()));
+
+        let ask = service.fetch_story_content(1).await.unwrap();
+        assert_eq!(ask.kind, StoryKind::Story);
+        assert_eq!(ask.text.as_deref(), Some("<p>Body</p>"));
+
+        let poll = service.fetch_story_content(2).await.unwrap();
+        assert_eq!(poll.kind, StoryKind::Poll);
+        assert_eq!(poll.parts, Some(vec![3, 4]));
+
+        match service.fetch_item(3).await.unwrap() {
+            Item::PollOpt(opt) => {
+                assert_eq!(opt.poll, 2);
+                assert_eq!(opt.score, Some(7));
+            }
+            other => panic!("expected pollopt, got {:?}", other),
+        }
+        // A poll option is not a story (served from the item cache)
+        assert!(service.fetch_story_content(3).await.is_err());
+
+        let job = service.fetch_story_content(5).await.unwrap();
+        assert_eq!(job.kind, StoryKind::Job);
+        assert!(job.dead);
+
+        ask_mock.assert();
+        poll_mock.assert();
+        opt_mock.assert();
+        job_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_poll_options_keeps_order_and_refreshes() {
+        let mut server = mockito::Server::new_async().await;
+        let _slow = server
+            .mock("GET", "/item/11.json")
+            .with_status(200)
+            .with_chunked_body(|w| {
+                std::thread::sleep(Duration::from_millis(100));
+                w.write_all(br#"{"id": 11, "type": "pollopt", "poll": 10, "score": 3}"#)
+            })
+            .create();
+        let fast = server
+            .mock("GET", "/item/12.json")
+            .with_status(200)
+            .with_body(r#"{"id": 12, "type": "pollopt", "poll": 10, "score": 1}"#)
+            .expect(2)
+            .create();
+
+        let service = ApiService::with_base_url(format!("{}/", server.url()));
+        let options = service.fetch_poll_options(vec![11, 12]).await;
+        let ids: Vec<u32> = options.iter().map(|o| o.id).collect();
+        assert_eq!(ids, vec![11, 12]);
+
+        // Cached fetches don't hit the server; refreshes do
+        service.fetch_poll_option(12, false).await.unwrap();
+        service.fetch_poll_option(12, true).await.unwrap();
+        fast.assert();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_item_null_is_not_found() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/item/404.json")
+            .with_status(200)
+            .with_body("null")
+            .create();
+
+        let service = ApiService::with_base_url(format!("{}/", server.url()));
+        let result = service.fetch_item(404).await;
+
+        mock.assert();
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("not found"));
+        assert_eq!(
+            ApiError::find(&err),
+            Some(&ApiError::NotFound("item 404".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_user_success() {
+        let mut server = mockito::Server::new_async().await;
+        let user_json = r#"{
+            "about": "Hacker <i>and</i> painter",
+            "created": 1160418092,
+            "id": "pg",
+            "karma": 155111,
+            "submitted": [3, 2, 1]
+        }"#;
+
+        let mock = server
+            .mock("GET", "/user/pg.json")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(user_json)
+            .expect(1)
+            .create();
+
+        let service = ApiService::with_base_url(format!("{}/", server.url()));
+        let user = service.fetch_user("pg").await.unwrap();
+        // Second call is served from the cache
+        let cached = service.fetch_user("pg").await.unwrap();
+
+        mock.assert();
+        assert_eq!(user.id, "pg");
+        assert_eq!(user.karma, 155111);
+        assert_eq!(user.submitted, vec![3, 2, 1]);
+        assert_eq!(user, cached);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_user_not_found() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/user/nobody.json")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("null")
+            .create();
+
+        let service = ApiService::with_base_url(format!("{}/", server.url()));
+        let result = service.fetch_user("nobody").await;
+
+        mock.assert();
+        assert!(result.unwrap_err().to_string().contains("not found"));
     }
 
     #[tokio::test]
@@ -799,6 +1533,109 @@ mod tests {
         }
 
         mock.assert();
+        assert!(service.inflight_requests.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_fetches_release_request_slots() {
+        // Accepts connections but never answers, so every request hangs
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let service = ApiService::with_config(NetworkConfig {
+            concurrent_requests: 2,
+            api_base_url: Some(format!("http://{}/", listener.local_addr().unwrap())),
+            ..NetworkConfig::default()
+        });
+
+        let token = CancellationToken::new();
+        let fetch = tokio::spawn({
+            let service = service.clone();
+            let token = token.clone();
+            async move {
+                service
+                    .fetch_stories_concurrent(vec![1, 2, 3], Some(token))
+                    .await
+            }
+        });
+
+        while service.queue_depths().running(Priority::Interactive) < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        token.cancel();
+
+        assert!(fetch.await.unwrap().is_empty());
+        assert_eq!(service.queue_depths(), QueueDepths::default());
+        assert!(service.inflight_requests.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_does_not_cancel_shared_request() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/item/5.json")
+            .with_status(200)
+            .with_chunked_body(|w| {
+                std::thread::sleep(Duration::from_millis(200));
+                w.write_all(br#"{"id": 5, "type": "story", "title": "Slow"}"#)
+            })
+            .expect(1)
+            .create();
+
+        let service = ApiService::with_base_url(format!("{}/", server.url()));
+        let token = CancellationToken::new();
+        let cancelled = tokio::spawn({
+            let service = service.clone();
+            let token = token.clone();
+            async move { service.fetch_stories_concurrent(vec![5], Some(token)).await }
+        });
+        let waiting = tokio::spawn({
+            let service = service.clone();
+            async move { service.fetch_item(5).await }
+        });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        token.cancel();
+
+        assert!(cancelled.await.unwrap().is_empty());
+        assert!(waiting.await.unwrap().is_ok());
+        mock.assert();
+        assert!(service.inflight_requests.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_interactive_request_preempts_background_request() {
+        let mut server = mockito::Server::new_async().await;
+        let slow = server
+            .mock("GET", "/slow")
+            .with_chunked_body(|w| {
+                std::thread::sleep(Duration::from_millis(200));
+                w.write_all(b"slow")
+            })
+            .expect(2)
+            .create();
+        let fast_mock = server.mock("GET", "/fast").with_body("fast").create();
+
+        let service = ApiService::with_config(NetworkConfig {
+            concurrent_requests: 1,
+            ..NetworkConfig::default()
+        });
+        let background = tokio::spawn({
+            let service = service.at_priority(Priority::Background);
+            let url = format!("{}/slow", server.url());
+            async move { service.fetch_article(&url).await.map(|a| a.body) }
+        });
+        while service.queue_depths().running(Priority::Background) == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let fast = service
+            .fetch_article(&format!("{}/fast", server.url()))
+            .await;
+        assert_eq!(fast.unwrap().body, "fast");
+
+        // The pre-empted request was queued again and sent a second time
+        assert_eq!(background.await.unwrap().unwrap(), "slow");
+        slow.assert();
+        fast_mock.assert();
     }
 
     #[tokio::test]
@@ -817,10 +1654,49 @@ mod tests {
             retry_on_timeout: false,
             concurrent_requests: 20,
             rate_limit_per_second: 5.0,
+            rate_limit_burst: 1,
+            api_base_url: Some("http://localhost:8080/v0".to_string()),
+            ..NetworkConfig::default()
         };
 
         let service = ApiService::with_config(config);
         assert_eq!(service.network_config.max_retries, 5);
         assert_eq!(service.network_config.concurrent_requests, 20);
+        assert_eq!(service.get_base_url(), "http://localhost:8080/v0/");
+    }
+
+    #[tokio::test]
+    async fn test_client_sends_configured_user_agent() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/maxitem.json")
+            .match_header("user-agent", "hn-test/1.0")
+            .with_status(200)
+            .with_body("42")
+            .create();
+
+        let service = ApiService::with_config(NetworkConfig {
+            user_agent: "hn-test/1.0".to_string(),
+            api_base_url: Some(server.url()),
+            ..NetworkConfig::default()
+        });
+
+        assert_eq!(service.fetch_max_item().await.unwrap(), 42);
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout_is_applied() {
+        let service = ApiService::with_config(NetworkConfig {
+            request_timeout_secs: 1,
+            max_retries: 0,
+            ..NetworkConfig::default()
+        });
+        // A listener that accepts but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+
+        let err = service.get_json::<u32>(&url).await.unwrap_err();
+        assert_eq!(ApiError::find(&err), Some(&ApiError::Timeout));
     }
 }
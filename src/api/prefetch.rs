@@ -0,0 +1,195 @@
+//! Background prefetching for the stories the user is looking at.
+//!
+//! While the story list sits still, the first page of comments and the linked
+//! article of the top visible rows are loaded into the item and article caches, so
+//! opening one of them doesn't start cold.
+
+use super::{ApiService, Priority, RateLimiter};
+use crate::config::PrefetchConfig;
+use crate::internal::models::Story;
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use tokio_util::sync::CancellationToken;
+
+/// Number of top-level comments shown before "load more", matching `AppState`.
+pub const COMMENT_PAGE_SIZE: usize = 20;
+
+/// Outcome of one prefetch pass.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PrefetchStats {
+    pub fetched: usize,
+    pub failed: usize,
+    /// The pass stopped early because the budget ran out
+    pub out_of_budget: bool,
+}
+
+/// Warms caches for visible stories within a request budget.
+///
+/// Requests run one at a time at `Priority::Prefetch`, so prefetching never takes
+/// more than one request slot and never delays what the user asked for.
+#[derive(Clone)]
+pub struct Prefetcher {
+    api: ApiService,
+    config: PrefetchConfig,
+    /// Refills at `budget_per_minute`, shared by every pass
+    budget: RateLimiter,
+}
+
+impl Prefetcher {
+    pub fn new(api: ApiService, config: PrefetchConfig) -> Self {
+        let budget = RateLimiter::new(
+            f64::from(config.budget_per_minute) / 60.0,
+            config.budget_per_minute,
+        );
+        Self {
+            api: api.at_priority(Priority::Prefetch),
+            config,
+            budget,
+        }
+    }
+
+    /// Whether prefetching is on; data saver mode turns it off.
+    pub fn is_enabled(&self) -> bool {
+        !self.config.data_saver && self.config.budget_per_minute > 0
+    }
+
+    /// Prefetch for `stories`, given top row first, until done, out of budget or cancelled.
+    pub async fn run(&self, stories: Vec<Story>, token: CancellationToken) -> PrefetchStats {
+        let mut stats = PrefetchStats::default();
+        if !self.is_enabled() {
+            return stats;
+        }
+
+        for (row, story) in stories.iter().enumerate() {
+            for request in self.requests_for(row, story) {
+                if self.budget.try_acquire().is_err() {
+                    stats.out_of_budget = true;
+                    return stats;
+                }
+
+                match token.run_until_cancelled(request).await {
+                    None => return stats,
+                    Some(Ok(())) => stats.fetched += 1,
+                    Some(Err(e)) => {
+                        tracing::debug!("Prefetch for story {} failed: {}", story.id, e);
+                        stats.failed += 1;
+                    }
+                }
+            }
+        }
+
+        stats
+    }
+
+    /// Requests for uncached comments and article of the story in `row`.
+    fn requests_for(
+        &self,
+        row: usize,
+        story: &Story,
+    ) -> Vec<BoxFuture<'static, anyhow::Result<()>>> {
+        let mut requests = Vec::new();
+
+        if row < self.config.comment_stories {
+            let kids = story.kids.as_deref().unwrap_or_default();
+            for &id in kids.iter().take(COMMENT_PAGE_SIZE) {
+                if self.api.item_cache.get(&format!("item_{}", id)).is_some() {
+                    continue;
+                }
+                let api = self.api.clone();
+                requests.push(async move { api.fetch_item(id).await.map(|_| ()) }.boxed());
+            }
+        }
+
+        if row < self.config.article_stories
+            && let Some(url) = story.url.clone()
+            && self.api.article_cache.get(&url).is_none()
+        {
+            let api = self.api.clone();
+            requests.push(async move { api.fetch_article(&url).await.map(|_| ()) }.boxed());
+        }
+
+        requests
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{FixtureBackend, NetworkConfig};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn story(id: u32, kids: Vec<u32>, url: Option<&str>) -> Story {
+        Story {
+            id,
+            kids: Some(kids),
+            url: url.map(str::to_string),
+            ..Story::default()
+        }
+    }
+
+    fn service() -> ApiService {
+        let backend = FixtureBackend::new()
+            .with_item(json!({ "id": 11, "type": "comment", "text": "a" }))
+            .with_item(json!({ "id": 12, "type": "comment", "text": "b" }))
+            .with_item(json!({ "id": 21, "type": "comment", "text": "c" }));
+        backend.insert("/article", "<p>Article</p>");
+        ApiService::with_backend(NetworkConfig::default(), Arc::new(backend))
+    }
+
+    #[tokio::test]
+    async fn test_warms_comments_and_articles_of_top_rows() {
+        let api = service();
+        let prefetcher = Prefetcher::new(
+            api.clone(),
+            PrefetchConfig {
+                comment_stories: 1,
+                article_stories: 1,
+                ..PrefetchConfig::default()
+            },
+        );
+
+        let stories = vec![
+            story(1, vec![11, 12], Some("http://example.com/article")),
+            story(2, vec![21], None),
+        ];
+        let stats = prefetcher.run(stories, CancellationToken::new()).await;
+
+        assert_eq!(stats.fetched, 3);
+        assert!(api.item_cache.get("item_11").is_some());
+        assert!(api.item_cache.get("item_12").is_some());
+        // Only the first row is within `comment_stories`
+        assert!(api.item_cache.get("item_21").is_none());
+        assert!(
+            api.article_cache
+                .get("http://example.com/article")
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_data_saver_and_budget_limit_requests() {
+        let stories = vec![story(1, vec![11, 12], None)];
+
+        let saver = Prefetcher::new(
+            service(),
+            PrefetchConfig {
+                data_saver: true,
+                ..PrefetchConfig::default()
+            },
+        );
+        let stats = saver.run(stories.clone(), CancellationToken::new()).await;
+        assert_eq!(stats, PrefetchStats::default());
+
+        let tight = Prefetcher::new(
+            service(),
+            PrefetchConfig {
+                budget_per_minute: 1,
+                ..PrefetchConfig::default()
+            },
+        );
+        let stats = tight.run(stories, CancellationToken::new()).await;
+        assert_eq!(stats.fetched, 1);
+        assert!(stats.out_of_budget);
+    }
+}
@@ -0,0 +1,156 @@
+//! Token-bucket rate limiting for outgoing requests.
+//!
+//! The bucket refills at `rate` tokens per second up to `burst` tokens, and each
+//! request takes one token. Unlike a semaphore sized to the rate, this bounds the
+//! number of requests per second rather than the number in flight.
+
+use super::sleep;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Longest wait handed out for a token, for rates so low the exact wait is huge
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// A token bucket shared by every clone.
+#[derive(Clone)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+struct Bucket {
+    /// Tokens added per second, infinite when requests aren't limited
+    rate: f64,
+    /// Maximum number of stored tokens
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last_refill = now;
+    }
+}
+
+impl RateLimiter {
+    /// Create a limiter allowing `rate` requests per second with bursts of up to
+    /// `burst` requests. The bucket starts full. A `rate` of 0 or less, or one
+    /// that isn't a number, leaves requests unlimited.
+    pub fn new(rate: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        let rate = if rate > 0.0 { rate } else { f64::INFINITY };
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                rate,
+                burst,
+                tokens: burst,
+                last_refill: Instant::now(),
+            })),
+        }
+    }
+
+    /// Take a token without waiting. Returns how long until one is available otherwise.
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        let mut bucket = self.bucket.lock().expect("rate limiter lock poisoned");
+        if bucket.rate.is_infinite() {
+            return Ok(());
+        }
+        bucket.refill(Instant::now());
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+
+        let missing = 1.0 - bucket.tokens;
+        Err(Duration::try_from_secs_f64(missing / bucket.rate)
+            .map_or(MAX_WAIT, |wait| wait.min(MAX_WAIT)))
+    }
+
+    /// Wait until a token is available and take it.
+    ///
+    /// Dropping the future while it waits takes nothing from the bucket.
+    pub async fn acquire(&self) {
+        while let Err(wait) = self.try_acquire() {
+            sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::join_all;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_burst_is_immediate_then_rate_limited() {
+        let limiter = RateLimiter::new(3.0, 3);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        for _ in 0..6 {
+            limiter.acquire().await;
+        }
+        // Six more tokens at 3/s take two seconds
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(2), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(2100), "{:?}", elapsed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_clones_share_one_bucket() {
+        let limiter = RateLimiter::new(2.0, 1);
+        let start = Instant::now();
+
+        // Ten concurrent callers, each on its own clone, still get 2 req/s in total
+        let callers = (0..10).map(|i| {
+            let limiter = limiter.clone();
+            async move {
+                limiter.acquire().await;
+                (i, start.elapsed())
+            }
+        });
+        let done = join_all(callers).await;
+
+        let last = done.iter().map(|(_, at)| *at).max().unwrap();
+        assert!(last >= Duration::from_millis(4500), "{:?}", last);
+        assert!(last < Duration::from_millis(5000), "{:?}", last);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_idle_time_refills_up_to_burst() {
+        let limiter = RateLimiter::new(1.0, 2);
+        limiter.acquire().await;
+        limiter.acquire().await;
+        assert!(limiter.try_acquire().is_err());
+
+        tokio::time::advance(Duration::from_secs(60)).await;
+
+        assert!(limiter.try_acquire().is_ok());
+        assert!(limiter.try_acquire().is_ok());
+        assert!(limiter.try_acquire().is_err());
+    }
+
+    #[test]
+    fn test_zero_rate_is_unlimited() {
+        let limiter = RateLimiter::new(0.0, 1);
+        for _ in 0..10 {
+            assert!(limiter.try_acquire().is_ok());
+        }
+        assert!(RateLimiter::new(f64::NAN, 1).try_acquire().is_ok());
+
+        // A rate too low to express as a wait is capped instead of overflowing
+        let limiter = RateLimiter::new(f64::MIN_POSITIVE, 1);
+        assert!(limiter.try_acquire().is_ok());
+        assert_eq!(limiter.try_acquire(), Err(MAX_WAIT));
+    }
+}
@@ -0,0 +1,178 @@
+//! Stale-while-revalidate on top of the disk cache.
+//!
+//! A disk cache hit is returned straight away, even when it has outlived its TTL,
+//! so the app shows last session's data without waiting for the network. A stale
+//! hit also queues a background request for a fresh copy, which is stored in the
+//! caches and handed to subscribers.
+
+use super::{ApiService, Priority, StoryListType, tokio_handle};
+use crate::internal::models::Item;
+use futures::channel::mpsc;
+use serde::de::DeserializeOwned;
+use std::sync::{Arc, Mutex};
+
+/// Fresh data fetched to replace a stale disk cache hit.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Revalidated {
+    StoryIds {
+        list_type: StoryListType,
+        ids: Vec<u32>,
+    },
+    Item(Item),
+}
+
+/// Senders of every [`ApiService::subscribe_revalidated`] receiver still open
+pub(crate) type RevalidatedSenders = Arc<Mutex<Vec<mpsc::UnboundedSender<Revalidated>>>>;
+
+/// What to fetch again after a stale hit.
+#[derive(Debug, Clone)]
+pub(crate) enum Target {
+    StoryIds(StoryListType),
+    Item(u32),
+    /// Articles are refreshed in the cache only; the open page isn't swapped out
+    Article(String),
+}
+
+impl ApiService {
+    /// Receive the data refreshed after stale disk cache hits, to update what is on screen.
+    pub fn subscribe_revalidated(&self) -> mpsc::UnboundedReceiver<Revalidated> {
+        let (tx, rx) = mpsc::unbounded();
+        if let Ok(mut senders) = self.revalidated.lock() {
+            senders.push(tx);
+        }
+        rx
+    }
+
+    /// Look `key` up in the disk cache. A stale hit is returned as well, and
+    /// `target` is fetched again in the background.
+    pub(crate) fn disk_lookup<T: DeserializeOwned>(
+        &self,
+        key: &str,
+        target: impl FnOnce() -> Target,
+    ) -> Option<T> {
+        let hit = self.disk_cache.as_ref()?.get(key)?;
+        let value = match serde_json::from_str(&hit.body) {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable disk cache entry {}: {}", key, e);
+                return None;
+            }
+        };
+
+        tracing::trace!(stale = hit.stale, "Disk cache hit for {}", key);
+        if hit.stale {
+            self.revalidate(target());
+        }
+        Some(value)
+    }
+
+    /// Fetch `target` at background priority and publish the result.
+    fn revalidate(&self, target: Target) {
+        let api = self.at_priority(Priority::Background);
+
+        tokio_handle().spawn(async move {
+            let result = match target.clone() {
+                Target::StoryIds(list_type) => api
+                    .refresh_story_ids(list_type, None)
+                    .await
+                    .map(|ids| Some(Revalidated::StoryIds { list_type, ids })),
+                Target::Item(id) => api
+                    .refresh_item(id)
+                    .await
+                    .map(|item| Some(Revalidated::Item(item))),
+                Target::Article(url) => api.refresh_article(&url).await.map(|_| None),
+            };
+
+            match result {
+                Ok(Some(revalidated)) => api.publish_revalidated(revalidated),
+                Ok(None) => {}
+                Err(e) => tracing::debug!("Revalidating {:?} failed: {:#}", target, e),
+            }
+        });
+    }
+
+    fn publish_revalidated(&self, revalidated: Revalidated) {
+        if let Ok(mut senders) = self.revalidated.lock() {
+            senders.retain(|tx| tx.unbounded_send(revalidated.clone()).is_ok());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{FixtureBackend, NetworkConfig};
+    use crate::config::CacheConfig;
+    use crate::disk_cache::{DiskCache, EntryKind};
+    use futures::StreamExt;
+    use serde_json::json;
+
+    fn disk_cache(name: &str, config: CacheConfig) -> DiskCache {
+        let path = std::env::temp_dir()
+            .join(format!("gpui_hn_test_revalidate_{}", std::process::id()))
+            .join(format!("{}.json", name));
+        let _ = std::fs::remove_file(&path);
+        DiskCache::open(path, config)
+    }
+
+    #[tokio::test]
+    async fn test_fresh_disk_entry_is_served_without_a_request() {
+        let disk = disk_cache("fresh", CacheConfig::default());
+        disk.insert(
+            EntryKind::Item,
+            "item_1",
+            &json!({ "id": 1, "type": "story", "title": "Cached" }).to_string(),
+        );
+
+        // The backend has nothing, so the story can only come from disk
+        let api =
+            ApiService::with_backend(NetworkConfig::default(), Arc::new(FixtureBackend::new()))
+                .with_disk_cache(disk);
+        let story = api.fetch_story_content(1).await.unwrap();
+        assert_eq!(story.title.as_deref(), Some("Cached"));
+    }
+
+    #[tokio::test]
+    async fn test_stale_disk_entry_is_served_then_revalidated() {
+        let disk = disk_cache(
+            "stale",
+            CacheConfig {
+                story_ids_ttl_secs: 0,
+                ..CacheConfig::default()
+            },
+        );
+        disk.insert(EntryKind::StoryIds, "story_ids_topstories", "[1, 2]");
+
+        let backend = FixtureBackend::new().with_story_list(StoryListType::Top, &[3, 1, 2]);
+        let api = ApiService::with_backend(NetworkConfig::default(), Arc::new(backend))
+            .with_disk_cache(disk.clone());
+        let mut revalidated = api.subscribe_revalidated();
+
+        let ids = api.fetch_story_ids(StoryListType::Top, None).await.unwrap();
+        assert_eq!(ids, vec![1, 2]);
+
+        assert_eq!(
+            revalidated.next().await,
+            Some(Revalidated::StoryIds {
+                list_type: StoryListType::Top,
+                ids: vec![3, 1, 2],
+            })
+        );
+        assert_eq!(disk.get("story_ids_topstories").unwrap().body, "[3,1,2]");
+    }
+
+    #[tokio::test]
+    async fn test_clear_cache_empties_memory_and_disk() {
+        let disk = disk_cache("clear", CacheConfig::default());
+        let backend = FixtureBackend::new().with_item(json!({ "id": 1, "type": "story" }));
+        let api = ApiService::with_backend(NetworkConfig::default(), Arc::new(backend))
+            .with_disk_cache(disk.clone());
+
+        api.fetch_item(1).await.unwrap();
+        assert_eq!(disk.len(), 1);
+
+        api.clear_cache().unwrap();
+        assert!(disk.is_empty());
+        assert!(api.item_cache.get("item_1").is_none());
+    }
+}
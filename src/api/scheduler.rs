@@ -0,0 +1,387 @@
+//! Priority scheduling of outgoing requests.
+//!
+//! Requests wait in one queue ordered by [`Priority`], then by arrival. Only the
+//! request at the head of the queue may take a request slot and a rate limiting
+//! token, so an interactive fetch is always the next to go out, however many
+//! background fetches are queued. Running background requests are pre-empted when
+//! a more urgent request finds every slot taken.
+
+use super::{RateLimiter, sleep};
+use futures::future::select;
+use std::pin::pin;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+/// How urgently a request is needed, most urgent first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Directly requested by the user, e.g. opening a story
+    Interactive,
+    /// Needed for what is on screen, e.g. the next page of the story list
+    Visible,
+    /// Speculative fetches for what the user may open next
+    Prefetch,
+    /// Periodic refreshes; pre-empted by anything more urgent
+    Background,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 4] = [
+        Priority::Interactive,
+        Priority::Visible,
+        Priority::Prefetch,
+        Priority::Background,
+    ];
+
+    fn from_u8(value: u8) -> Self {
+        Self::ALL
+            .get(usize::from(value))
+            .copied()
+            .unwrap_or(Priority::Background)
+    }
+
+    /// Whether a running request may be interrupted to make room for a more urgent one.
+    pub fn is_preemptible(self) -> bool {
+        self == Priority::Background
+    }
+}
+
+/// The priority of one request. Raised when a more urgent caller joins a
+/// deduplicated request that is still queued.
+#[derive(Debug, Clone)]
+pub(crate) struct RequestPriority(Arc<AtomicU8>);
+
+impl RequestPriority {
+    pub(crate) fn new(priority: Priority) -> Self {
+        Self(Arc::new(AtomicU8::new(priority as u8)))
+    }
+
+    pub(crate) fn get(&self) -> Priority {
+        Priority::from_u8(self.0.load(Ordering::Relaxed))
+    }
+
+    /// Raise the priority to `priority` if that is more urgent. Returns whether it changed.
+    pub(crate) fn raise(&self, priority: Priority) -> bool {
+        self.0.fetch_min(priority as u8, Ordering::Relaxed) > priority as u8
+    }
+}
+
+/// Number of queued and running requests per priority, indexed by `Priority as usize`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QueueDepths {
+    pub queued: [usize; 4],
+    pub running: [usize; 4],
+}
+
+impl QueueDepths {
+    pub fn queued(&self, priority: Priority) -> usize {
+        self.queued[priority as usize]
+    }
+
+    pub fn running(&self, priority: Priority) -> usize {
+        self.running[priority as usize]
+    }
+}
+
+/// Hands out request slots in priority order, each with a rate limiting token.
+/// Clones share the same queue.
+#[derive(Clone)]
+pub struct Scheduler {
+    state: Arc<Mutex<State>>,
+    /// Woken whenever a slot frees up or the queue changes
+    changed: Arc<Notify>,
+    rate_limiter: RateLimiter,
+}
+
+struct State {
+    free_slots: usize,
+    next_ticket: u64,
+    queued: Vec<Queued>,
+    running: Vec<Running>,
+}
+
+struct Queued {
+    ticket: u64,
+    priority: RequestPriority,
+}
+
+struct Running {
+    ticket: u64,
+    priority: Priority,
+    preempt: CancellationToken,
+}
+
+impl State {
+    /// The queued request that goes next: most urgent, then first come.
+    fn head(&self) -> Option<u64> {
+        self.queued
+            .iter()
+            .min_by_key(|q| (q.priority.get(), q.ticket))
+            .map(|q| q.ticket)
+    }
+}
+
+/// A request slot, held until dropped.
+pub struct SlotPermit {
+    scheduler: Scheduler,
+    ticket: u64,
+    preempt: CancellationToken,
+}
+
+impl SlotPermit {
+    /// Cancelled when a more urgent request needs this slot. The holder should stop
+    /// and queue again.
+    pub fn preempted(&self) -> &CancellationToken {
+        &self.preempt
+    }
+}
+
+impl Drop for SlotPermit {
+    fn drop(&mut self) {
+        let mut state = self.scheduler.lock();
+        state.free_slots += 1;
+        state.running.retain(|r| r.ticket != self.ticket);
+        drop(state);
+        self.scheduler.changed.notify_waiters();
+    }
+}
+
+/// Removes a caller from the queue if it stops waiting before getting a slot.
+struct QueuedGuard<'a> {
+    scheduler: &'a Scheduler,
+    ticket: u64,
+}
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        let mut state = self.scheduler.lock();
+        let before = state.queued.len();
+        state.queued.retain(|q| q.ticket != self.ticket);
+        let removed = state.queued.len() != before;
+        drop(state);
+        if removed {
+            self.scheduler.changed.notify_waiters();
+        }
+    }
+}
+
+impl Scheduler {
+    /// Allow `slots` requests in flight at once, sending them no faster than `rate_limiter`.
+    pub fn new(slots: usize, rate_limiter: RateLimiter) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                free_slots: slots.max(1),
+                next_ticket: 0,
+                queued: Vec::new(),
+                running: Vec::new(),
+            })),
+            changed: Arc::new(Notify::new()),
+            rate_limiter,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("scheduler lock poisoned")
+    }
+
+    /// Wait in the queue for a request slot and a rate limiting token.
+    ///
+    /// `priority` is read again whenever the queue changes, so raising it moves a
+    /// waiting request forward. Dropping the future leaves the queue.
+    pub(crate) async fn acquire(&self, priority: &RequestPriority) -> SlotPermit {
+        let ticket = {
+            let mut state = self.lock();
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state.queued.push(Queued {
+                ticket,
+                priority: priority.clone(),
+            });
+            ticket
+        };
+        let _queued = QueuedGuard {
+            scheduler: self,
+            ticket,
+        };
+        // Let requests behind this one re-check their position
+        self.changed.notify_waiters();
+
+        loop {
+            // Register for wake-ups before looking at the state, so none are missed
+            let mut changed = pin!(self.changed.notified());
+            changed.as_mut().enable();
+
+            let wait = {
+                let mut state = self.lock();
+                if state.head() != Some(ticket) {
+                    None
+                } else if state.free_slots == 0 {
+                    let priority = priority.get();
+                    if !priority.is_preemptible()
+                        && let Some(victim) = state
+                            .running
+                            .iter()
+                            .filter(|r| r.priority.is_preemptible() && !r.preempt.is_cancelled())
+                            .max_by_key(|r| r.ticket)
+                    {
+                        tracing::debug!(?priority, "Pre-empting a background request");
+                        victim.preempt.cancel();
+                    }
+                    None
+                } else {
+                    match self.rate_limiter.try_acquire() {
+                        Ok(()) => {
+                            let preempt = CancellationToken::new();
+                            state.free_slots -= 1;
+                            state.queued.retain(|q| q.ticket != ticket);
+                            state.running.push(Running {
+                                ticket,
+                                priority: priority.get(),
+                                preempt: preempt.clone(),
+                            });
+                            return SlotPermit {
+                                scheduler: self.clone(),
+                                ticket,
+                                preempt,
+                            };
+                        }
+                        Err(wait) => Some(wait),
+                    }
+                }
+            };
+
+            match wait {
+                None => changed.await,
+                // Waiting for a token; a more urgent arrival may take over the head
+                Some(wait) => {
+                    select(changed, pin!(sleep(wait))).await;
+                }
+            }
+        }
+    }
+
+    /// Wake queued requests to re-read their priorities.
+    pub(crate) fn reprioritized(&self) {
+        self.changed.notify_waiters();
+    }
+
+    /// Current queue depth and running requests per priority.
+    pub fn depths(&self) -> QueueDepths {
+        let state = self.lock();
+        let mut depths = QueueDepths::default();
+        for queued in &state.queued {
+            depths.queued[queued.priority.get() as usize] += 1;
+        }
+        for running in &state.running {
+            depths.running[running.priority as usize] += 1;
+        }
+        depths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn scheduler(slots: usize) -> Scheduler {
+        Scheduler::new(slots, RateLimiter::new(1000.0, 1000))
+    }
+
+    #[tokio::test]
+    async fn test_interactive_request_jumps_the_queue() {
+        let scheduler = scheduler(1);
+        let held = scheduler
+            .acquire(&RequestPriority::new(Priority::Visible))
+            .await;
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let spawn = |priority: Priority| {
+            let scheduler = scheduler.clone();
+            let order = order.clone();
+            tokio::spawn(async move {
+                let _permit = scheduler.acquire(&RequestPriority::new(priority)).await;
+                order.lock().unwrap().push(priority);
+            })
+        };
+
+        let mut waiters = vec![spawn(Priority::Background), spawn(Priority::Prefetch)];
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        waiters.push(spawn(Priority::Interactive));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert_eq!(scheduler.depths().queued(Priority::Interactive), 1);
+        assert_eq!(scheduler.depths().running(Priority::Visible), 1);
+
+        drop(held);
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec![
+                Priority::Interactive,
+                Priority::Prefetch,
+                Priority::Background
+            ]
+        );
+        assert_eq!(scheduler.depths(), QueueDepths::default());
+    }
+
+    #[tokio::test]
+    async fn test_background_request_is_preempted() {
+        let scheduler = scheduler(1);
+        let background = scheduler
+            .acquire(&RequestPriority::new(Priority::Background))
+            .await;
+        assert!(!background.preempted().is_cancelled());
+
+        let interactive = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move {
+                scheduler
+                    .acquire(&RequestPriority::new(Priority::Interactive))
+                    .await;
+            }
+        });
+
+        background.preempted().cancelled().await;
+        drop(background);
+        interactive.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_raised_priority_moves_request_forward() {
+        let scheduler = scheduler(1);
+        let held = scheduler
+            .acquire(&RequestPriority::new(Priority::Interactive))
+            .await;
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let spawn = |name: &'static str, priority: RequestPriority| {
+            let scheduler = scheduler.clone();
+            let order = order.clone();
+            tokio::spawn(async move {
+                let _permit = scheduler.acquire(&priority).await;
+                order.lock().unwrap().push(name);
+            })
+        };
+
+        let prefetch = RequestPriority::new(Priority::Prefetch);
+        let first = spawn("visible", RequestPriority::new(Priority::Visible));
+        let second = spawn("prefetch", prefetch.clone());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert!(prefetch.raise(Priority::Interactive));
+        assert!(!prefetch.raise(Priority::Background));
+        scheduler.reprioritized();
+
+        drop(held);
+        first.await.unwrap();
+        second.await.unwrap();
+        assert_eq!(*order.lock().unwrap(), vec!["prefetch", "visible"]);
+    }
+}
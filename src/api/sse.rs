@@ -0,0 +1,512 @@
+//! Server-Sent Events client for the Firebase streaming endpoints.
+//!
+//! Requesting any Firebase URL with `Accept: text/event-stream` returns the current
+//! value as a `put` event at path `/`, followed by `put`/`patch` events for every
+//! change. We keep a local copy of the JSON document, apply each event to it and
+//! hand the re-parsed value to the subscriber.
+
+use super::{ApiService, StoryListType, tokio_handle};
+use crate::internal::models::Item;
+use anyhow::{Context, Result};
+use futures::channel::mpsc;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// A raw event as framed by the `text/event-stream` format.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SseEvent {
+    pub event: String,
+    pub data: String,
+}
+
+/// Incremental parser for `text/event-stream` bodies. Chunks may split lines anywhere,
+/// even inside a UTF-8 character.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    /// Bytes of the line still being received, decoded once it is complete
+    buffer: Vec<u8>,
+    event: String,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk of the body and return every event it completed.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let bytes: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&bytes);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                // A blank line dispatches the pending event
+                if !self.data.is_empty() || !self.event.is_empty() {
+                    events.push(SseEvent {
+                        event: std::mem::take(&mut self.event),
+                        data: std::mem::take(&mut self.data).join("\n"),
+                    });
+                }
+                continue;
+            }
+
+            // Lines starting with ':' are comments
+            if line.starts_with(':') {
+                continue;
+            }
+
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line, ""),
+            };
+
+            match field {
+                "event" => self.event = value.to_string(),
+                "data" => self.data.push(value.to_string()),
+                _ => {}
+            }
+        }
+
+        events
+    }
+}
+
+/// Events sent by the Firebase REST streaming API.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FirebaseEvent {
+    /// Replace the value at `path`
+    Put {
+        path: String,
+        data: Value,
+    },
+    /// Merge the keys of `data` into the value at `path`
+    Patch {
+        path: String,
+        data: Value,
+    },
+    KeepAlive,
+    /// The stream was closed by the server (e.g. security rules changed)
+    Cancel,
+    AuthRevoked,
+}
+
+#[derive(Deserialize)]
+struct FirebasePayload {
+    path: String,
+    data: Value,
+}
+
+impl FirebaseEvent {
+    pub fn parse(event: &SseEvent) -> Result<Self> {
+        let payload = || {
+            serde_json::from_str::<FirebasePayload>(&event.data)
+                .with_context(|| format!("invalid {} payload: {}", event.event, event.data))
+        };
+
+        match event.event.as_str() {
+            "put" => payload().map(|p| FirebaseEvent::Put {
+                path: p.path,
+                data: p.data,
+            }),
+            "patch" => payload().map(|p| FirebaseEvent::Patch {
+                path: p.path,
+                data: p.data,
+            }),
+            "keep-alive" => Ok(FirebaseEvent::KeepAlive),
+            "cancel" => Ok(FirebaseEvent::Cancel),
+            "auth_revoked" => Ok(FirebaseEvent::AuthRevoked),
+            other => Err(anyhow::anyhow!("unknown event type: {}", other)),
+        }
+    }
+
+    /// Apply a `put` or `patch` to `doc`. Returns `false` if the document is unchanged.
+    pub fn apply(&self, doc: &mut Value) -> bool {
+        match self {
+            FirebaseEvent::Put { path, data } => {
+                let Some(target) = value_at_path(doc, path) else {
+                    return false;
+                };
+                *target = data.clone();
+                true
+            }
+            FirebaseEvent::Patch { path, data } => {
+                let (Some(target), Value::Object(fields)) = (value_at_path(doc, path), data) else {
+                    return false;
+                };
+                if !target.is_object() {
+                    *target = Value::Object(Default::default());
+                }
+                let object = target.as_object_mut().expect("target is an object");
+                for (key, value) in fields {
+                    match value {
+                        Value::Null => object.remove(key),
+                        value => object.insert(key.clone(), value.clone()),
+                    };
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Walk (and create as needed) the value at a Firebase path like `/kids/3`.
+fn value_at_path<'a>(doc: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    let mut current = doc;
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        current = match current {
+            Value::Array(items) => {
+                let index: usize = segment.parse().ok()?;
+                if index >= items.len() {
+                    items.resize(index + 1, Value::Null);
+                }
+                &mut items[index]
+            }
+            value => {
+                if !value.is_object() {
+                    *value = Value::Object(Default::default());
+                }
+                value
+                    .as_object_mut()
+                    .expect("value is an object")
+                    .entry(segment.to_string())
+                    .or_insert(Value::Null)
+            }
+        };
+    }
+    Some(current)
+}
+
+impl ApiService {
+    /// Stream the ranking of a story list. Each message is the full, current id list.
+    pub fn subscribe_story_list(
+        &self,
+        list_type: StoryListType,
+        token: CancellationToken,
+    ) -> mpsc::UnboundedReceiver<Vec<u32>> {
+        let url = format!("{}{}.json", self.get_base_url(), list_type.as_api_str());
+        let cache = self.story_ids_cache.clone();
+        let cache_key = format!("story_ids_{}", list_type.as_api_str());
+
+        self.subscribe_json(url, token, move |ids: &Vec<u32>| {
+            cache.insert(cache_key.clone(), ids.clone());
+        })
+    }
+
+    /// Stream an item. Each message is the item's current state.
+    pub fn subscribe_item(
+        &self,
+        id: u32,
+        token: CancellationToken,
+    ) -> mpsc::UnboundedReceiver<Item> {
+        let url = format!("{}item/{}.json", self.get_base_url(), id);
+        let cache = self.item_cache.clone();
+        let cache_key = format!("item_{}", id);
+
+        self.subscribe_json(url, token, move |item: &Item| {
+            cache.insert(cache_key.clone(), item.clone());
+        })
+    }
+
+    /// Keep an SSE connection to `url` open on the tokio runtime, reconnecting with the
+    /// configured backoff, until `token` is cancelled or the receiver is dropped.
+    /// Gives up after `max_retries` consecutive failed connections.
+    fn subscribe_json<T, F>(
+        &self,
+        url: String,
+        token: CancellationToken,
+        on_update: F,
+    ) -> mpsc::UnboundedReceiver<T>
+    where
+        T: DeserializeOwned + Send + 'static,
+        F: Fn(&T) + Send + Sync + 'static,
+    {
+        let (tx, rx) = mpsc::unbounded();
+        // Recorded and fixture data never changes; the stream simply ends
+        if !self.backend.is_live() {
+            return rx;
+        }
+
+        let client = self.stream_client.clone();
+        let config = self.network_config.clone();
+
+        tokio_handle().spawn(async move {
+            let mut failures = 0;
+            let mut delay = config.initial_retry_delay_ms;
+
+            loop {
+                let mut doc = Value::Null;
+                let result = tokio::select! {
+                    res = stream_events(&client, &url, |event| {
+                        if !event.apply(&mut doc) {
+                            return true;
+                        }
+                        match serde_json::from_value::<T>(doc.clone()) {
+                            Ok(value) => {
+                                on_update(&value);
+                                tx.unbounded_send(value).is_ok()
+                            }
+                            Err(e) => {
+                                tracing::debug!("Skipping unparseable stream value from {}: {}", url, e);
+                                true
+                            }
+                        }
+                    }) => res,
+                    _ = token.cancelled() => break,
+                };
+
+                match result {
+                    // The receiver is gone; nobody is listening any more
+                    Ok(StreamEnd::Closed) => break,
+                    Ok(StreamEnd::Disconnected { received }) => {
+                        if received {
+                            failures = 0;
+                            delay = config.initial_retry_delay_ms;
+                        } else {
+                            failures += 1;
+                        }
+                        tracing::debug!("Event stream {} ended, reconnecting", url);
+                    }
+                    Err(e) => {
+                        failures += 1;
+                        tracing::warn!("Event stream {} failed: {}", url, e);
+                    }
+                }
+
+                if failures > config.max_retries {
+                    tracing::error!("Giving up on event stream {} after {} attempts", url, failures);
+                    break;
+                }
+
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_millis(delay)) => {}
+                    _ = token.cancelled() => break,
+                }
+                delay = (delay * 2).min(config.max_retry_delay_ms);
+            }
+
+            tracing::debug!("Event stream {} stopped", url);
+        });
+
+        rx
+    }
+}
+
+enum StreamEnd {
+    /// The subscriber hung up
+    Closed,
+    /// The server closed the connection; `received` is whether any event arrived
+    Disconnected { received: bool },
+}
+
+/// Read one SSE connection, calling `on_event` for each Firebase event until the
+/// body ends or `on_event` returns `false`.
+async fn stream_events<F>(client: &reqwest::Client, url: &str, mut on_event: F) -> Result<StreamEnd>
+where
+    F: FnMut(&FirebaseEvent) -> bool,
+{
+    let mut resp = client
+        .get(url)
+        .header(reqwest::header::ACCEPT, "text/event-stream")
+        .send()
+        .await
+        .with_context(|| format!("failed to connect to event stream {}", url))?
+        .error_for_status()
+        .with_context(|| format!("event stream {} rejected", url))?;
+
+    let mut parser = SseParser::new();
+    let mut received = false;
+
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .with_context(|| format!("event stream {} interrupted", url))?
+    {
+        for raw in parser.push(&chunk) {
+            let event = match FirebaseEvent::parse(&raw) {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::debug!("Ignoring event from {}: {}", url, e);
+                    continue;
+                }
+            };
+            received = true;
+
+            match event {
+                FirebaseEvent::Cancel | FirebaseEvent::AuthRevoked => {
+                    return Err(anyhow::anyhow!("event stream {} closed by server", url));
+                }
+                FirebaseEvent::KeepAlive => {}
+                event => {
+                    if !on_event(&event) {
+                        return Ok(StreamEnd::Closed);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(StreamEnd::Disconnected { received })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use serde_json::json;
+
+    #[test]
+    fn test_parser_handles_split_chunks_and_comments() {
+        let mut parser = SseParser::new();
+        assert!(parser.push(b": hello\nevent: pu").is_empty());
+        assert!(parser.push(b"t\r\ndata: {\"path\":\"/\",").is_empty());
+        let events = parser.push(b"\ndata: \"data\":1}\r\n\r\nevent: keep-alive\ndata: null\n\n");
+
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    event: "put".to_string(),
+                    data: "{\"path\":\"/\",\n\"data\":1}".to_string(),
+                },
+                SseEvent {
+                    event: "keep-alive".to_string(),
+                    data: "null".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_keeps_characters_split_across_chunks() {
+        let body = "event: patch\ndata: {\"path\":\"/\",\"data\":{\"title\":\"Café ☕\"}}\n\n";
+        // Split inside the two bytes of 'é'
+        let split = body.find('é').unwrap() + 1;
+        let mut parser = SseParser::new();
+        assert!(parser.push(&body.as_bytes()[..split]).is_empty());
+        let events = parser.push(&body.as_bytes()[split..]);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].data,
+            "{\"path\":\"/\",\"data\":{\"title\":\"Café ☕\"}}"
+        );
+    }
+
+    #[test]
+    fn test_put_and_patch_apply_at_paths() {
+        let mut doc = Value::Null;
+        let put_root = FirebaseEvent::Put {
+            path: "/".to_string(),
+            data: json!({"id": 1, "score": 1, "kids": [10, 11]}),
+        };
+        assert!(put_root.apply(&mut doc));
+
+        let put_kid = FirebaseEvent::Put {
+            path: "/kids/2".to_string(),
+            data: json!(12),
+        };
+        put_kid.apply(&mut doc);
+
+        let patch = FirebaseEvent::Patch {
+            path: "/".to_string(),
+            data: json!({"score": 5, "id": null}),
+        };
+        patch.apply(&mut doc);
+
+        assert_eq!(doc, json!({"score": 5, "kids": [10, 11, 12]}));
+        assert!(!FirebaseEvent::KeepAlive.apply(&mut doc));
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_events() {
+        let event = SseEvent {
+            event: "mystery".to_string(),
+            data: String::new(),
+        };
+        assert!(FirebaseEvent::parse(&event).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_story_list_applies_events_and_reconnects() {
+        let mut server = mockito::Server::new_async().await;
+        let body = concat!(
+            "event: put\n",
+            "data: {\"path\":\"/\",\"data\":[1,2,3]}\n\n",
+            "event: keep-alive\n",
+            "data: null\n\n",
+            "event: put\n",
+            "data: {\"path\":\"/1\",\"data\":9}\n\n",
+        );
+        let mock = server
+            .mock("GET", "/topstories.json")
+            .match_header("accept", "text/event-stream")
+            .with_status(200)
+            .with_header("content-type", "text/event-stream")
+            .with_body(body)
+            .expect_at_least(2)
+            .create();
+
+        let mut service = ApiService::with_base_url(format!("{}/", server.url()));
+        service.network_config.initial_retry_delay_ms = 10;
+
+        let token = CancellationToken::new();
+        let mut rx = service.subscribe_story_list(StoryListType::Top, token.clone());
+
+        assert_eq!(rx.next().await, Some(vec![1, 2, 3]));
+        assert_eq!(rx.next().await, Some(vec![1, 9, 3]));
+        // The body ended, so the client reconnects and receives the snapshot again
+        assert_eq!(rx.next().await, Some(vec![1, 2, 3]));
+
+        token.cancel();
+        while rx.next().await.is_some() {}
+        mock.assert();
+
+        // Stream updates keep the story id cache current
+        let cached = service
+            .fetch_story_ids(StoryListType::Top, None)
+            .await
+            .unwrap();
+        assert_eq!(cached, vec![1, 9, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_item_applies_patch() {
+        let mut server = mockito::Server::new_async().await;
+        let body = concat!(
+            "event: put\n",
+            "data: {\"path\":\"/\",\"data\":{\"id\":7,\"type\":\"story\",\"score\":1}}\n\n",
+            "event: patch\n",
+            "data: {\"path\":\"/\",\"data\":{\"score\":5,\"descendants\":2}}\n\n",
+        );
+        let _mock = server
+            .mock("GET", "/item/7.json")
+            .with_status(200)
+            .with_header("content-type", "text/event-stream")
+            .with_body(body)
+            .create();
+
+        let service = ApiService::with_base_url(format!("{}/", server.url()));
+        let token = CancellationToken::new();
+        let mut rx = service.subscribe_item(7, token.clone());
+
+        rx.next().await.expect("initial snapshot");
+        let story = rx
+            .next()
+            .await
+            .and_then(Item::into_story)
+            .expect("patched story");
+        assert_eq!(story.score, Some(5));
+        assert_eq!(story.descendants, Some(2));
+
+        token.cancel();
+    }
+}
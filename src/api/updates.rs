@@ -0,0 +1,238 @@
+//! Live change feed built on the `updates.json` and `maxitem.json` endpoints.
+//!
+//! HN publishes the ids of recently changed items and profiles. Rather than waiting
+//! for cache TTLs to lapse, the poller refreshes exactly the cached items that
+//! changed and hands them to the UI.
+
+use super::{ApiService, Priority, tokio_handle};
+use crate::internal::models::Item;
+use anyhow::{Context, Result};
+use futures::channel::mpsc;
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Body of `updates.json`.
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+pub struct Updates {
+    #[serde(default)]
+    pub items: Vec<u32>,
+    #[serde(default)]
+    pub profiles: Vec<String>,
+}
+
+/// Result of one poll of the change feed.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChangeSet {
+    /// Fresh copies of changed items that were already cached
+    pub items: Vec<Item>,
+    /// Changed profiles whose cache entries were invalidated
+    pub profiles: Vec<String>,
+    /// Largest item id known to the API
+    pub max_item: u32,
+}
+
+impl ChangeSet {
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty() && self.profiles.is_empty()
+    }
+}
+
+impl ApiService {
+    /// Fetch the ids of recently changed items and profiles.
+    #[tracing::instrument(skip(self))]
+    pub async fn fetch_updates(&self) -> Result<Updates> {
+        let url = format!("{}updates.json", self.get_base_url());
+        self.get_json(&url).await.context("fetch_updates failed")
+    }
+
+    /// Fetch the current largest item id.
+    #[tracing::instrument(skip(self))]
+    pub async fn fetch_max_item(&self) -> Result<u32> {
+        let url = format!("{}maxitem.json", self.get_base_url());
+        self.get_json(&url).await.context("fetch_max_item failed")
+    }
+
+    /// Poll the change feed once.
+    ///
+    /// Changed items that are in the item cache are re-fetched; items we have never
+    /// loaded are ignored. Changed profiles are dropped from the user cache so the
+    /// next visit loads them fresh.
+    #[tracing::instrument(skip(self))]
+    pub async fn poll_changes(&self) -> Result<ChangeSet> {
+        let (updates, max_item) = futures::try_join!(self.fetch_updates(), self.fetch_max_item())?;
+
+        let cached_ids: Vec<u32> = updates
+            .items
+            .into_iter()
+            .filter(|id| self.item_cache.contains_key(&format!("item_{}", id)))
+            .collect();
+
+        let limit = self.network_config.concurrent_requests;
+        let items: Vec<Item> = stream::iter(cached_ids)
+            .map(|id| {
+                let api = self.clone();
+                async move { api.refresh_item(id).await }
+            })
+            .buffer_unordered(limit)
+            .filter_map(|r| async move { r.ok() })
+            .collect()
+            .await;
+
+        let profiles: Vec<String> = updates
+            .profiles
+            .into_iter()
+            .filter(|id| self.user_cache.contains_key(&format!("user_{}", id)))
+            .collect();
+        // Drop the cached profiles so the next view fetches them fresh
+        for id in &profiles {
+            self.user_cache.remove(&format!("user_{}", id));
+        }
+
+        if self.enable_metrics {
+            tracing::debug!(
+                refreshed = items.len(),
+                profiles = profiles.len(),
+                max_item = max_item,
+                "Polled change feed"
+            );
+        }
+
+        Ok(ChangeSet {
+            items,
+            profiles,
+            max_item,
+        })
+    }
+
+    /// Poll the change feed every `interval` on the tokio runtime until `token` is
+    /// cancelled or the receiver is dropped. Only non-empty change sets and new
+    /// `max_item` values are sent.
+    pub fn spawn_update_poller(
+        &self,
+        interval: Duration,
+        token: CancellationToken,
+    ) -> mpsc::UnboundedReceiver<ChangeSet> {
+        let (tx, rx) = mpsc::unbounded();
+        let api = self.at_priority(Priority::Background);
+
+        tokio_handle().spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // The first tick completes immediately; skip it so data loaded at
+            // startup isn't refreshed straight away.
+            ticker.tick().await;
+            let mut last_max_item = 0;
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = token.cancelled() => break,
+                }
+
+                match api.poll_changes().await {
+                    Ok(changes) => {
+                        if changes.is_empty() && changes.max_item == last_max_item {
+                            continue;
+                        }
+                        last_max_item = changes.max_item;
+                        if tx.unbounded_send(changes).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => tracing::warn!("Change feed poll failed: {}", e),
+                }
+            }
+
+            tracing::debug!("Update poller stopped");
+        });
+
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_poll_changes_refreshes_only_cached_items() {
+        let mut server = mockito::Server::new_async().await;
+        let updates = server
+            .mock("GET", "/updates.json")
+            .with_status(200)
+            .with_body(r#"{"items": [1, 2], "profiles": ["pg", "dang"]}"#)
+            .create();
+        let max_item = server
+            .mock("GET", "/maxitem.json")
+            .with_status(200)
+            .with_body("4200")
+            .create();
+        // Fetched once to warm the cache, once more by the poll
+        let cached = server
+            .mock("GET", "/item/1.json")
+            .with_status(200)
+            .with_body(r#"{"id": 1, "type": "story", "score": 10}"#)
+            .expect(2)
+            .create();
+        let uncached = server
+            .mock("GET", "/item/2.json")
+            .with_status(200)
+            .with_body(r#"{"id": 2, "type": "story"}"#)
+            .expect(0)
+            .create();
+        let user = server
+            .mock("GET", "/user/pg.json")
+            .with_status(200)
+            .with_body(r#"{"id": "pg", "created": 1160418092, "karma": 1}"#)
+            .expect(2)
+            .create();
+
+        let service = ApiService::with_base_url(format!("{}/", server.url()));
+        service.fetch_story_content(1).await.unwrap();
+        service.fetch_user("pg").await.unwrap();
+
+        let changes = service.poll_changes().await.unwrap();
+        assert_eq!(changes.max_item, 4200);
+        assert_eq!(changes.items.len(), 1);
+        assert_eq!(changes.items[0].id(), 1);
+        assert_eq!(changes.profiles, vec!["pg".to_string()]);
+
+        // The profile was invalidated, so this goes back to the server
+        service.fetch_user("pg").await.unwrap();
+
+        updates.assert();
+        max_item.assert();
+        cached.assert();
+        uncached.assert();
+        user.assert();
+    }
+
+    #[tokio::test]
+    async fn test_update_poller_sends_changes_until_cancelled() {
+        let mut server = mockito::Server::new_async().await;
+        let _updates = server
+            .mock("GET", "/updates.json")
+            .with_status(200)
+            .with_body(r#"{"items": [], "profiles": []}"#)
+            .create();
+        let _max_item = server
+            .mock("GET", "/maxitem.json")
+            .with_status(200)
+            .with_body("99")
+            .create();
+
+        let service = ApiService::with_base_url(format!("{}/", server.url()));
+        let token = CancellationToken::new();
+        let mut rx = service.spawn_update_poller(Duration::from_millis(20), token.clone());
+
+        let changes = rx.next().await.expect("poller should report max_item");
+        assert_eq!(changes.max_item, 99);
+        assert!(changes.is_empty());
+
+        token.cancel();
+        // Unchanged polls are not sent, and cancelling closes the channel
+        assert_eq!(rx.next().await, None);
+    }
+}
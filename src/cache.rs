@@ -1,82 +1,260 @@
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock, Weak};
 use std::time::{Duration, Instant};
 
 #[derive(Clone)]
 struct CacheEntry<T> {
     value: T,
     expires_at: Instant,
+    /// Position in `Store::recency`
+    last_used: u64,
 }
 
-/// Thread-safe in-memory cache with TTL support
+struct Store<T> {
+    entries: HashMap<String, CacheEntry<T>>,
+    /// Keys by `last_used`, least recently used first
+    recency: BTreeMap<u64, String>,
+    next_use: u64,
+}
+
+impl<T> Store<T> {
+    /// Mark `key` as just used.
+    fn touch(&mut self, key: &str) {
+        let next_use = self.next_use;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = next_use;
+            self.recency.insert(next_use, key.to_string());
+            self.next_use += 1;
+        }
+    }
+
+    fn remove(&mut self, key: &str) -> Option<CacheEntry<T>> {
+        let entry = self.entries.remove(key)?;
+        self.recency.remove(&entry.last_used);
+        Some(entry)
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stale_hits: AtomicU64,
+    evictions: AtomicU64,
+    expirations: AtomicU64,
+}
+
+/// Snapshot of a cache's size and counters since it was created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    pub len: usize,
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Expired entries handed out by `get_stale`
+    pub stale_hits: u64,
+    /// Entries dropped to stay within capacity
+    pub evictions: u64,
+    /// Expired entries dropped by `cleanup`
+    pub expirations: u64,
+}
+
+impl CacheStats {
+    /// Share of lookups answered from the cache, from 0.0 to 1.0
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        match lookups {
+            0 => 0.0,
+            _ => self.hits as f64 / lookups as f64,
+        }
+    }
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} entries, {:.0}% hits, {} stale, {} evicted",
+            self.len,
+            self.capacity,
+            self.hit_rate() * 100.0,
+            self.stale_hits,
+            self.evictions
+        )
+    }
+}
+
+/// Thread-safe in-memory cache with TTL support, holding at most `capacity`
+/// entries. The least recently used entry is evicted to make room.
 #[derive(Clone)]
 pub struct Cache<T: Clone> {
-    store: Arc<RwLock<HashMap<String, CacheEntry<T>>>>,
+    inner: Arc<Inner<T>>,
+}
+
+struct Inner<T> {
+    store: RwLock<Store<T>>,
+    counters: Counters,
     ttl: Duration,
+    capacity: usize,
 }
 
 impl<T: Clone> Cache<T> {
-    /// Create a new cache with the specified TTL in seconds
+    /// Create a new unbounded cache with the specified TTL in seconds
     pub fn new(ttl_seconds: u64) -> Self {
+        Self::with_capacity(ttl_seconds, usize::MAX)
+    }
+
+    /// Create a new cache with the specified TTL in seconds, holding at most `capacity` entries
+    pub fn with_capacity(ttl_seconds: u64, capacity: usize) -> Self {
         Self {
-            store: Arc::new(RwLock::new(HashMap::new())),
-            ttl: Duration::from_secs(ttl_seconds),
+            inner: Arc::new(Inner {
+                store: RwLock::new(Store {
+                    entries: HashMap::new(),
+                    recency: BTreeMap::new(),
+                    next_use: 0,
+                }),
+                counters: Counters::default(),
+                ttl: Duration::from_secs(ttl_seconds),
+                capacity: capacity.max(1),
+            }),
         }
     }
 
+    fn count(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
     /// Get a value from the cache if it exists and hasn't expired
     pub fn get(&self, key: &str) -> Option<T> {
-        let store = self.store.read().ok()?;
-        let entry = store.get(key)?;
-
-        if entry.expires_at > Instant::now() {
-            Some(entry.value.clone())
-        } else {
-            // Entry has expired, we'll clean it up later
-            None
+        let Ok(mut store) = self.inner.store.write() else {
+            return None;
+        };
+
+        match store.entries.get(key) {
+            Some(entry) if entry.expires_at > Instant::now() => {
+                let value = entry.value.clone();
+                store.touch(key);
+                Self::count(&self.inner.counters.hits);
+                Some(value)
+            }
+            // Expired entries stay for `get_stale` until the next cleanup
+            _ => {
+                Self::count(&self.inner.counters.misses);
+                None
+            }
         }
     }
 
-    /// Get a value from the cache even if it has expired
+    /// Get a value from the cache even if it has expired.
+    ///
+    /// Meant as a fallback after `get` missed, so a missing key isn't counted again.
     pub fn get_stale(&self, key: &str) -> Option<T> {
-        let store = self.store.read().ok()?;
-        let entry = store.get(key)?;
-        Some(entry.value.clone())
+        let mut store = self.inner.store.write().ok()?;
+        let entry = store.entries.get(key)?;
+
+        let value = entry.value.clone();
+        match entry.expires_at > Instant::now() {
+            true => Self::count(&self.inner.counters.hits),
+            false => Self::count(&self.inner.counters.stale_hits),
+        }
+        store.touch(key);
+        Some(value)
     }
 
-    /// Insert a value into the cache with TTL
+    /// Insert a value into the cache with TTL, evicting the least recently used
+    /// entries beyond capacity
     pub fn insert(&self, key: String, value: T) {
-        let entry = CacheEntry {
-            value,
-            expires_at: Instant::now() + self.ttl,
+        let Ok(mut store) = self.inner.store.write() else {
+            return;
         };
 
-        if let Ok(mut store) = self.store.write() {
-            store.insert(key, entry);
+        store.remove(&key);
+        let last_used = store.next_use;
+        store.next_use += 1;
+        store.recency.insert(last_used, key.clone());
+        store.entries.insert(
+            key,
+            CacheEntry {
+                value,
+                expires_at: Instant::now() + self.inner.ttl,
+                last_used,
+            },
+        );
+
+        while store.entries.len() > self.inner.capacity {
+            let Some((_, oldest)) = store.recency.pop_first() else {
+                break;
+            };
+            store.entries.remove(&oldest);
+            Self::count(&self.inner.counters.evictions);
+        }
+    }
+
+    /// Check whether a key is present, even if its entry has expired
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.inner
+            .store
+            .read()
+            .map(|s| s.entries.contains_key(key))
+            .unwrap_or(false)
+    }
+
+    /// Remove a single entry from the cache
+    pub fn remove(&self, key: &str) {
+        if let Ok(mut store) = self.inner.store.write() {
+            store.remove(key);
         }
     }
 
     /// Remove expired entries from the cache
-    #[allow(dead_code)]
     pub fn cleanup(&self) {
-        if let Ok(mut store) = self.store.write() {
+        self.cleanup_after(Duration::ZERO);
+    }
+
+    /// Remove entries that expired more than `grace` ago, keeping the rest for `get_stale`
+    pub fn cleanup_after(&self, grace: Duration) {
+        if let Ok(mut store) = self.inner.store.write() {
             let now = Instant::now();
-            store.retain(|_, entry| entry.expires_at > now);
+            let expired: Vec<String> = store
+                .entries
+                .iter()
+                .filter(|(_, entry)| {
+                    entry
+                        .expires_at
+                        .checked_add(grace)
+                        .is_some_and(|gone_at| gone_at <= now)
+                })
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in &expired {
+                store.remove(key);
+            }
+            self.inner
+                .counters
+                .expirations
+                .fetch_add(expired.len() as u64, Ordering::Relaxed);
         }
     }
 
     /// Clear all entries from the cache
-    #[allow(dead_code)]
     pub fn clear(&self) {
-        if let Ok(mut store) = self.store.write() {
-            store.clear();
+        if let Ok(mut store) = self.inner.store.write() {
+            store.entries.clear();
+            store.recency.clear();
         }
     }
 
     /// Get the number of entries in the cache (including expired)
     #[allow(dead_code)]
     pub fn len(&self) -> usize {
-        self.store.read().map(|s| s.len()).unwrap_or(0)
+        self.inner
+            .store
+            .read()
+            .map(|s| s.entries.len())
+            .unwrap_or(0)
     }
 
     /// Check if the cache is empty
@@ -84,6 +262,40 @@ impl<T: Clone> Cache<T> {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Current size and counters
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            len: self.len(),
+            capacity: self.inner.capacity,
+            hits: self.inner.counters.hits.load(Ordering::Relaxed),
+            misses: self.inner.counters.misses.load(Ordering::Relaxed),
+            stale_hits: self.inner.counters.stale_hits.load(Ordering::Relaxed),
+            evictions: self.inner.counters.evictions.load(Ordering::Relaxed),
+            expirations: self.inner.counters.expirations.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> Cache<T> {
+    /// Every `interval`, remove entries that expired more than `grace` ago, until the
+    /// last clone of this cache is dropped
+    pub fn sweep_periodically(
+        &self,
+        interval: Duration,
+        grace: Duration,
+    ) -> impl Future<Output = ()> + use<T> {
+        let inner: Weak<Inner<T>> = Arc::downgrade(&self.inner);
+        async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(inner) = inner.upgrade() else {
+                    break;
+                };
+                Cache { inner }.cleanup_after(grace);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -144,4 +356,82 @@ mod tests {
 
         assert_eq!(cache.len(), 0);
     }
+
+    #[test]
+    fn test_cache_contains_and_remove() {
+        let cache = Cache::new(300);
+        cache.insert("key1".to_string(), "value1".to_string());
+
+        assert!(cache.contains_key("key1"));
+        cache.remove("key1");
+        assert!(!cache.contains_key("key1"));
+        assert_eq!(cache.get_stale("key1"), None);
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let cache = Cache::with_capacity(300, 2);
+        cache.insert("key1".to_string(), "value1".to_string());
+        cache.insert("key2".to_string(), "value2".to_string());
+
+        // Using key1 makes key2 the least recently used
+        assert!(cache.get("key1").is_some());
+        cache.insert("key3".to_string(), "value3".to_string());
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains_key("key1"));
+        assert!(!cache.contains_key("key2"));
+        assert!(cache.contains_key("key3"));
+        assert_eq!(cache.stats().evictions, 1);
+
+        // Replacing an entry doesn't evict anything
+        cache.insert("key3".to_string(), "value3b".to_string());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_cache_stats_count_lookups() {
+        let cache = Cache::new(0); // Entries expire immediately
+        cache.insert("key1".to_string(), "value1".to_string());
+
+        assert_eq!(cache.get("key1"), None);
+        assert_eq!(cache.get("key2"), None);
+        assert_eq!(cache.get_stale("key1"), Some("value1".to_string()));
+        cache.cleanup();
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 0);
+        assert_eq!(stats.misses, 2);
+        assert_eq!(stats.stale_hits, 1);
+        assert_eq!(stats.expirations, 1);
+        assert_eq!(stats.len, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cache_sweeps_expired_entries() {
+        let cache = Cache::new(1);
+        cache.insert("key1".to_string(), "value1".to_string());
+        tokio::spawn(cache.sweep_periodically(Duration::from_secs(60), Duration::ZERO));
+
+        // The TTL uses the real clock, so let the entry expire first
+        thread::sleep(Duration::from_millis(1100));
+        tokio::time::sleep(Duration::from_secs(61)).await;
+
+        assert!(cache.is_empty());
+        assert_eq!(cache.stats().expirations, 1);
+    }
+
+    #[test]
+    fn test_sweep_keeps_stale_entries_within_grace() {
+        let cache = Cache::new(0);
+        cache.insert("key1".to_string(), "value1".to_string());
+        assert_eq!(cache.get("key1"), None);
+
+        cache.cleanup_after(Duration::from_secs(3600));
+        assert_eq!(cache.get_stale("key1"), Some("value1".to_string()));
+
+        cache.cleanup_after(Duration::ZERO);
+        assert_eq!(cache.get_stale("key1"), None);
+    }
 }
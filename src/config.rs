@@ -2,12 +2,14 @@ use regex::RegexBuilder;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
+use strum_macros::Display;
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
 pub enum Action {
     Quit,
     Back,
     FocusSearch,
+    SearchHackerNews,
     CycleSearchMode,
     CycleSortOption,
     ToggleSortOrder,
@@ -18,14 +20,45 @@ pub enum Action {
     ShowBookmarks,
     ShowHistory,
     ClearHistory,
+    ClearCache,
     OpenThemeEditor,
     ShowLogViewer,
     ShowKeyboardHelp,
+    ToggleLinkIndex,
+    /// Open link N of the open story in the app: HN items natively, other pages
+    /// in the webview
+    OpenLink(usize),
+    /// Open link N of the open story in the system browser
+    OpenLinkInBrowser(usize),
+    /// Copy link N of the open story to the clipboard
+    CopyLink(usize),
     None,
 }
 
 pub type KeyMap = std::collections::HashMap<String, Action>;
 
+/// Where the comments of an opened story come from.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CommentSource {
+    /// One Firebase request per comment, level by level
+    #[default]
+    Firebase,
+    /// The whole thread in one request to the Algolia `items` endpoint, falling back
+    /// to Firebase if that fails
+    Algolia,
+}
+
+/// A web archive that copies of unreadable linked pages are looked up in.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Display)]
+pub enum ArchiveSource {
+    /// The Internet Archive, found through its availability API
+    #[strum(to_string = "Wayback Machine")]
+    Wayback,
+    /// archive.today, whose newest copy of a page has a fixed URL
+    #[strum(to_string = "archive.today")]
+    ArchiveToday,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct UiConfig {
     #[serde(default = "default_padding")]
@@ -64,14 +97,70 @@ impl Default for UiConfig {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// Network settings, used both for the `network:` block of `config.ron` and by `ApiService`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct NetworkConfig {
+    /// Maximum number of retry attempts (0 = no retries)
     #[serde(default = "default_max_retries")]
     pub max_retries: u32,
+    /// Initial retry delay in milliseconds
     #[serde(default = "default_initial_retry_delay_ms")]
     pub initial_retry_delay_ms: u64,
+    /// Maximum retry delay in milliseconds (caps exponential backoff)
     #[serde(default = "default_max_retry_delay_ms")]
     pub max_retry_delay_ms: u64,
+    /// Whether to retry on timeout errors
+    #[serde(default = "default_retry_on_timeout")]
+    pub retry_on_timeout: bool,
+    /// Maximum number of concurrent requests
+    #[serde(default = "default_concurrent_requests")]
+    pub concurrent_requests: usize,
+    /// Rate limit in requests per second
+    #[serde(default = "default_rate_limit_per_second")]
+    pub rate_limit_per_second: f64,
+    /// Number of requests that may be sent back to back before the rate limit applies
+    #[serde(default = "default_rate_limit_burst")]
+    pub rate_limit_burst: u32,
+    /// Seconds before a whole request times out (0 = no timeout)
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+    /// Seconds before connecting to a server times out (0 = no timeout)
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// HTTP, HTTPS or SOCKS5 proxy URL for all requests (e.g. "socks5h://127.0.0.1:9050")
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// User-Agent header sent with every request
+    #[serde(default = "default_user_agent")]
+    pub user_agent: String,
+    /// Alternate Firebase API base URL, for mirrors or a local fake
+    #[serde(default)]
+    pub api_base_url: Option<String>,
+    /// Serve API responses from this directory instead of the network
+    #[serde(default)]
+    pub replay_dir: Option<String>,
+    /// With `replay_dir`, fetch live and save every response there instead
+    #[serde(default)]
+    pub record_responses: bool,
+    /// Seconds between polls of the HN change feed (0 disables live updates)
+    #[serde(default = "default_update_poll_interval_secs")]
+    pub update_poll_interval_secs: u64,
+    /// Keep server-sent event streams open for the current list and story
+    #[serde(default = "default_stream_updates")]
+    pub stream_updates: bool,
+    /// Where to load comment threads from
+    #[serde(default)]
+    pub comment_source: CommentSource,
+    /// Largest linked article body that is downloaded, in bytes
+    #[serde(default = "default_article_max_bytes")]
+    pub article_max_bytes: usize,
+    /// Number of redirects followed when fetching a linked article
+    #[serde(default = "default_article_max_redirects")]
+    pub article_max_redirects: usize,
+    /// Archives tried in order for a linked page that fails to load or looks like
+    /// a paywall stub (empty disables the fallback)
+    #[serde(default = "default_archive_sources")]
+    pub archive_sources: Vec<ArchiveSource>,
 }
 
 fn default_max_retries() -> u32 {
@@ -79,11 +168,59 @@ fn default_max_retries() -> u32 {
 }
 
 fn default_initial_retry_delay_ms() -> u64 {
-    1000
+    500
 }
 
 fn default_max_retry_delay_ms() -> u64 {
-    30000
+    5000
+}
+
+fn default_retry_on_timeout() -> bool {
+    true
+}
+
+fn default_concurrent_requests() -> usize {
+    10
+}
+
+fn default_rate_limit_per_second() -> f64 {
+    3.0
+}
+
+fn default_rate_limit_burst() -> u32 {
+    3
+}
+
+fn default_request_timeout_secs() -> u64 {
+    30
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    10
+}
+
+fn default_user_agent() -> String {
+    concat!("gpui-hn-app/", env!("CARGO_PKG_VERSION")).to_string()
+}
+
+fn default_update_poll_interval_secs() -> u64 {
+    30
+}
+
+fn default_stream_updates() -> bool {
+    true
+}
+
+fn default_article_max_bytes() -> usize {
+    5 * 1024 * 1024
+}
+
+fn default_article_max_redirects() -> usize {
+    10
+}
+
+fn default_archive_sources() -> Vec<ArchiveSource> {
+    vec![ArchiveSource::Wayback, ArchiveSource::ArchiveToday]
 }
 
 impl Default for NetworkConfig {
@@ -92,6 +229,114 @@ impl Default for NetworkConfig {
             max_retries: default_max_retries(),
             initial_retry_delay_ms: default_initial_retry_delay_ms(),
             max_retry_delay_ms: default_max_retry_delay_ms(),
+            retry_on_timeout: default_retry_on_timeout(),
+            concurrent_requests: default_concurrent_requests(),
+            rate_limit_per_second: default_rate_limit_per_second(),
+            rate_limit_burst: default_rate_limit_burst(),
+            request_timeout_secs: default_request_timeout_secs(),
+            connect_timeout_secs: default_connect_timeout_secs(),
+            proxy: None,
+            user_agent: default_user_agent(),
+            api_base_url: None,
+            replay_dir: None,
+            record_responses: false,
+            update_poll_interval_secs: default_update_poll_interval_secs(),
+            stream_updates: default_stream_updates(),
+            comment_source: CommentSource::default(),
+            article_max_bytes: default_article_max_bytes(),
+            article_max_redirects: default_article_max_redirects(),
+            archive_sources: default_archive_sources(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PrefetchConfig {
+    /// Turn off background prefetching to save data
+    #[serde(default)]
+    pub data_saver: bool,
+    /// Number of top visible stories whose first page of comments is prefetched
+    #[serde(default = "default_prefetch_comment_stories")]
+    pub comment_stories: usize,
+    /// Number of top visible stories whose linked article is prefetched
+    #[serde(default = "default_prefetch_article_stories")]
+    pub article_stories: usize,
+    /// Maximum number of prefetch requests per minute; 0 disables prefetching
+    #[serde(default = "default_prefetch_budget_per_minute")]
+    pub budget_per_minute: u32,
+}
+
+fn default_prefetch_comment_stories() -> usize {
+    5
+}
+
+fn default_prefetch_article_stories() -> usize {
+    3
+}
+
+fn default_prefetch_budget_per_minute() -> u32 {
+    60
+}
+
+impl Default for PrefetchConfig {
+    fn default() -> Self {
+        Self {
+            data_saver: false,
+            comment_stories: default_prefetch_comment_stories(),
+            article_stories: default_prefetch_article_stories(),
+            budget_per_minute: default_prefetch_budget_per_minute(),
+        }
+    }
+}
+
+/// On-disk cache of story lists, items and articles, kept between launches.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct CacheConfig {
+    /// Keep fetched data on disk and show it at startup
+    #[serde(default = "default_cache_enabled")]
+    pub enabled: bool,
+    /// Seconds before a cached story list is refreshed in the background
+    #[serde(default = "default_cache_story_ids_ttl_secs")]
+    pub story_ids_ttl_secs: u64,
+    /// Seconds before a cached story or comment is refreshed in the background
+    #[serde(default = "default_cache_item_ttl_secs")]
+    pub item_ttl_secs: u64,
+    /// Seconds before a cached article is refreshed in the background
+    #[serde(default = "default_cache_article_ttl_secs")]
+    pub article_ttl_secs: u64,
+    /// Maximum size of the cache file in megabytes; the oldest entries go first
+    #[serde(default = "default_cache_max_size_mb")]
+    pub max_size_mb: u64,
+}
+
+fn default_cache_enabled() -> bool {
+    true
+}
+
+fn default_cache_story_ids_ttl_secs() -> u64 {
+    300
+}
+
+fn default_cache_item_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_cache_article_ttl_secs() -> u64 {
+    86400
+}
+
+fn default_cache_max_size_mb() -> u64 {
+    50
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_cache_enabled(),
+            story_ids_ttl_secs: default_cache_story_ids_ttl_secs(),
+            item_ttl_secs: default_cache_item_ttl_secs(),
+            article_ttl_secs: default_cache_article_ttl_secs(),
+            max_size_mb: default_cache_max_size_mb(),
         }
     }
 }
@@ -174,6 +419,10 @@ pub struct AppConfig {
     /// Set to 0 to disable the soft-wrap insertion behavior.
     #[serde(default = "default_soft_wrap_max_run")]
     pub soft_wrap_max_run: usize,
+    /// Deepest level of replies loaded with a thread (top-level comments are level 0).
+    /// Deeper replies are loaded on demand.
+    #[serde(default = "default_comment_max_depth")]
+    pub comment_max_depth: u32,
     /// Window width in pixels
     #[serde(default = "default_window_width")]
     pub window_width: f32,
@@ -189,6 +438,12 @@ pub struct AppConfig {
     /// Network Configuration
     #[serde(default)]
     pub network: NetworkConfig,
+    /// Background prefetching of comments and articles
+    #[serde(default)]
+    pub prefetch: PrefetchConfig,
+    /// On-disk cache between launches
+    #[serde(default)]
+    pub cache: CacheConfig,
     /// Logging Configuration
     #[serde(default)]
     pub log: LogConfig,
@@ -217,6 +472,10 @@ fn default_soft_wrap_max_run() -> usize {
     20
 }
 
+fn default_comment_max_depth() -> u32 {
+    3
+}
+
 fn default_window_width() -> f32 {
     980.0
 }
@@ -238,6 +497,7 @@ fn default_keybindings() -> KeyMap {
     map.insert("q".to_string(), Action::Quit);
     map.insert("escape".to_string(), Action::Back);
     map.insert("ctrl+r".to_string(), Action::FocusSearch);
+    map.insert("ctrl+enter".to_string(), Action::SearchHackerNews);
     map.insert("ctrl+m".to_string(), Action::CycleSearchMode);
     map.insert("ctrl+s".to_string(), Action::CycleSortOption);
     map.insert("o".to_string(), Action::ToggleSortOrder);
@@ -248,10 +508,20 @@ fn default_keybindings() -> KeyMap {
     map.insert("shift+b".to_string(), Action::ShowBookmarks);
     map.insert("shift+h".to_string(), Action::ShowHistory);
     map.insert("shift+x".to_string(), Action::ClearHistory);
+    map.insert("shift+c".to_string(), Action::ClearCache);
     map.insert("t".to_string(), Action::OpenThemeEditor);
     map.insert("shift+l".to_string(), Action::ShowLogViewer);
     map.insert("shift+?".to_string(), Action::ShowKeyboardHelp);
     map.insert("?".to_string(), Action::ShowKeyboardHelp);
+    map.insert("l".to_string(), Action::ToggleLinkIndex);
+    for number in 1..=9 {
+        map.insert(number.to_string(), Action::OpenLink(number));
+        map.insert(
+            format!("ctrl+{}", number),
+            Action::OpenLinkInBrowser(number),
+        );
+        map.insert(format!("alt+{}", number), Action::CopyLink(number));
+    }
     map
 }
 
@@ -268,11 +538,14 @@ impl Default for AppConfig {
             webview_theme_mode: default_webview_theme_mode(),
             webview_trusted_domains: Vec::new(),
             soft_wrap_max_run: default_soft_wrap_max_run(),
+            comment_max_depth: default_comment_max_depth(),
             window_width: 980.0,
             window_height: 720.0,
             keybindings: default_keybindings(),
             ui: Default::default(),
             network: Default::default(),
+            prefetch: Default::default(),
+            cache: Default::default(),
             log: Default::default(),
             accessibility: Default::default(),
         }
@@ -514,4 +787,73 @@ mod tests {
         // Cleanup
         let _ = fs::remove_file(config_path);
     }
+
+    #[test]
+    fn test_network_block_fills_missing_fields_with_defaults() {
+        let config: AppConfig = ron::from_str(
+            r#"(
+    font_sans: "Sans",
+    font_serif: "Serif",
+    font_mono: "Mono",
+    network: (
+        max_retries: 5,
+        proxy: Some("socks5h://127.0.0.1:9050"),
+        api_base_url: Some("http://localhost:8080/v0/"),
+        comment_source: Algolia,
+        archive_sources: [ArchiveToday],
+    ),
+)"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.network.max_retries, 5);
+        assert_eq!(
+            config.network.proxy.as_deref(),
+            Some("socks5h://127.0.0.1:9050")
+        );
+        assert_eq!(
+            config.network.api_base_url.as_deref(),
+            Some("http://localhost:8080/v0/")
+        );
+        assert_eq!(
+            config.network.rate_limit_per_second,
+            default_rate_limit_per_second()
+        );
+        assert_eq!(config.network.user_agent, default_user_agent());
+        // The retry delays ApiService used before it read this config
+        assert_eq!(config.network.initial_retry_delay_ms, 500);
+        assert_eq!(config.network.max_retry_delay_ms, 5000);
+        assert_eq!(config.network.comment_source, CommentSource::Algolia);
+        assert_eq!(
+            config.network.archive_sources,
+            vec![ArchiveSource::ArchiveToday]
+        );
+        assert_eq!(
+            NetworkConfig::default().archive_sources,
+            default_archive_sources()
+        );
+    }
+
+    #[test]
+    fn test_link_actions_take_the_link_number() {
+        let config: AppConfig = ron::from_str(
+            r#"(
+    font_sans: "Sans",
+    font_serif: "Serif",
+    font_mono: "Mono",
+    keybindings: {
+        "f": OpenLink(12),
+        "ctrl+y": CopyLink(2),
+    },
+)"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.keybindings.get("f"), Some(&Action::OpenLink(12)));
+        assert_eq!(config.keybindings.get("ctrl+y"), Some(&Action::CopyLink(2)));
+        assert_eq!(
+            default_keybindings().get("ctrl+3"),
+            Some(&Action::OpenLinkInBrowser(3))
+        );
+    }
 }
@@ -0,0 +1,455 @@
+//! Persistent cache of API responses, kept in a single JSON file.
+//!
+//! Story id lists, items and article pages are stored as the raw response body
+//! together with the time they were fetched. Entries outlive their TTL: a stale
+//! entry is still handed out, so the app can show it at once while the caller
+//! fetches a fresh copy in the background.
+
+use crate::config::CacheConfig;
+use anyhow::{Context, Result};
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::time::Duration;
+
+/// What an entry holds, which decides its TTL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntryKind {
+    StoryIds,
+    Item,
+    Article,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DiskEntry {
+    kind: EntryKind,
+    stored_at: Timestamp,
+    body: String,
+}
+
+impl DiskEntry {
+    fn size(&self, key: &str) -> usize {
+        key.len() + self.body.len()
+    }
+}
+
+/// A cached response body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskHit {
+    pub body: String,
+    /// The entry is older than the TTL of its kind and should be revalidated
+    pub stale: bool,
+}
+
+struct Store {
+    entries: HashMap<String, Arc<DiskEntry>>,
+    /// Total size of keys and bodies in bytes
+    size: usize,
+    /// Keys stored or removed since the last flush
+    dirty: HashSet<String>,
+}
+
+/// Disk-backed response cache. Clones share the same store.
+///
+/// Changes are kept in memory until [`DiskCache::flush`], which rewrites the file.
+/// Entries are shared with the copy that is written, so a flush only has to take
+/// the changed ones from the store.
+#[derive(Clone)]
+pub struct DiskCache {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    path: PathBuf,
+    config: CacheConfig,
+    store: Mutex<Store>,
+    /// Contents of the file as of the last flush. Held while flushing, so
+    /// flushes write one at a time.
+    written: Mutex<HashMap<String, Arc<DiskEntry>>>,
+}
+
+impl DiskCache {
+    /// Default location of the cache file in the user's cache directory.
+    pub fn default_path() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("gpui-hn-app")
+            .join("cache.json")
+    }
+
+    /// Open the cache stored at `path`. A missing or unreadable file gives an empty cache.
+    pub fn open(path: impl Into<PathBuf>, config: CacheConfig) -> Self {
+        let path = path.into();
+        let entries: HashMap<String, Arc<DiskEntry>> = match Self::read_entries(&path) {
+            Ok(entries) => entries
+                .into_iter()
+                .map(|(key, entry)| (key, Arc::new(entry)))
+                .collect(),
+            Err(e) => {
+                tracing::warn!("Starting with an empty disk cache: {:#}", e);
+                HashMap::new()
+            }
+        };
+        let size = entries.iter().map(|(key, entry)| entry.size(key)).sum();
+        tracing::info!(
+            "Loaded {} cached responses ({} KiB) from {}",
+            entries.len(),
+            size / 1024,
+            path.display()
+        );
+
+        let cache = Self {
+            inner: Arc::new(Inner {
+                path,
+                config,
+                written: Mutex::new(entries.clone()),
+                store: Mutex::new(Store {
+                    entries,
+                    size,
+                    dirty: HashSet::new(),
+                }),
+            }),
+        };
+        // The size limit may have been lowered since the file was written
+        cache.evict(&mut cache.lock());
+        cache
+    }
+
+    fn read_entries(path: &Path) -> Result<HashMap<String, DiskEntry>> {
+        if !path.exists() {
+            return Ok(HashMap::new());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Store> {
+        self.inner.store.lock().expect("disk cache lock poisoned")
+    }
+
+    fn ttl(&self, kind: EntryKind) -> Duration {
+        let secs = match kind {
+            EntryKind::StoryIds => self.inner.config.story_ids_ttl_secs,
+            EntryKind::Item => self.inner.config.item_ttl_secs,
+            EntryKind::Article => self.inner.config.article_ttl_secs,
+        };
+        Duration::from_secs(secs)
+    }
+
+    /// Look up `key`, whether fresh or stale.
+    pub fn get(&self, key: &str) -> Option<DiskHit> {
+        let store = self.lock();
+        let entry = store.entries.get(key)?;
+        // An entry from the future, after the clock was turned back, counts as fresh
+        let age = Duration::try_from(Timestamp::now().duration_since(entry.stored_at))
+            .unwrap_or_default();
+        let stale = age >= self.ttl(entry.kind);
+        Some(DiskHit {
+            body: entry.body.clone(),
+            stale,
+        })
+    }
+
+    /// Store the response `body` under `key`, evicting the oldest entries if the
+    /// cache grows past its size limit.
+    pub fn insert(&self, kind: EntryKind, key: &str, body: &str) {
+        let entry = DiskEntry {
+            kind,
+            stored_at: Timestamp::now(),
+            body: body.to_string(),
+        };
+        if entry.size(key) > self.max_size() {
+            return;
+        }
+
+        let mut store = self.lock();
+        store.size += entry.size(key);
+        if let Some(old) = store.entries.insert(key.to_string(), Arc::new(entry)) {
+            store.size -= old.size(key);
+        }
+        store.dirty.insert(key.to_string());
+        self.evict(&mut store);
+    }
+
+    fn max_size(&self) -> usize {
+        usize::try_from(self.inner.config.max_size_mb.saturating_mul(1024 * 1024))
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Drop the oldest entries until the store fits the size limit.
+    fn evict(&self, store: &mut Store) {
+        let max_size = self.max_size();
+        if store.size <= max_size {
+            return;
+        }
+
+        let mut by_age: Vec<(Timestamp, String)> = store
+            .entries
+            .iter()
+            .map(|(key, entry)| (entry.stored_at, key.clone()))
+            .collect();
+        by_age.sort();
+
+        let mut evicted = 0;
+        for (_, key) in by_age {
+            if store.size <= max_size {
+                break;
+            }
+            if let Some(entry) = store.entries.remove(&key) {
+                store.size -= entry.size(&key);
+                store.dirty.insert(key);
+                evicted += 1;
+            }
+        }
+        tracing::debug!("Evicted {} entries from the disk cache", evicted);
+    }
+
+    /// Remove every entry and write the empty cache to disk.
+    pub fn clear(&self) -> Result<()> {
+        {
+            let mut store = self.lock();
+            let keys: Vec<String> = store.entries.drain().map(|(key, _)| key).collect();
+            store.dirty.extend(keys);
+            store.size = 0;
+        }
+        self.flush()
+    }
+
+    /// Number of cached entries.
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Check if the cache is empty
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Total size of cached keys and bodies in bytes.
+    #[allow(dead_code)]
+    pub fn size_bytes(&self) -> usize {
+        self.lock().size
+    }
+
+    /// Whether anything changed since the last flush.
+    pub fn is_dirty(&self) -> bool {
+        !self.lock().dirty.is_empty()
+    }
+
+    /// Write the cache file if anything changed since the last flush.
+    ///
+    /// This blocks on serializing and writing the whole file, so async callers
+    /// should run it on a blocking thread.
+    pub fn flush(&self) -> Result<()> {
+        let mut written = self.inner.written.lock().expect("disk cache lock poisoned");
+
+        // Only the changed entries are taken under the store lock
+        let changes: Vec<(String, Option<Arc<DiskEntry>>)> = {
+            let mut store = self.lock();
+            let dirty = std::mem::take(&mut store.dirty);
+            dirty
+                .into_iter()
+                .map(|key| {
+                    let entry = store.entries.get(&key).cloned();
+                    (key, entry)
+                })
+                .collect()
+        };
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        for (key, entry) in &changes {
+            match entry {
+                Some(entry) => written.insert(key.clone(), entry.clone()),
+                None => written.remove(key),
+            };
+        }
+
+        let entries: HashMap<&str, &DiskEntry> = written
+            .iter()
+            .map(|(key, entry)| (key.as_str(), entry.as_ref()))
+            .collect();
+        let result = serde_json::to_string(&entries)
+            .context("failed to serialize disk cache")
+            .and_then(|content| self.write(&content));
+        if result.is_err() {
+            // Try again on the next flush
+            self.lock()
+                .dirty
+                .extend(changes.into_iter().map(|(key, _)| key));
+        }
+        result
+    }
+
+    /// Replace the file through a temporary file, so a crash never leaves it half written.
+    fn write(&self, content: &str) -> Result<()> {
+        if let Some(dir) = self.inner.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        let tmp = self.inner.path.with_extension("json.tmp");
+        fs::write(&tmp, content).with_context(|| format!("failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, &self.inner.path)
+            .with_context(|| format!("failed to replace {}", self.inner.path.display()))
+    }
+
+    /// Flush every `interval` until the last clone of this cache is dropped.
+    pub fn flush_periodically(&self, interval: Duration) -> impl Future<Output = ()> + use<> {
+        let inner: Weak<Inner> = Arc::downgrade(&self.inner);
+        async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(inner) = inner.upgrade() else {
+                    break;
+                };
+                let cache = DiskCache { inner };
+                if !cache.is_dirty() {
+                    continue;
+                }
+                // Keep the serialization and file write off the runtime's workers
+                match tokio::task::spawn_blocking(move || cache.flush()).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => tracing::warn!("Failed to save disk cache: {:#}", e),
+                    Err(e) => tracing::warn!("Disk cache flush task failed: {}", e),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("gpui_hn_test_disk_cache_{}", std::process::id()));
+        let path = dir.join(format!("{}.json", name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_entries_survive_reopening() {
+        let path = temp_path("reopen");
+        let cache = DiskCache::open(&path, CacheConfig::default());
+        cache.insert(EntryKind::StoryIds, "story_ids_topstories", "[1,2,3]");
+        cache.insert(EntryKind::Item, "item_1", r#"{"id":1}"#);
+        cache.flush().unwrap();
+
+        let reopened = DiskCache::open(&path, CacheConfig::default());
+        assert_eq!(reopened.len(), 2);
+        assert_eq!(
+            reopened.get("story_ids_topstories"),
+            Some(DiskHit {
+                body: "[1,2,3]".to_string(),
+                stale: false,
+            })
+        );
+        assert_eq!(reopened.get("item_2"), None);
+    }
+
+    #[test]
+    fn test_expired_entries_are_served_stale() {
+        let path = temp_path("stale");
+        let cache = DiskCache::open(
+            &path,
+            CacheConfig {
+                item_ttl_secs: 0,
+                ..CacheConfig::default()
+            },
+        );
+        cache.insert(EntryKind::Item, "item_1", r#"{"id":1}"#);
+        cache.insert(EntryKind::StoryIds, "story_ids_newstories", "[1]");
+
+        assert!(cache.get("item_1").unwrap().stale);
+        assert!(!cache.get("story_ids_newstories").unwrap().stale);
+    }
+
+    #[test]
+    fn test_size_limit_evicts_oldest_entries() {
+        let path = temp_path("evict");
+        let cache = DiskCache::open(
+            &path,
+            CacheConfig {
+                max_size_mb: 1,
+                ..CacheConfig::default()
+            },
+        );
+        let body = "x".repeat(400 * 1024);
+        cache.insert(EntryKind::Article, "article_a", &body);
+        cache.insert(EntryKind::Article, "article_b", &body);
+        cache.insert(EntryKind::Article, "article_c", &body);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("article_a").is_none());
+        assert!(cache.size_bytes() <= 1024 * 1024);
+
+        // Bodies larger than the whole cache are not stored
+        cache.insert(
+            EntryKind::Article,
+            "article_d",
+            &"x".repeat(2 * 1024 * 1024),
+        );
+        assert!(cache.get("article_d").is_none());
+    }
+
+    #[test]
+    fn test_clear_empties_the_file() {
+        let path = temp_path("clear");
+        let cache = DiskCache::open(&path, CacheConfig::default());
+        cache.insert(EntryKind::Item, "item_1", r#"{"id":1}"#);
+        cache.flush().unwrap();
+
+        cache.clear().unwrap();
+        assert!(cache.is_empty());
+        assert!(DiskCache::open(&path, CacheConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_flush_writes_only_changes() {
+        let path = temp_path("changes");
+        let cache = DiskCache::open(
+            &path,
+            CacheConfig {
+                max_size_mb: 1,
+                ..CacheConfig::default()
+            },
+        );
+        cache.flush().unwrap();
+        assert!(!path.exists());
+
+        let body = "x".repeat(400 * 1024);
+        cache.insert(EntryKind::Article, "article_a", &body);
+        cache.insert(EntryKind::Article, "article_b", &body);
+        assert!(cache.is_dirty());
+        cache.flush().unwrap();
+        assert!(!cache.is_dirty());
+
+        // Evicts article_a, which has to leave the file as well
+        cache.insert(EntryKind::Article, "article_c", &body);
+        cache.flush().unwrap();
+
+        let reopened = DiskCache::open(&path, CacheConfig::default());
+        assert_eq!(reopened.len(), 2);
+        assert!(reopened.get("article_a").is_none());
+        assert!(reopened.get("article_c").is_some());
+    }
+
+    #[test]
+    fn test_corrupt_file_gives_empty_cache() {
+        let path = temp_path("corrupt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "not json").unwrap();
+
+        assert!(DiskCache::open(&path, CacheConfig::default()).is_empty());
+    }
+}
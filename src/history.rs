@@ -189,6 +189,7 @@ mod tests {
             time: Some(1234567890),
             descendants: Some(10),
             kids: None,
+            ..Default::default()
         }
     }
 
@@ -66,6 +66,8 @@ pub fn handle_key_down(
             match view_mode {
                 ViewMode::Bookmarks
                 | ViewMode::History
+                | ViewMode::Search
+                | ViewMode::User(_)
                 | ViewMode::ThemeEditor
                 | ViewMode::LogViewer => {
                     tracing::debug!("Back action - returning to List view");
@@ -80,6 +82,11 @@ pub fn handle_key_down(
             crate::state::AppState::trigger_search_focus(viewer.app_state.clone(), cx);
             cx.notify();
         }
+        crate::config::Action::SearchHackerNews => {
+            tracing::debug!("Search all of Hacker News");
+            crate::state::AppState::search_hacker_news(viewer.app_state.clone(), cx);
+            cx.notify();
+        }
         crate::config::Action::CycleSearchMode => {
             tracing::debug!("Cycle search mode");
             let current_mode = app_state.search_mode;
@@ -131,6 +138,16 @@ pub fn handle_key_down(
                         view.scroll_by(SCROLL_STEP);
                     });
                 }
+                ViewMode::Search => {
+                    viewer.search_results_view().update(cx, |view, _| {
+                        view.scroll_by(SCROLL_STEP);
+                    });
+                }
+                ViewMode::User(_) => {
+                    viewer.user_profile_view().update(cx, |view, _| {
+                        view.scroll_by(SCROLL_STEP);
+                    });
+                }
                 ViewMode::Webview(_) | ViewMode::ThemeEditor | ViewMode::LogViewer => {
                     // WebView, ThemeEditor, and LogViewer handle their own scrolling
                 }
@@ -161,6 +178,16 @@ pub fn handle_key_down(
                         view.scroll_by(-SCROLL_STEP);
                     });
                 }
+                ViewMode::Search => {
+                    viewer.search_results_view().update(cx, |view, _| {
+                        view.scroll_by(-SCROLL_STEP);
+                    });
+                }
+                ViewMode::User(_) => {
+                    viewer.user_profile_view().update(cx, |view, _| {
+                        view.scroll_by(-SCROLL_STEP);
+                    });
+                }
                 ViewMode::Webview(_) | ViewMode::ThemeEditor | ViewMode::LogViewer => {
                     // WebView, ThemeEditor, and LogViewer handle their own scrolling
                 }
@@ -191,6 +218,16 @@ pub fn handle_key_down(
                         view.scroll_to_top();
                     });
                 }
+                ViewMode::Search => {
+                    viewer.search_results_view().update(cx, |view, _| {
+                        view.scroll_to_top();
+                    });
+                }
+                ViewMode::User(_) => {
+                    viewer.user_profile_view().update(cx, |view, _| {
+                        view.scroll_to_top();
+                    });
+                }
                 ViewMode::Webview(_) | ViewMode::ThemeEditor | ViewMode::LogViewer => {
                     // WebView, ThemeEditor, and LogViewer handle their own scrolling
                 }
@@ -234,6 +271,11 @@ pub fn handle_key_down(
                 }
             }
         }
+        crate::config::Action::ClearCache => {
+            tracing::debug!("Clear cache");
+            crate::state::AppState::clear_cache(viewer.app_state.clone(), cx);
+            cx.notify();
+        }
         crate::config::Action::None => {
             // Do nothing
         }
@@ -266,5 +308,38 @@ pub fn handle_key_down(
                 cx.notify();
             });
         }
+        crate::config::Action::ToggleLinkIndex => {
+            if let ViewMode::Story(_) = app_state.view_mode {
+                tracing::debug!("Toggle link index");
+                viewer.app_state.update(cx, |state, cx| {
+                    state.show_link_index = !state.show_link_index;
+                    cx.notify();
+                });
+            }
+        }
+        crate::config::Action::OpenLink(number) => {
+            if let ViewMode::Story(_) = app_state.view_mode
+                && let Some(link) = app_state.link_index().get(*number).cloned()
+            {
+                tracing::debug!("Open link {}: {}", number, link.url);
+                crate::state::AppState::open_link(viewer.app_state.clone(), link.url, cx);
+            }
+        }
+        crate::config::Action::OpenLinkInBrowser(number) => {
+            if let ViewMode::Story(_) = app_state.view_mode
+                && let Some(link) = app_state.link_index().get(*number).cloned()
+            {
+                tracing::debug!("Open link {} in browser: {}", number, link.url);
+                cx.open_url(&link.url);
+            }
+        }
+        crate::config::Action::CopyLink(number) => {
+            if let ViewMode::Story(_) = app_state.view_mode
+                && let Some(link) = app_state.link_index().get(*number).cloned()
+            {
+                tracing::info!("Copied link {}: {}", number, link.url);
+                cx.write_to_clipboard(gpui::ClipboardItem::new_string(link.url));
+            }
+        }
     }
 }
@@ -1,7 +1,8 @@
 use crate::api::StoryListType;
 use crate::internal::ui::{
-    BookmarkListView, HistoryListView, KeyboardHelpOverlay, LogViewerView, StoryDetailView,
-    StoryListView, ThemeEditorView, render_header, render_webview_controls,
+    BookmarkListView, HistoryListView, KeyboardHelpOverlay, LogViewerView, SearchResultsView,
+    StoryDetailView, StoryListView, ThemeEditorView, UserProfileView, render_header,
+    render_webview_controls,
 };
 use crate::state::{AppState, ViewMode};
 use gpui::{prelude::*, *};
@@ -24,6 +25,8 @@ pub struct HnLayout {
     story_detail_view: Entity<StoryDetailView>,
     pub bookmark_list_view: Entity<BookmarkListView>,
     pub history_list_view: Entity<HistoryListView>,
+    search_results_view: Entity<SearchResultsView>,
+    user_profile_view: Entity<UserProfileView>,
     pub theme_editor_view: Entity<ThemeEditorView>,
     log_viewer_view: Entity<LogViewerView>,
     keyboard_help_view: Entity<KeyboardHelpOverlay>,
@@ -73,6 +76,8 @@ impl HnLayout {
         let focus_handle = cx.focus_handle();
         window.focus(&focus_handle);
 
+        let search_results_view = cx.new(|cx| SearchResultsView::new(app_state.clone(), cx));
+        let user_profile_view = cx.new(|cx| UserProfileView::new(app_state.clone(), cx));
         let theme_editor_view = cx.new(|cx| ThemeEditorView::new(app_state.clone(), cx));
         let log_buffer = app_state.read(cx).log_buffer.clone();
         let log_viewer_view = cx.new(|cx| LogViewerView::new(app_state.clone(), log_buffer, cx));
@@ -90,6 +95,8 @@ impl HnLayout {
             story_detail_view,
             bookmark_list_view,
             history_list_view,
+            search_results_view,
+            user_profile_view,
             theme_editor_view,
             log_viewer_view,
             keyboard_help_view,
@@ -110,6 +117,14 @@ impl HnLayout {
     pub fn history_list_view(&self) -> Entity<HistoryListView> {
         self.history_list_view.clone()
     }
+
+    pub fn search_results_view(&self) -> Entity<SearchResultsView> {
+        self.search_results_view.clone()
+    }
+
+    pub fn user_profile_view(&self) -> Entity<UserProfileView> {
+        self.user_profile_view.clone()
+    }
 }
 
 impl Render for HnLayout {
@@ -176,6 +191,8 @@ impl Render for HnLayout {
                 ViewMode::Story(_) => div().flex_1().child(self.story_detail_view.clone()),
                 ViewMode::Bookmarks => div().flex_1().child(self.bookmark_list_view.clone()),
                 ViewMode::History => div().flex_1().child(self.history_list_view.clone()),
+                ViewMode::Search => div().flex_1().child(self.search_results_view.clone()),
+                ViewMode::User(_) => div().flex_1().child(self.user_profile_view.clone()),
                 ViewMode::Webview(_) => {
                     let app_state_entity = self.app_state.clone();
                     div()
@@ -1,7 +1,9 @@
 use crate::config::AppConfig;
-use comrak::nodes::{AstNode, NodeValue};
+use crate::utils::links::LinkIndex;
+use comrak::nodes::{AstNode, ListType, NodeValue};
 use comrak::{Arena, Options, parse_document};
 use gpui::{FontWeight, Hsla, SharedString, div, prelude::*, px};
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct MarkdownStyle {
@@ -10,12 +12,17 @@ pub struct MarkdownStyle {
     pub code_bg_color: Hsla,
     pub font_sans: SharedString,
     pub font_mono: SharedString,
+    /// Numbers shown after the links, as footnote markers
+    pub links: Arc<LinkIndex>,
 }
 
 /// Parse markdown text and render it as GPUI elements
 pub fn render_markdown(text: &str, style: MarkdownStyle, config: &AppConfig) -> impl IntoElement {
     let arena = Arena::new();
-    let options = Options::default();
+    let mut options = Options::default();
+    // Articles converted from HTML use GFM tables and strikethrough
+    options.extension.table = true;
+    options.extension.strikethrough = true;
     let root = parse_document(&arena, text, &options);
 
     div()
@@ -115,10 +122,17 @@ fn render_node<'a>(
                     .into_any_element(),
             )
         }
-        NodeValue::List(_) => {
+        NodeValue::List(list) => {
             let items: Vec<_> = node
                 .children()
-                .filter_map(|child| render_node(child, style, config))
+                .enumerate()
+                .map(|(index, item)| {
+                    let marker = match list.list_type {
+                        ListType::Ordered => format!("{}.", list.start + index),
+                        ListType::Bullet => "•".to_string(),
+                    };
+                    render_list_item(item, marker, style, config)
+                })
                 .collect();
 
             Some(
@@ -131,23 +145,57 @@ fn render_node<'a>(
                     .into_any_element(),
             )
         }
-        NodeValue::Item(_) => {
-            // Render list item using the inline renderer so that long
-            // runs are split into smaller elements and can wrap.
+        NodeValue::BlockQuote => Some(
+            div()
+                .flex()
+                .flex_col()
+                .gap_2()
+                .pl_3()
+                .border_l_2()
+                .border_color(style.code_bg_color)
+                .children(iter_nodes(node, style, config))
+                .into_any_element(),
+        ),
+        NodeValue::ThematicBreak => Some(
+            div()
+                .w_full()
+                .h(px(1.0))
+                .my_2()
+                .bg(style.code_bg_color)
+                .into_any_element(),
+        ),
+        NodeValue::Table(_) => {
+            let rows = node.children().map(|row| {
+                let is_header = matches!(row.data.borrow().value, NodeValue::TableRow(true));
+                div()
+                    .flex()
+                    .flex_row()
+                    .w_full()
+                    .when(is_header, |row| {
+                        row.bg(style.code_bg_color).font_weight(FontWeight(600.0))
+                    })
+                    .children(row.children().map(|cell| {
+                        div()
+                            .flex_1()
+                            .min_w_0()
+                            .px_2()
+                            .py_1()
+                            .child(render_inline_content(cell, style, config))
+                    }))
+            });
+
             Some(
                 div()
                     .flex()
-                    .gap_2()
+                    .flex_col()
+                    .w_full()
+                    .border_1()
+                    .border_color(style.code_bg_color)
+                    .rounded_md()
+                    .text_sm()
                     .text_color(style.text_color)
                     .font_family(style.font_sans.clone())
-                    .child("•")
-                    .child(
-                        div()
-                            .flex()
-                            .flex_wrap()
-                            .w_full()
-                            .child(render_inline_content(node, style, config)),
-                    )
+                    .children(rows)
                     .into_any_element(),
             )
         }
@@ -155,6 +203,40 @@ fn render_node<'a>(
     }
 }
 
+/// Render a list item after its bullet or number. Paragraphs use the inline
+/// renderer so that long runs are split into smaller elements and can wrap,
+/// and nested lists are rendered as blocks below them.
+fn render_list_item<'a>(
+    node: &'a AstNode<'a>,
+    marker: String,
+    style: &MarkdownStyle,
+    config: &AppConfig,
+) -> gpui::AnyElement {
+    let blocks = node
+        .children()
+        .filter_map(|child| match &child.data.borrow().value {
+            NodeValue::Paragraph => Some(
+                div()
+                    .flex()
+                    .flex_wrap()
+                    .w_full()
+                    .child(render_inline_content(child, style, config))
+                    .into_any_element(),
+            ),
+            _ => render_node(child, style, config),
+        })
+        .collect::<Vec<_>>();
+
+    div()
+        .flex()
+        .gap_2()
+        .text_color(style.text_color)
+        .font_family(style.font_sans.clone())
+        .child(marker)
+        .child(div().flex().flex_col().gap_1().w_full().children(blocks))
+        .into_any_element()
+}
+
 fn render_inline_content<'a>(
     node: &'a AstNode<'a>,
     style: &MarkdownStyle,
@@ -225,15 +307,37 @@ fn collect_inline_parts<'a>(
             };
 
             for word in split_into_words(&display) {
+                let url = url.clone();
                 parts.push(
                     div()
+                        .id((SharedString::from(format!("link-{}", url)), parts.len()))
                         .text_color(style.link_color)
                         .underline()
+                        .cursor_pointer()
+                        .on_click(move |_, _w, cx| cx.open_url(&url))
                         .child(word)
                         .into_any_element(),
                 );
             }
+            if let Some(number) = style.links.number(&url) {
+                parts.push(
+                    div()
+                        .text_color(style.text_color.opacity(0.6))
+                        .child(format!("[{}]", number))
+                        .into_any_element(),
+                );
+            }
+        }
+        NodeValue::Image(_) => {
+            parts.push(
+                div()
+                    .child(format!("[Image: {}]", collect_text(node)))
+                    .into_any_element(),
+            );
         }
+        NodeValue::SoftBreak => parts.push(div().child(" ").into_any_element()),
+        // A full-width element forces the following parts onto a new line
+        NodeValue::LineBreak => parts.push(div().w_full().into_any_element()),
         _ => {
             for child in node.children() {
                 collect_inline_parts(child, parts, style, config);
@@ -1,6 +1,17 @@
 use serde::Deserialize;
+use strum_macros::Display;
 
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+/// Kind of top-level item a `Story` was parsed from.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default, Display)]
+#[serde(rename_all = "lowercase")]
+pub enum StoryKind {
+    #[default]
+    Story,
+    Job,
+    Poll,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
 pub struct Story {
     pub id: u32,
     pub title: Option<String>,
@@ -10,6 +21,19 @@ pub struct Story {
     pub time: Option<i64>,
     pub descendants: Option<u32>,
     pub kids: Option<Vec<u32>>,
+    /// Set from the item's `type` tag when parsed through `Item`
+    #[serde(skip)]
+    pub kind: StoryKind,
+    /// HTML body of self-posts (Ask HN, jobs, polls)
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub dead: bool,
+    #[serde(default)]
+    pub deleted: bool,
+    /// Poll option ids, in display order
+    #[serde(default)]
+    pub parts: Option<Vec<u32>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -33,6 +57,97 @@ pub struct Comment {
     pub kids: Option<Vec<u32>>,
     #[serde(default)]
     pub deleted: bool,
+    #[serde(default)]
+    pub dead: bool,
+    #[serde(default)]
+    pub parent: Option<u32>,
+}
+
+/// A single option of a poll.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct PollOpt {
+    pub id: u32,
+    pub poll: u32,
+    #[serde(default)]
+    pub by: Option<String>,
+    #[serde(default)]
+    pub score: Option<u32>,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub time: Option<i64>,
+    #[serde(default)]
+    pub dead: bool,
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+/// Any item from the HN `item/{id}.json` endpoint, tagged by its `type` field.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Item {
+    Story(Story),
+    Comment(Comment),
+    Job(Story),
+    Poll(Story),
+    PollOpt(PollOpt),
+}
+
+#[allow(dead_code)]
+impl Item {
+    pub fn id(&self) -> u32 {
+        match self {
+            Item::Story(s) | Item::Job(s) | Item::Poll(s) => s.id,
+            Item::Comment(c) => c.id,
+            Item::PollOpt(o) => o.id,
+        }
+    }
+
+    /// Whether the item was killed by flags or moderators.
+    pub fn is_dead(&self) -> bool {
+        match self {
+            Item::Story(s) | Item::Job(s) | Item::Poll(s) => s.dead,
+            Item::Comment(c) => c.dead,
+            Item::PollOpt(o) => o.dead,
+        }
+    }
+
+    /// Convert a story, job or poll into a `Story` with its `kind` set.
+    pub fn into_story(self) -> Option<Story> {
+        let (story, kind) = match self {
+            Item::Story(s) => (s, StoryKind::Story),
+            Item::Job(s) => (s, StoryKind::Job),
+            Item::Poll(s) => (s, StoryKind::Poll),
+            Item::Comment(_) | Item::PollOpt(_) => return None,
+        };
+        Some(Story { kind, ..story })
+    }
+
+    pub fn into_comment(self) -> Option<Comment> {
+        match self {
+            Item::Comment(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn into_poll_opt(self) -> Option<PollOpt> {
+        match self {
+            Item::PollOpt(o) => Some(o),
+            _ => None,
+        }
+    }
+}
+
+/// Share of the total score for each poll option, as fractions in `0.0..=1.0`.
+pub fn poll_vote_shares(options: &[PollOpt]) -> Vec<f32> {
+    let total: u32 = options.iter().map(|o| o.score.unwrap_or(0)).sum();
+    options
+        .iter()
+        .map(|o| match total {
+            0 => 0.0,
+            total => o.score.unwrap_or(0) as f32 / total as f32,
+        })
+        .collect()
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -42,4 +157,27 @@ pub struct CommentViewModel {
     pub depth: u32,
     pub collapsed: bool,
     pub loading: bool,
+    /// Whether the replies in `comment.kids` have been fetched
+    pub replies_loaded: bool,
+}
+
+impl CommentViewModel {
+    /// Number of replies that are not loaded yet and can be fetched on demand.
+    pub fn unloaded_replies(&self) -> usize {
+        match self.replies_loaded {
+            true => 0,
+            false => self.comment.kids.as_ref().map_or(0, Vec::len),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct User {
+    pub id: String,
+    pub created: i64,
+    pub karma: i64,
+    #[serde(default)]
+    pub about: Option<String>,
+    #[serde(default)]
+    pub submitted: Vec<u32>,
 }
@@ -0,0 +1,152 @@
+//! Rendering of HN comment and self-post HTML as GPUI elements.
+//!
+//! Paragraphs are styled text with clickable link ranges, each followed by its
+//! number in the story's link index. A click opens the link in the app, HN items
+//! natively and other pages in the webview, and a click with the platform
+//! modifier (Cmd or Ctrl) opens it in the system browser.
+
+use crate::internal::markdown::soft_wrap;
+use crate::state::AppState;
+use crate::utils::comment_html::{CommentBlock, Span, parse_comment};
+use crate::utils::links::LinkIndex;
+use gpui::{
+    App, Entity, FontStyle, HighlightStyle, InteractiveText, IntoElement, SharedString, StyledText,
+    UnderlineStyle, Window, div, prelude::*, px,
+};
+use gpui_component::theme::ThemeColor;
+
+/// Render `html` from the HN API. `id` must be unique among the rendered texts.
+pub fn render_hn_text(
+    id: SharedString,
+    html: &str,
+    colors: &ThemeColor,
+    font_mono: SharedString,
+    max_run: usize,
+    links: &LinkIndex,
+    app_state: Entity<AppState>,
+) -> impl IntoElement {
+    let blocks = parse_comment(html);
+
+    div()
+        .flex()
+        .flex_col()
+        .gap_2()
+        .children(blocks.into_iter().enumerate().map(|(index, block)| {
+            let id = (id.clone(), index);
+            match block {
+                CommentBlock::Paragraph(spans) => div()
+                    .child(render_spans(
+                        id,
+                        &spans,
+                        colors,
+                        max_run,
+                        links,
+                        app_state.clone(),
+                    ))
+                    .into_any_element(),
+                CommentBlock::Quote(spans) => div()
+                    .pl_3()
+                    .border_l_2()
+                    .border_color(colors.border)
+                    .text_color(colors.muted_foreground)
+                    .child(render_spans(
+                        id,
+                        &spans,
+                        colors,
+                        max_run,
+                        links,
+                        app_state.clone(),
+                    ))
+                    .into_any_element(),
+                CommentBlock::Code(code) => div()
+                    .flex()
+                    .flex_col()
+                    .p_2()
+                    .bg(colors.secondary)
+                    .rounded_md()
+                    .font_family(font_mono.clone())
+                    .text_sm()
+                    .children(code.lines().map(|line| {
+                        // An empty line would collapse to no height
+                        let line = match line.is_empty() {
+                            true => " ".to_string(),
+                            false => soft_wrap(line, max_run),
+                        };
+                        div().child(line)
+                    }))
+                    .into_any_element(),
+            }
+        }))
+}
+
+/// A paragraph as one styled text, so it wraps like prose.
+fn render_spans(
+    id: (SharedString, usize),
+    spans: &[Span],
+    colors: &ThemeColor,
+    max_run: usize,
+    links: &LinkIndex,
+    app_state: Entity<AppState>,
+) -> impl IntoElement {
+    let mut text = String::new();
+    let mut highlights = Vec::new();
+    let mut link_ranges = Vec::new();
+    let mut urls = Vec::new();
+
+    for (index, span) in spans.iter().enumerate() {
+        let start = text.len();
+        text.push_str(&soft_wrap(&span.text, max_run));
+        let range = start..text.len();
+
+        if !span.italic && span.link.is_none() {
+            continue;
+        }
+        let mut style = HighlightStyle::default();
+        if span.italic {
+            style.font_style = Some(FontStyle::Italic);
+        }
+        if let Some(url) = &span.link {
+            style.color = Some(colors.info);
+            style.underline = Some(UnderlineStyle {
+                thickness: px(1.0),
+                color: Some(colors.info),
+                wavy: false,
+            });
+            link_ranges.push(range.clone());
+            urls.push(url.clone());
+        }
+        highlights.push((range, style));
+
+        // The marker goes after the last span of a link
+        let link_ends = spans
+            .get(index + 1)
+            .is_none_or(|next| next.link != span.link);
+        if let Some(number) = span.link.as_deref().and_then(|url| links.number(url))
+            && link_ends
+        {
+            let start = text.len();
+            text.push_str(&format!("[{}]", number));
+            let marker = HighlightStyle {
+                color: Some(colors.muted_foreground),
+                ..Default::default()
+            };
+            highlights.push((start..text.len(), marker));
+        }
+    }
+
+    InteractiveText::new(id, StyledText::new(text).with_highlights(highlights)).on_click(
+        link_ranges,
+        move |index, window, cx| {
+            if let Some(url) = urls.get(index) {
+                open_link(url, &app_state, window, cx);
+            }
+        },
+    )
+}
+
+fn open_link(url: &str, app_state: &Entity<AppState>, window: &mut Window, cx: &mut App) {
+    match window.modifiers().secondary() {
+        true => cx.open_url(url),
+        false => AppState::open_link(app_state.clone(), url.to_string(), cx),
+    }
+}
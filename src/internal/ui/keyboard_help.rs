@@ -167,6 +167,12 @@ fn get_shortcuts_for_context(
                 Action::FocusSearch,
                 "Focus search",
             );
+            add_shortcut(
+                &mut shortcuts,
+                &action_to_key,
+                Action::SearchHackerNews,
+                "Search all of Hacker News",
+            );
             add_shortcut(
                 &mut shortcuts,
                 &action_to_key,
@@ -211,8 +217,32 @@ fn get_shortcuts_for_context(
                 Action::ToggleBookmark,
                 "Toggle bookmark",
             );
+            add_shortcut(
+                &mut shortcuts,
+                &action_to_key,
+                Action::ToggleLinkIndex,
+                "Show numbered links",
+            );
+            add_shortcut(
+                &mut shortcuts,
+                &action_to_key,
+                Action::OpenLink(1),
+                "Open link 1 in the app (same for 2-9)",
+            );
+            add_shortcut(
+                &mut shortcuts,
+                &action_to_key,
+                Action::OpenLinkInBrowser(1),
+                "Open link 1 in the browser",
+            );
+            add_shortcut(
+                &mut shortcuts,
+                &action_to_key,
+                Action::CopyLink(1),
+                "Copy link 1",
+            );
         }
-        ViewMode::Bookmarks | ViewMode::History => {
+        ViewMode::Bookmarks | ViewMode::History | ViewMode::Search | ViewMode::User(_) => {
             add_shortcut(
                 &mut shortcuts,
                 &action_to_key,
@@ -236,6 +266,12 @@ fn get_shortcuts_for_context(
         Action::ShowLogViewer,
         "Show log viewer",
     );
+    add_shortcut(
+        &mut shortcuts,
+        &action_to_key,
+        Action::ClearCache,
+        "Clear cache",
+    );
 
     shortcuts
 }
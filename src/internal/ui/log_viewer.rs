@@ -32,6 +32,7 @@ impl Render for LogViewerView {
         let theme = cx.theme();
         let colors = &theme.colors;
         let log_lines = self.log_buffer.get_lines();
+        let cache_stats = self.app_state.read(cx).cache_stats();
 
         div()
             .track_focus(&self.focus_handle)
@@ -66,6 +67,17 @@ impl Render for LogViewerView {
                             .child(format!("{} log entries", log_lines.len())),
                     ),
             )
+            .child(
+                h_flex()
+                    .gap_6()
+                    .flex_wrap()
+                    .children(cache_stats.named().into_iter().map(|(name, stats)| {
+                        div()
+                            .text_xs()
+                            .text_color(colors.muted_foreground)
+                            .child(format!("{}: {}", name, stats))
+                    })),
+            )
             .child(
                 v_flex()
                     .flex_1()
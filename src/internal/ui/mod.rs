@@ -2,11 +2,14 @@ pub mod bookmark_list;
 pub mod constants;
 pub mod header;
 pub mod history_list;
+pub mod hn_text;
 pub mod keyboard_help;
 pub mod log_viewer;
+pub mod search_results;
 pub mod story_detail;
 pub mod story_list;
 pub mod theme_editor;
+pub mod user_profile;
 pub mod webview_controls;
 
 pub use bookmark_list::BookmarkListView;
@@ -14,7 +17,9 @@ pub use header::render_header;
 pub use history_list::HistoryListView;
 pub use keyboard_help::KeyboardHelpOverlay;
 pub use log_viewer::LogViewerView;
+pub use search_results::SearchResultsView;
 pub use story_detail::StoryDetailView;
 pub use story_list::StoryListView;
 pub use theme_editor::ThemeEditorView;
+pub use user_profile::UserProfileView;
 pub use webview_controls::render_webview_controls;
@@ -0,0 +1,219 @@
+use crate::api::{SearchSort, SearchTag};
+use crate::internal::scroll::ScrollState;
+use crate::internal::ui::story_list::story_item;
+use crate::state::AppState;
+use gpui::{
+    Context, Entity, FocusHandle, IntoElement, MouseButton, Render, Window, div, prelude::*,
+};
+use gpui_component::Disableable;
+use gpui_component::Sizable;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::spinner::Spinner;
+use gpui_component::theme::ActiveTheme;
+
+/// SearchResultsView - renders full-text HN search results using the story list rows
+pub struct SearchResultsView {
+    app_state: Entity<AppState>,
+    scroll_state: ScrollState,
+    focus_handle: FocusHandle,
+}
+
+impl SearchResultsView {
+    pub fn new(app_state: Entity<AppState>, cx: &mut Context<Self>) -> Self {
+        // Observe app_state for changes
+        cx.observe(&app_state, |_, _, cx| cx.notify()).detach();
+
+        Self {
+            app_state,
+            scroll_state: ScrollState::new(),
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    pub fn scroll_by(&mut self, delta: f32) {
+        self.scroll_state.scroll_by(delta);
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.scroll_state.scroll_to_top();
+    }
+}
+
+impl Render for SearchResultsView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let app_state = self.app_state.read(cx);
+        let query = app_state.hn_search.clone();
+        let sort = app_state.hn_search_sort;
+        let results = app_state.hn_search_results.clone();
+        let loading = app_state.hn_search_loading;
+        let total_hits = app_state.hn_search_total_hits;
+        let has_more = app_state.hn_search_has_more;
+        let error = app_state.hn_search_error.clone();
+        let list_view_items = app_state.config.ui.list_view_items.clone();
+        let bookmarks = app_state.bookmarks.clone();
+        let _ = app_state; // Release borrow
+
+        let scroll_y = self.scroll_state.scroll_y;
+        let colors = cx.theme().colors;
+
+        let mut summary = format!("\"{}\"", query.text);
+        if let Some(author) = &query.author {
+            summary.push_str(&format!(" by {}", author));
+        }
+
+        div()
+            .track_focus(&self.focus_handle)
+            .flex()
+            .size_full()
+            .overflow_hidden()
+            .on_scroll_wheel(cx.listener(|this, event: &gpui::ScrollWheelEvent, _, cx| {
+                let delta_pixels = event.delta.pixel_delta(gpui::px(1.0)).y;
+                let delta_y: f32 = delta_pixels.into();
+                this.scroll_state.scroll_by(-delta_y);
+                cx.notify();
+            }))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .w_full()
+                    .relative()
+                    .top(gpui::px(-scroll_y))
+                    .p_2()
+                    .gap_2()
+                    .child(
+                        div()
+                            .p_2()
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .child(
+                                div()
+                                    .text_lg()
+                                    .font_weight(gpui::FontWeight::BOLD)
+                                    .text_color(colors.foreground)
+                                    .child(format!("Search {} ({} results)", summary, total_hits)),
+                            )
+                            .child({
+                                let app_state_sort = self.app_state.clone();
+                                let next_sort = match sort {
+                                    SearchSort::Relevance => SearchSort::Date,
+                                    SearchSort::Date => SearchSort::Relevance,
+                                };
+                                div()
+                                    .text_sm()
+                                    .cursor_pointer()
+                                    .text_color(colors.muted_foreground)
+                                    .child(format!("Sort: {}", sort))
+                                    .on_mouse_down(MouseButton::Left, move |_, _, cx| {
+                                        AppState::set_search_sort(
+                                            app_state_sort.clone(),
+                                            next_sort,
+                                            cx,
+                                        );
+                                    })
+                            }),
+                    )
+                    // Item type filters
+                    .child(div().px_2().flex().gap_4().text_sm().children(
+                        SearchTag::ALL.into_iter().map(|tag| {
+                            let app_state_tag = self.app_state.clone();
+                            let active = query.tags.contains(&tag);
+                            div()
+                                .cursor_pointer()
+                                .font_weight(if active {
+                                    gpui::FontWeight::BOLD
+                                } else {
+                                    gpui::FontWeight::NORMAL
+                                })
+                                .text_color(if active {
+                                    colors.primary
+                                } else {
+                                    colors.foreground
+                                })
+                                .child(format!("{}", tag))
+                                .on_mouse_down(MouseButton::Left, move |_, _, cx| {
+                                    AppState::toggle_search_tag(app_state_tag.clone(), tag, cx);
+                                })
+                        }),
+                    ))
+                    .when_some(error, |this, err| {
+                        this.child(
+                            div()
+                                .p_2()
+                                .text_sm()
+                                .text_color(colors.danger)
+                                .child(format!("Search failed: {}", err)),
+                        )
+                    })
+                    .children(results.iter().map(|story| {
+                        story_item(
+                            story.id,
+                            story.title.clone().unwrap_or_default(),
+                            story.url.clone(),
+                            story.score.unwrap_or(0),
+                            story.descendants.unwrap_or(0),
+                            story.by.clone().unwrap_or_default(),
+                            story.time.unwrap_or(0),
+                            story
+                                .url
+                                .as_deref()
+                                .and_then(crate::utils::url::extract_domain)
+                                .unwrap_or_default(),
+                            bookmarks.is_bookmarked(story.id),
+                            colors.background.into(),
+                            colors.foreground.into(),
+                            colors.foreground.into(),
+                            colors.border.into(),
+                            self.app_state.clone(),
+                            &list_view_items,
+                            &colors,
+                        )
+                    }))
+                    .when(loading, |this| {
+                        this.child(
+                            div()
+                                .p_4()
+                                .flex()
+                                .items_center()
+                                .justify_center()
+                                .gap_2()
+                                .text_color(colors.foreground)
+                                .child(Spinner::new().small().color(colors.foreground))
+                                .child("Searching..."),
+                        )
+                    })
+                    .when(!loading && results.is_empty(), |this| {
+                        this.child(
+                            div()
+                                .p_8()
+                                .flex()
+                                .justify_center()
+                                .text_color(colors.muted_foreground)
+                                .child("No results"),
+                        )
+                    })
+                    .when(has_more && !results.is_empty(), |this| {
+                        let app_state_more = self.app_state.clone();
+                        this.child(
+                            div().flex().justify_center().mt_4().child(
+                                Button::new("load-more-results")
+                                    .primary()
+                                    .label(format!(
+                                        "Load More Results ({} of {})",
+                                        results.len(),
+                                        total_hits
+                                    ))
+                                    .disabled(loading)
+                                    .on_click(move |_, _w, cx| {
+                                        AppState::fetch_more_search_results(
+                                            app_state_more.clone(),
+                                            cx,
+                                        );
+                                    }),
+                            ),
+                        )
+                    }),
+            )
+    }
+}
@@ -1,7 +1,10 @@
 use crate::internal::markdown::{MarkdownStyle, render_markdown, soft_wrap};
-use crate::internal::models::CommentViewModel;
+use crate::internal::models::{CommentViewModel, PollOpt, StoryKind, poll_vote_shares};
 use crate::internal::scroll::ScrollState;
-use crate::state::{AppState, ViewMode};
+use crate::internal::ui::hn_text::render_hn_text;
+use crate::state::{AppState, ArchivedContent, ViewMode};
+use crate::utils::html::extract_text_from_html;
+use crate::utils::links::LinkIndex;
 use gpui::{
     Context, Entity, FocusHandle, IntoElement, MouseButton, Render, SharedString, Window, div,
     prelude::*,
@@ -70,10 +73,18 @@ impl Render for StoryDetailView {
             }
         };
 
-        let selected_story_content = app_state.selected_story_content.clone();
+        let archive = app_state.selected_story_archive.clone();
+        let archive_loading = app_state.selected_story_archive_loading;
+        let showing_archive = app_state.showing_archive;
+        let selected_story_content = app_state.shown_article().cloned();
+        let links = app_state.link_index().clone();
+        let show_link_index = app_state.show_link_index;
         let selected_story_content_loading = app_state.selected_story_content_loading;
+        let selected_story_content_error = app_state.selected_story_content_error.clone();
         let comments = app_state.comments.clone();
         let comments_loading = app_state.comments_loading;
+        let poll_options = app_state.poll_options.clone();
+        let poll_options_loading = app_state.poll_options_loading;
         let loaded_comment_count = app_state.loaded_comment_count;
         let total_comment_count = app_state.comment_ids.len();
         let _ = app_state; // Release borrow
@@ -123,6 +134,15 @@ impl Render for StoryDetailView {
                             .flex()
                             .flex_col()
                             .mt_2()
+                            .gap_2()
+                            .when(archive_loading || archive.is_some(), |this| {
+                                this.child(render_archive_bar(
+                                    archive.as_ref(),
+                                    showing_archive,
+                                    &colors,
+                                    self.app_state.clone(),
+                                ))
+                            })
                             .child(if selected_story_content_loading {
                                 div()
                                     .p_4()
@@ -140,12 +160,11 @@ impl Render for StoryDetailView {
                                     code_bg_color: colors.secondary,
                                     font_sans: font_sans.clone().into(),
                                     font_mono: font_mono.clone().into(),
+                                    links: links.clone(),
                                 };
 
-                                // Wrap rendered markdown in a full-width flex row with wrapping
-                                // Preprocess the fetched text with soft_wrap to ensure
-                                // long runs don't overflow and can wrap inside the layout.
-                                let preprocessed = soft_wrap(text, config.soft_wrap_max_run);
+                                // The text is Markdown; render_markdown soft-wraps long runs
+                                // itself, so links and code fences reach the parser intact.
                                 div()
                                     .p_4()
                                     .bg(colors.background)
@@ -155,7 +174,41 @@ impl Render for StoryDetailView {
                                     .text_base()
                                     .line_height(gpui::rems(1.5))
                                     .text_color(colors.foreground)
-                                    .child(render_markdown(&preprocessed, style, &config))
+                                    .child(render_markdown(text, style, &config))
+                            } else if let Some(ref html) = story.text {
+                                // Self-post body (Ask HN, jobs, polls)
+                                div()
+                                    .p_4()
+                                    .bg(colors.background)
+                                    .rounded_md()
+                                    .border_1()
+                                    .border_color(colors.border)
+                                    .text_base()
+                                    .line_height(gpui::rems(1.5))
+                                    .text_color(colors.foreground)
+                                    .child(render_hn_text(
+                                        format!("story-{}", story.id).into(),
+                                        html,
+                                        &colors,
+                                        font_mono.clone().into(),
+                                        config.soft_wrap_max_run,
+                                        &links,
+                                        self.app_state.clone(),
+                                    ))
+                            } else if let Some(ref error) = selected_story_content_error {
+                                div()
+                                    .p_4()
+                                    .flex()
+                                    .flex_col()
+                                    .gap_1()
+                                    .text_sm()
+                                    .text_color(colors.danger)
+                                    .child(
+                                        div()
+                                            .font_weight(gpui::FontWeight::SEMIBOLD)
+                                            .child("Failed to load content"),
+                                    )
+                                    .child(error.clone())
                             } else if story.url.is_some() {
                                 div()
                                     .p_4()
@@ -170,6 +223,24 @@ impl Render for StoryDetailView {
                                     .child("No URL available for this story")
                             })
                     })
+                    .when(!links.is_empty(), |this| {
+                        this.child(render_link_index(
+                            &links,
+                            show_link_index,
+                            &colors,
+                            config.soft_wrap_max_run,
+                            self.app_state.clone(),
+                        ))
+                    })
+                    // Poll options
+                    .when(story.kind == StoryKind::Poll, |this| {
+                        this.child(render_poll_options(
+                            &poll_options,
+                            poll_options_loading,
+                            &colors,
+                            self.app_state.clone(),
+                        ))
+                    })
                     // Comments section
                     .child(render_comments_list(CommentsListParams {
                         story: &story,
@@ -178,6 +249,7 @@ impl Render for StoryDetailView {
                         colors: &colors,
                         font_mono: font_mono.clone().into(),
                         max_run: config.soft_wrap_max_run,
+                        links: &links,
                         app_state: self.app_state.clone(),
                         loaded_comment_count,
                         total_comment_count,
@@ -186,6 +258,166 @@ impl Render for StoryDetailView {
     }
 }
 
+/// Which archived copy of the linked page there is, with a switch between it and
+/// the page itself. Without `archive` the lookup is still running.
+fn render_archive_bar(
+    archive: Option<&ArchivedContent>,
+    showing: bool,
+    colors: &ThemeColor,
+    app_state: Entity<AppState>,
+) -> impl IntoElement {
+    let bar = div()
+        .flex()
+        .flex_wrap()
+        .items_center()
+        .gap_2()
+        .px_4()
+        .py_2()
+        .rounded_md()
+        .bg(colors.secondary)
+        .text_sm()
+        .text_color(colors.foreground);
+
+    let Some(archive) = archive else {
+        return bar
+            .child(Spinner::new().small().color(colors.foreground))
+            .child("Looking for an archived copy...");
+    };
+
+    let snapshot = &archive.snapshot;
+    let captured = snapshot
+        .captured
+        .map(|at| format!(", captured {}", at.strftime("%-d %b %Y")))
+        .unwrap_or_default();
+    let url = snapshot.url.clone();
+    let open_state = app_state.clone();
+
+    let bar = match showing {
+        true => bar
+            .child(format!(
+                "📦 Showing the {} copy{}",
+                snapshot.source, captured
+            ))
+            .child(
+                Button::new("show-original")
+                    .small()
+                    .ghost()
+                    .label("Show original")
+                    .on_click(move |_, _w, cx| {
+                        AppState::show_archive(app_state.clone(), false, cx);
+                    }),
+            ),
+        false => bar
+            .child(format!(
+                "📦 An archived copy from {} is available{}",
+                snapshot.source, captured
+            ))
+            .child(
+                Button::new("show-archive")
+                    .small()
+                    .ghost()
+                    .label("Show archived copy")
+                    .on_click(move |_, _w, cx| {
+                        AppState::show_archive(app_state.clone(), true, cx);
+                    }),
+            ),
+    };
+    bar.child(
+        Button::new("open-snapshot")
+            .small()
+            .ghost()
+            .label("Open snapshot")
+            .on_click(move |_, _w, cx| {
+                AppState::show_webview(open_state.clone(), url.clone(), cx);
+            }),
+    )
+}
+
+/// The numbered links of the story, folded into a toggle until the user opens it.
+/// A click opens a link in the app, or with the platform modifier in the browser.
+fn render_link_index(
+    links: &LinkIndex,
+    show: bool,
+    colors: &ThemeColor,
+    max_run: usize,
+    app_state: Entity<AppState>,
+) -> impl IntoElement {
+    let toggle_state = app_state.clone();
+    let toggle = Button::new("toggle-link-index")
+        .small()
+        .ghost()
+        .label(match show {
+            true => "Hide links".to_string(),
+            false => format!("🔗 Links ({})", links.len()),
+        })
+        .on_click(move |_, _w, cx| {
+            toggle_state.update(cx, |state, cx| {
+                state.show_link_index = !state.show_link_index;
+                cx.notify();
+            });
+        });
+
+    if !show {
+        return div().flex().child(toggle);
+    }
+
+    div()
+        .flex()
+        .flex_col()
+        .gap_1()
+        .p_4()
+        .rounded_md()
+        .border_1()
+        .border_color(colors.border)
+        .text_sm()
+        .child(
+            div()
+                .flex()
+                .justify_between()
+                .items_center()
+                .child(
+                    div()
+                        .text_lg()
+                        .font_weight(gpui::FontWeight::BOLD)
+                        .text_color(colors.foreground)
+                        .child(format!("Links ({})", links.len())),
+                )
+                .child(toggle),
+        )
+        .children(links.iter().map(|(number, link)| {
+            let app_state = app_state.clone();
+            let url = link.url.clone();
+            let label = match link.text.is_empty() {
+                true => soft_wrap(&link.url, max_run),
+                false => soft_wrap(&link.text, max_run),
+            };
+            let domain = crate::utils::url::extract_domain(&link.url).unwrap_or_default();
+
+            div()
+                .id(("link-index", number))
+                .flex()
+                .gap_2()
+                .cursor_pointer()
+                .child(
+                    div()
+                        .flex_shrink_0()
+                        .text_color(colors.muted_foreground)
+                        .child(format!("[{}]", number)),
+                )
+                .child(div().text_color(colors.info).child(label))
+                .child(
+                    div()
+                        .flex_shrink_0()
+                        .text_color(colors.muted_foreground)
+                        .child(domain),
+                )
+                .on_click(move |_, window, cx| match window.modifiers().secondary() {
+                    true => cx.open_url(&url),
+                    false => AppState::open_link(app_state.clone(), url.clone(), cx),
+                })
+        }))
+}
+
 fn render_story_header(
     story: &crate::internal::models::Story,
     colors: &ThemeColor,
@@ -262,7 +494,22 @@ fn render_story_header(
                         .items_center()
                         .child("💬")
                         .child(format!("{} comments", story.descendants.unwrap_or(0))),
-                ),
+                )
+                .when(story.kind != StoryKind::Story, |this| {
+                    this.child(
+                        div()
+                            .px_2()
+                            .rounded_md()
+                            .bg(colors.secondary)
+                            .child(story.kind.to_string()),
+                    )
+                })
+                .when(story.dead, |this| {
+                    this.child(div().text_color(colors.danger).child("[dead]"))
+                })
+                .when(story.deleted, |this| {
+                    this.child(div().text_color(colors.danger).child("[deleted]"))
+                }),
         )
         // Metadata row - second line (time)
         .when_some(story.time, |this, time| {
@@ -291,6 +538,104 @@ fn render_story_header(
         })
 }
 
+fn render_poll_options(
+    options: &[PollOpt],
+    loading: bool,
+    colors: &ThemeColor,
+    app_state: Entity<AppState>,
+) -> impl IntoElement {
+    let shares = poll_vote_shares(options);
+
+    div()
+        .flex()
+        .flex_col()
+        .gap_3()
+        .p_4()
+        .rounded_md()
+        .border_1()
+        .border_color(colors.border)
+        .child(
+            div()
+                .text_lg()
+                .font_weight(gpui::FontWeight::BOLD)
+                .text_color(colors.foreground)
+                .child("Poll"),
+        )
+        .when(loading, |this| {
+            this.child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap_2()
+                    .text_sm()
+                    .text_color(colors.foreground)
+                    .child(Spinner::new().small().color(colors.foreground))
+                    .child("Loading poll options..."),
+            )
+        })
+        .children(options.iter().zip(shares).map(|(option, share)| {
+            let app_state_refresh = app_state.clone();
+            let option_id = option.id;
+            let label = option
+                .text
+                .as_deref()
+                .map(extract_text_from_html)
+                .unwrap_or_default();
+
+            div()
+                .flex()
+                .flex_col()
+                .gap_1()
+                .child(
+                    div()
+                        .flex()
+                        .justify_between()
+                        .gap_4()
+                        .text_sm()
+                        .text_color(colors.foreground)
+                        .child(label.trim().to_string())
+                        .child(
+                            div()
+                                .flex()
+                                .gap_2()
+                                .flex_shrink_0()
+                                .child(format!(
+                                    "{} points ({:.1}%)",
+                                    option.score.unwrap_or(0),
+                                    share * 100.0
+                                ))
+                                .child(
+                                    div()
+                                        .cursor_pointer()
+                                        .text_color(colors.info)
+                                        .child("↻")
+                                        .on_mouse_down(MouseButton::Left, move |_, _w, cx| {
+                                            AppState::refresh_poll_option(
+                                                app_state_refresh.clone(),
+                                                option_id,
+                                                cx,
+                                            );
+                                        }),
+                                ),
+                        ),
+                )
+                .child(
+                    div()
+                        .w_full()
+                        .h(gpui::px(8.0))
+                        .rounded_md()
+                        .bg(colors.secondary)
+                        .child(
+                            div()
+                                .h_full()
+                                .w(gpui::relative(share))
+                                .rounded_md()
+                                .bg(colors.primary),
+                        ),
+                )
+        }))
+}
+
 struct CommentsListParams<'a> {
     story: &'a crate::internal::models::Story,
     comments: &'a [CommentViewModel],
@@ -298,6 +643,7 @@ struct CommentsListParams<'a> {
     colors: &'a ThemeColor,
     font_mono: SharedString,
     max_run: usize,
+    links: &'a LinkIndex,
     app_state: Entity<AppState>,
     loaded_comment_count: usize,
     total_comment_count: usize,
@@ -341,7 +687,14 @@ fn render_comments_list(params: CommentsListParams) -> impl IntoElement {
                 .flex_col()
                 .gap_2()
                 .children(params.comments.iter().map(|vm| {
-                    render_comment(vm, params.colors, params.font_mono.clone(), params.max_run)
+                    render_comment(
+                        vm,
+                        params.colors,
+                        params.font_mono.clone(),
+                        params.max_run,
+                        params.links,
+                        params.app_state.clone(),
+                    )
                 })),
         })
         .when(has_more_comments && !params.comments.is_empty(), |this| {
@@ -372,13 +725,13 @@ fn render_comment(
     colors: &ThemeColor,
     font_mono: SharedString,
     max_run: usize,
+    links: &LinkIndex,
+    app_state: Entity<AppState>,
 ) -> impl IntoElement {
     let comment = &vm.comment;
-    let display_text = match (comment.deleted, &comment.text) {
-        (true, _) => "[deleted]".to_string(),
-        (false, Some(text)) => html2text::from_read(text.as_bytes(), 80)
-            .unwrap_or_else(|_| "[failed to parse comment]".to_string()),
-        (false, None) => String::new(),
+    let text_color = match comment.dead {
+        true => colors.muted_foreground,
+        false => colors.foreground,
     };
 
     // Indentation based on depth
@@ -420,14 +773,50 @@ fn render_comment(
                             .child("🕒")
                             .child(crate::utils::datetime::format_timestamp(&time)),
                     )
+                })
+                .when(comment.dead, |this| {
+                    this.child(div().text_color(colors.danger).child("[dead]"))
                 }),
         )
         .child(
             div()
                 .text_base()
                 .line_height(gpui::rems(1.4))
-                .text_color(colors.foreground)
-                .font_family(font_mono)
-                .child(soft_wrap(&display_text, max_run)),
+                .text_color(text_color)
+                .map(|this| match (comment.deleted, &comment.text) {
+                    (true, _) => this.child("[deleted]"),
+                    (false, Some(html)) => this.child(render_hn_text(
+                        format!("comment-{}", comment.id).into(),
+                        html,
+                        colors,
+                        font_mono,
+                        max_run,
+                        links,
+                        app_state.clone(),
+                    )),
+                    (false, None) => this,
+                }),
         )
+        .when(vm.unloaded_replies() > 0, |this| {
+            let comment_id = vm.id;
+            let label = match (vm.loading, vm.unloaded_replies()) {
+                (true, _) => "Loading replies...".to_string(),
+                (false, 1) => "1 more reply".to_string(),
+                (false, n) => format!("{} more replies", n),
+            };
+
+            this.child(
+                div().flex().child(
+                    Button::new(("more-replies", comment_id as usize))
+                        .small()
+                        .ghost()
+                        .label(label)
+                        .loading(vm.loading)
+                        .disabled(vm.loading)
+                        .on_click(move |_, _w, cx| {
+                            AppState::load_replies(app_state.clone(), comment_id, cx);
+                        }),
+                ),
+            )
+        })
 }
@@ -74,8 +74,15 @@ impl Render for StoryListView {
         let current_list = app_state_read.current_list;
         let loaded_count = app_state_read.loaded_count;
         let total_count = app_state_read.story_ids.len();
+        let list_error = app_state_read.list_error.clone();
+        let offline = app_state_read.offline;
+        let verbose_status = app_state_read.config.accessibility.verbose_status;
         let _ = app_state_read; // Release borrow
 
+        let visible_end = end_idx.min(stories.len());
+        let visible = &stories[start_idx.min(visible_end)..visible_end];
+        AppState::schedule_prefetch(self.app_state.clone(), visible, cx);
+
         if should_focus {
             window.focus(&self.search_focus_handle);
             let app_state = self.app_state.clone();
@@ -87,7 +94,6 @@ impl Render for StoryListView {
         let colors = cx.theme().colors;
 
         // Format status bar text
-        let verbose_status = app_state_read.config.accessibility.verbose_status;
         let status_bar_text = if verbose_status {
             format!(
                 "Viewing {:?} results for {}, {} of {} stories loaded, sorted by {:?} in {:?} order.",
@@ -358,6 +364,25 @@ impl Render for StoryListView {
                                         .child("Loading stories..."),
                                 )
                             })
+                            .when_some(list_error, |this, error| {
+                                this.child(
+                                    div()
+                                        .p_4()
+                                        .flex()
+                                        .flex_col()
+                                        .items_center()
+                                        .gap_1()
+                                        .text_color(colors.danger)
+                                        .when(offline, |this| {
+                                            this.child(
+                                                div()
+                                                    .font_weight(gpui::FontWeight::SEMIBOLD)
+                                                    .child("Offline"),
+                                            )
+                                        })
+                                        .child(error),
+                                )
+                            })
                             .when(loading_more, |this| {
                                 this.child(
                                     div()
@@ -391,7 +416,7 @@ impl Render for StoryListView {
 }
 
 #[allow(clippy::too_many_arguments)]
-fn story_item(
+pub(crate) fn story_item(
     id: u32,
     title: String,
     url: Option<String>,
@@ -476,13 +501,28 @@ fn story_item(
                             },
                         )
                         .when(visible_fields.contains(&"author".to_string()), |this| {
+                            let app_state_user = app_state.clone();
+                            let username = author.clone();
                             this.child(
                                 div()
                                     .flex()
                                     .gap_1()
                                     .items_center()
+                                    .cursor_pointer()
+                                    .hover(|style| style.text_color(colors.accent))
                                     .child("👤")
-                                    .child(author.clone()),
+                                    .child(author.clone())
+                                    .on_mouse_down(MouseButton::Left, move |_, _window, cx| {
+                                        // Keep the row's click from opening the story
+                                        cx.stop_propagation();
+                                        if !username.is_empty() {
+                                            AppState::show_user(
+                                                app_state_user.clone(),
+                                                username.clone(),
+                                                cx,
+                                            );
+                                        }
+                                    }),
                             )
                         })
                         .when(visible_fields.contains(&"age".to_string()), |this| {
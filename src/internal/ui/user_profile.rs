@@ -0,0 +1,296 @@
+use crate::internal::markdown::soft_wrap;
+use crate::internal::models::Comment;
+use crate::internal::scroll::ScrollState;
+use crate::internal::ui::hn_text::render_hn_text;
+use crate::internal::ui::story_list::story_item;
+use crate::state::{AppState, ViewMode};
+use crate::utils::datetime::{format_account_age, format_relative_time};
+use crate::utils::html::extract_text_from_html;
+use crate::utils::links::LinkIndex;
+use gpui::{
+    AnyElement, Context, Entity, FocusHandle, IntoElement, Render, Window, div, prelude::*,
+};
+use gpui_component::Disableable;
+use gpui_component::Sizable;
+use gpui_component::button::{Button, ButtonVariants};
+use gpui_component::spinner::Spinner;
+use gpui_component::theme::{ActiveTheme, ThemeColor};
+
+/// Characters of a submitted comment shown in the profile
+const COMMENT_EXCERPT_CHARS: usize = 280;
+
+/// UserProfileView - renders a user's karma, about text and submissions
+pub struct UserProfileView {
+    app_state: Entity<AppState>,
+    scroll_state: ScrollState,
+    focus_handle: FocusHandle,
+}
+
+impl UserProfileView {
+    pub fn new(app_state: Entity<AppState>, cx: &mut Context<Self>) -> Self {
+        // Observe app_state for changes
+        cx.observe(&app_state, |_, _, cx| cx.notify()).detach();
+
+        Self {
+            app_state,
+            scroll_state: ScrollState::new(),
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    pub fn scroll_by(&mut self, delta: f32) {
+        self.scroll_state.scroll_by(delta);
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.scroll_state.scroll_to_top();
+    }
+}
+
+impl Render for UserProfileView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let app_state = self.app_state.read(cx);
+        let username = match &app_state.view_mode {
+            ViewMode::User(name) => name.clone(),
+            _ => String::new(),
+        };
+        let user = app_state.selected_user.clone();
+        let loading = app_state.user_loading;
+        let error = app_state.user_error.clone();
+        let submissions = app_state.user_submissions.clone();
+        let submitted_comments = app_state.user_comments.clone();
+        let submissions_loaded = app_state.user_submissions_loaded;
+        let submissions_loading = app_state.user_submissions_loading;
+        let list_view_items = app_state.config.ui.list_view_items.clone();
+        let max_run = app_state.config.soft_wrap_max_run;
+        let font_mono = app_state.config.font_mono.clone();
+        let bookmarks = app_state.bookmarks.clone();
+        let _ = app_state; // Release borrow
+
+        let scroll_y = self.scroll_state.scroll_y;
+        let colors = cx.theme().colors;
+
+        let about = user.as_ref().and_then(|u| u.about.clone());
+        let total_submitted = user.as_ref().map(|u| u.submitted.len()).unwrap_or(0);
+        let has_more = submissions_loaded < total_submitted;
+
+        div()
+            .track_focus(&self.focus_handle)
+            .flex()
+            .size_full()
+            .overflow_hidden()
+            .on_scroll_wheel(cx.listener(|this, event: &gpui::ScrollWheelEvent, _, cx| {
+                let delta_pixels = event.delta.pixel_delta(gpui::px(1.0)).y;
+                let delta_y: f32 = delta_pixels.into();
+                this.scroll_state.scroll_by(-delta_y);
+                cx.notify();
+            }))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .w_full()
+                    .relative()
+                    .top(gpui::px(-scroll_y))
+                    .p_2()
+                    .gap_2()
+                    .child(
+                        div()
+                            .p_2()
+                            .text_lg()
+                            .font_weight(gpui::FontWeight::BOLD)
+                            .text_color(colors.foreground)
+                            .child(format!("👤 {}", username)),
+                    )
+                    .when(loading, |this| {
+                        this.child(
+                            div()
+                                .p_4()
+                                .flex()
+                                .items_center()
+                                .justify_center()
+                                .gap_2()
+                                .text_color(colors.foreground)
+                                .child(Spinner::new().small().color(colors.foreground))
+                                .child("Loading profile..."),
+                        )
+                    })
+                    .when_some(error, |this, err| {
+                        this.child(
+                            div()
+                                .p_2()
+                                .text_sm()
+                                .text_color(colors.danger)
+                                .child(format!("Failed to load profile: {}", err)),
+                        )
+                    })
+                    .when_some(user, |this, user| {
+                        this.child(
+                            div()
+                                .px_2()
+                                .flex()
+                                .gap_4()
+                                .text_sm()
+                                .text_color(colors.muted_foreground)
+                                .child(format!("⭐ {} karma", user.karma))
+                                .child(format!(
+                                    "🕒 member for {}",
+                                    format_account_age(user.created)
+                                ))
+                                .child(format!("📝 {} submissions", user.submitted.len())),
+                        )
+                    })
+                    .when_some(about, |this, about| {
+                        this.child(
+                            div()
+                                .p_3()
+                                .bg(colors.background)
+                                .border_1()
+                                .border_color(colors.border)
+                                .rounded_md()
+                                .text_sm()
+                                .text_color(colors.foreground)
+                                .child(render_hn_text(
+                                    format!("about-{}", username).into(),
+                                    &about,
+                                    &colors,
+                                    font_mono.into(),
+                                    max_run,
+                                    // Profile links aren't numbered; that index is the story's
+                                    &LinkIndex::default(),
+                                    self.app_state.clone(),
+                                )),
+                        )
+                    })
+                    .children({
+                        // Stories and comments in submission order, which ids follow
+                        let mut rows: Vec<(u32, AnyElement)> = submissions
+                            .iter()
+                            .filter(|s| s.title.is_some())
+                            .map(|story| {
+                                let row = story_item(
+                                    story.id,
+                                    story.title.clone().unwrap_or_default(),
+                                    story.url.clone(),
+                                    story.score.unwrap_or(0),
+                                    story.descendants.unwrap_or(0),
+                                    story.by.clone().unwrap_or_default(),
+                                    story.time.unwrap_or(0),
+                                    story
+                                        .url
+                                        .as_deref()
+                                        .and_then(crate::utils::url::extract_domain)
+                                        .unwrap_or_default(),
+                                    bookmarks.is_bookmarked(story.id),
+                                    colors.background.into(),
+                                    colors.foreground.into(),
+                                    colors.foreground.into(),
+                                    colors.border.into(),
+                                    self.app_state.clone(),
+                                    &list_view_items,
+                                    &colors,
+                                );
+                                (story.id, row.into_any_element())
+                            })
+                            .collect();
+                        rows.extend(submitted_comments.iter().filter(|c| !c.deleted).map(
+                            |comment| {
+                                let row = render_submitted_comment(
+                                    comment,
+                                    &colors,
+                                    max_run,
+                                    self.app_state.clone(),
+                                );
+                                (comment.id, row.into_any_element())
+                            },
+                        ));
+                        rows.sort_by_key(|(id, _)| std::cmp::Reverse(*id));
+                        rows.into_iter().map(|(_, row)| row)
+                    })
+                    .when(submissions_loading, |this| {
+                        this.child(
+                            div()
+                                .p_4()
+                                .flex()
+                                .items_center()
+                                .justify_center()
+                                .gap_2()
+                                .text_sm()
+                                .text_color(colors.foreground)
+                                .child(Spinner::new().xsmall().color(colors.foreground))
+                                .child("Loading submissions..."),
+                        )
+                    })
+                    .when(has_more, |this| {
+                        let app_state_more = self.app_state.clone();
+                        this.child(
+                            div().flex().justify_center().mt_4().child(
+                                Button::new("load-more-submissions")
+                                    .primary()
+                                    .label(format!(
+                                        "Load More Submissions ({} of {})",
+                                        submissions_loaded, total_submitted
+                                    ))
+                                    .disabled(submissions_loading)
+                                    .on_click(move |_, _w, cx| {
+                                        AppState::fetch_more_user_submissions(
+                                            app_state_more.clone(),
+                                            cx,
+                                        );
+                                    }),
+                            ),
+                        )
+                    }),
+            )
+    }
+}
+
+/// A comment the user wrote, as an excerpt. A click opens the story it is on.
+fn render_submitted_comment(
+    comment: &Comment,
+    colors: &ThemeColor,
+    max_run: usize,
+    app_state: Entity<AppState>,
+) -> impl IntoElement {
+    let comment_id = comment.id;
+    let text = comment
+        .text
+        .as_deref()
+        .map(extract_text_from_html)
+        .unwrap_or_default();
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut excerpt: String = text.chars().take(COMMENT_EXCERPT_CHARS).collect();
+    if excerpt.len() < text.len() {
+        excerpt.push('…');
+    }
+
+    div()
+        .id(("submitted-comment", comment_id as usize))
+        .flex()
+        .flex_col()
+        .p_3()
+        .gap_2()
+        .bg(colors.background)
+        .border_1()
+        .border_color(colors.border)
+        .rounded_md()
+        .cursor_pointer()
+        .child(
+            div()
+                .text_xs()
+                .text_color(colors.muted_foreground)
+                .child(match comment.time {
+                    Some(time) => format!("💬 Comment · {}", format_relative_time(time)),
+                    None => "💬 Comment".to_string(),
+                }),
+        )
+        .child(
+            div()
+                .text_sm()
+                .text_color(colors.foreground)
+                .child(soft_wrap(&excerpt, max_run)),
+        )
+        .on_click(move |_, _w, cx| {
+            AppState::open_item(app_state.clone(), comment_id, cx);
+        })
+}
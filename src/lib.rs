@@ -2,6 +2,7 @@ pub mod api;
 pub mod bookmarks;
 pub mod cache;
 pub mod config;
+pub mod disk_cache;
 pub mod history;
 pub mod internal;
 pub mod log_buffer;
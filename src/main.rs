@@ -4,6 +4,7 @@ mod api;
 mod bookmarks;
 mod cache;
 mod config;
+mod disk_cache;
 mod history;
 mod internal;
 mod log_buffer;
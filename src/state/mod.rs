@@ -1,15 +1,35 @@
 mod imp {
-    use crate::api::{ApiService, StoryListType};
+    use crate::api::{
+        ApiCacheStats, ApiError, ApiService, Article, ArticleKind, ChangeSet, Prefetcher, Priority,
+        Revalidated, SearchQuery, SearchResults, SearchSort, SearchTag, Snapshot, StoryListType,
+    };
     use crate::bookmarks::Bookmarks;
+    use crate::config::CommentSource;
+    use crate::disk_cache::DiskCache;
     use crate::history::History;
-    use crate::internal::models::{CommentViewModel, Story};
+    use crate::internal::models::{
+        Comment, CommentViewModel, Item, PollOpt, Story, StoryKind, User,
+    };
     use crate::log_buffer::LogBuffer;
     use crate::search::SearchHistory;
-    use crate::utils::html::extract_text_from_html;
+    use crate::utils::html_to_markdown::{escape_text, html_to_markdown};
+    use crate::utils::links::LinkIndex;
+    use crate::utils::readability::{ExtractedArticle, extract_article};
+    use crate::utils::url::hn_item_id;
     use futures::StreamExt;
     use futures::channel::mpsc;
     use gpui::{App, Entity, Task, prelude::*};
+    use std::collections::HashMap;
     use std::sync::Arc;
+    use std::time::Duration;
+    use tokio_util::sync::CancellationToken;
+
+    /// How long the visible stories must stay unchanged before they are prefetched
+    const PREFETCH_DELAY: Duration = Duration::from_millis(300);
+
+    /// Pages with less article text than this, in characters, are probably a paywall
+    /// or consent stub, so an archived copy is looked for
+    const ARTICLE_STUB_CHARS: usize = 600;
 
     #[derive(Clone, PartialEq, Debug)]
     pub enum ViewMode {
@@ -18,6 +38,10 @@ mod imp {
         Webview(String),
         Bookmarks,
         History,
+        /// Full-text results from the HN Algolia API
+        Search,
+        /// Profile of the given username
+        User(String),
         ThemeEditor,
         LogViewer,
     }
@@ -42,18 +66,64 @@ mod imp {
         Descending,
     }
 
+    /// A copy of the selected story's linked page from a web archive.
+    #[derive(Clone, PartialEq, Debug)]
+    pub struct ArchivedContent {
+        pub snapshot: Snapshot,
+        /// The copy as Markdown, like `selected_story_content`
+        pub content: String,
+    }
+
+    /// Progress of loading the selected story's linked page.
+    #[derive(Debug)]
+    enum ArticleUpdate {
+        /// The page itself, and whether an archived copy is being looked up
+        Original {
+            content: Result<String, String>,
+            archive_lookup: bool,
+        },
+        /// The archived copy, if one was found
+        Archived(Option<ArchivedContent>),
+    }
+
+    /// An open server-sent event subscription. Dropping it closes the stream.
+    struct LiveStream {
+        token: CancellationToken,
+        _task: Task<()>,
+    }
+
+    impl Drop for LiveStream {
+        fn drop(&mut self) {
+            self.token.cancel();
+        }
+    }
+
     pub struct AppState {
         pub stories: Vec<Story>,
         pub loading: bool,
         pub loading_more: bool,
         pub all_stories_loaded: bool,
         pub current_list: StoryListType,
+        /// Why the current list failed to load, if it did
+        pub list_error: Option<String>,
+        /// Whether the last list fetch could not reach the server at all
+        pub offline: bool,
         api_service: Arc<ApiService>,
         pub view_mode: ViewMode,
         pub selected_story_content: Option<String>,
         pub selected_story_content_loading: bool,
+        /// Why the linked page couldn't be shown, if it couldn't
+        pub selected_story_content_error: Option<String>,
+        /// Archived copy of the linked page, looked up when the page failed to load
+        /// or looked like a paywall stub
+        pub selected_story_archive: Option<ArchivedContent>,
+        pub selected_story_archive_loading: bool,
+        /// Whether the archived copy is shown instead of the page itself
+        pub showing_archive: bool,
         pub comments: Vec<CommentViewModel>,
         pub comments_loading: bool,
+        pub poll_options: Vec<PollOpt>,
+        pub poll_options_loading: bool,
         pub loaded_comment_count: usize,
         pub comment_ids: Vec<u32>,
         pub config: crate::config::AppConfig,
@@ -68,8 +138,49 @@ mod imp {
         pub sort_order: SortOrder,
         pub regex_error: Option<String>,
         pub should_focus_search: bool,
+        // Full-text search (Algolia)
+        pub hn_search: SearchQuery,
+        pub hn_search_sort: SearchSort,
+        pub hn_search_results: Vec<Story>,
+        pub hn_search_loading: bool,
+        pub hn_search_total_hits: u32,
+        pub hn_search_has_more: bool,
+        pub hn_search_error: Option<String>,
+        // User profile
+        pub selected_user: Option<User>,
+        pub user_loading: bool,
+        pub user_error: Option<String>,
+        pub user_submissions: Vec<Story>,
+        /// Comments among the user's submissions, listed between the stories by id
+        pub user_comments: Vec<Comment>,
+        pub user_submissions_loaded: usize,
+        pub user_submissions_loading: bool,
         pub fetch_task: Option<Task<()>>,
         pub comment_fetch_task: Option<Task<()>>,
+        pub poll_fetch_task: Option<Task<()>>,
+        pub search_task: Option<Task<()>>,
+        pub user_task: Option<Task<()>>,
+        // Cancellation for tokio-side requests. Dropping a `Task` stops the GPUI future,
+        // but requests already handed to the API keep their rate-limiter slots until
+        // their token is cancelled.
+        root_token: CancellationToken,
+        list_token: CancellationToken,
+        story_token: CancellationToken,
+        // Background prefetch for the visible stories
+        prefetcher: Prefetcher,
+        prefetch_task: Option<Task<()>>,
+        prefetch_token: CancellationToken,
+        /// Stories the current prefetch pass was scheduled for
+        prefetch_ids: Vec<u32>,
+        // Live updates from the HN change feed
+        pub max_item: Option<u32>,
+        pub update_task: Option<Task<()>>,
+        update_poller_token: Option<CancellationToken>,
+        // Fresh data replacing what was shown from the disk cache
+        revalidate_task: Option<Task<()>>,
+        // Event streams for the current list and the selected story
+        list_stream: Option<LiveStream>,
+        story_stream: Option<LiveStream>,
         // Windowing for performance optimization
         pub viewport_start_index: usize,
         pub viewport_end_index: usize,
@@ -81,6 +192,16 @@ mod imp {
         pub log_buffer: LogBuffer,
         // UI overlay state
         pub show_keyboard_help: bool,
+        pub show_link_index: bool,
+        /// Numbered links of the selected story, rebuilt when its article or comments change
+        link_index: Arc<LinkIndex>,
+    }
+
+    impl Drop for AppState {
+        fn drop(&mut self) {
+            self.root_token.cancel();
+            self.api_service.flush_disk_cache();
+        }
     }
 
     impl AppState {
@@ -89,7 +210,26 @@ mod imp {
             log_buffer: LogBuffer,
             cx: &mut App,
         ) -> Entity<Self> {
-            let api_service = Arc::new(ApiService::new());
+            let mut api_service = ApiService::with_config(config.network.clone())
+                .with_metrics(config.log.enable_performance_metrics);
+            if config.cache.enabled {
+                api_service = api_service.with_disk_cache(DiskCache::open(
+                    DiskCache::default_path(),
+                    config.cache.clone(),
+                ));
+            }
+            let api_service = Arc::new(api_service);
+            Self::with_api_service(config, log_buffer, api_service, cx)
+        }
+
+        /// Create the state around an existing service, e.g. one backed by a
+        /// `FixtureBackend` for demos and tests without the network.
+        pub fn with_api_service(
+            config: crate::config::AppConfig,
+            log_buffer: LogBuffer,
+            api_service: Arc<ApiService>,
+            cx: &mut App,
+        ) -> Entity<Self> {
             let bookmarks = Bookmarks::load();
             let history = History::load();
             // Assuming config dir is available or we can construct it.
@@ -104,18 +244,29 @@ mod imp {
                 .unwrap_or_else(|| std::path::PathBuf::from("."));
             let search_history = SearchHistory::new(config_dir);
 
-            cx.new(|_cx| Self {
+            let root_token = CancellationToken::new();
+            let prefetcher = Prefetcher::new((*api_service).clone(), config.prefetch.clone());
+
+            let entity = cx.new(|_cx| Self {
                 stories: Vec::new(),
                 loading: true,
                 loading_more: false,
                 all_stories_loaded: false,
                 current_list: StoryListType::Top,
+                list_error: None,
+                offline: false,
                 api_service,
                 view_mode: ViewMode::List,
                 selected_story_content: None,
                 selected_story_content_loading: false,
+                selected_story_content_error: None,
+                selected_story_archive: None,
+                selected_story_archive_loading: false,
+                showing_archive: false,
                 comments: Vec::new(),
                 comments_loading: false,
+                poll_options: Vec::new(),
+                poll_options_loading: false,
                 loaded_comment_count: 0,
                 comment_ids: Vec::new(),
                 config,
@@ -130,8 +281,38 @@ mod imp {
                 sort_order: SortOrder::Descending,
                 regex_error: None,
                 should_focus_search: false,
+                hn_search: SearchQuery::default(),
+                hn_search_sort: SearchSort::default(),
+                hn_search_results: Vec::new(),
+                hn_search_loading: false,
+                hn_search_total_hits: 0,
+                hn_search_has_more: false,
+                hn_search_error: None,
+                selected_user: None,
+                user_loading: false,
+                user_error: None,
+                user_submissions: Vec::new(),
+                user_comments: Vec::new(),
+                user_submissions_loaded: 0,
+                user_submissions_loading: false,
                 fetch_task: None,
                 comment_fetch_task: None,
+                poll_fetch_task: None,
+                search_task: None,
+                user_task: None,
+                list_token: root_token.child_token(),
+                story_token: root_token.child_token(),
+                prefetcher,
+                prefetch_task: None,
+                prefetch_token: root_token.child_token(),
+                prefetch_ids: Vec::new(),
+                root_token,
+                max_item: None,
+                update_task: None,
+                update_poller_token: None,
+                revalidate_task: None,
+                list_stream: None,
+                story_stream: None,
                 // Windowing defaults
                 viewport_start_index: 0,
                 viewport_end_index: 0,
@@ -143,15 +324,164 @@ mod imp {
                 log_buffer,
                 // UI overlay state
                 show_keyboard_help: false,
-            })
+                show_link_index: false,
+                link_index: Arc::default(),
+            });
+
+            Self::start_update_poller(entity.clone(), cx);
+            Self::start_revalidation_listener(entity.clone(), cx);
+            entity
+        }
+
+        /// Poll the HN change feed and apply updated items to the loaded data
+        pub fn start_update_poller(entity: Entity<Self>, cx: &mut App) {
+            let (api_service, interval_secs) = {
+                let state = entity.read(cx);
+                (
+                    state.api_service.clone(),
+                    state.config.network.update_poll_interval_secs,
+                )
+            };
+
+            if interval_secs == 0 {
+                tracing::info!("Live updates disabled");
+                return;
+            }
+
+            let token = CancellationToken::new();
+            let mut rx =
+                api_service.spawn_update_poller(Duration::from_secs(interval_secs), token.clone());
+
+            let entity_clone = entity.clone();
+            let mut async_cx = cx.to_async();
+
+            let task = cx.foreground_executor().spawn(async move {
+                while let Some(changes) = rx.next().await {
+                    let _ = entity_clone.update(&mut async_cx, |state, cx| {
+                        state.apply_changes(changes);
+                        cx.notify();
+                    });
+                }
+            });
+
+            entity.update(cx, |state, _| {
+                if let Some(old) = state.update_poller_token.replace(token) {
+                    old.cancel();
+                }
+                state.update_task = Some(task);
+            });
+        }
+
+        /// Swap in data the API refreshed after showing it stale from the disk cache
+        fn start_revalidation_listener(entity: Entity<Self>, cx: &mut App) {
+            let api_service = entity.read(cx).api_service.clone();
+            let mut rx = api_service.subscribe_revalidated();
+
+            let entity_clone = entity.clone();
+            let mut async_cx = cx.to_async();
+
+            let task = cx.foreground_executor().spawn(async move {
+                while let Some(revalidated) = rx.next().await {
+                    let Ok(missing) = entity_clone.update(&mut async_cx, |state, cx| {
+                        let missing = match revalidated {
+                            Revalidated::StoryIds { list_type, ids }
+                                if list_type == state.current_list =>
+                            {
+                                state.apply_story_ranking(ids)
+                            }
+                            Revalidated::StoryIds { .. } => Vec::new(),
+                            Revalidated::Item(item) => {
+                                state.apply_item_updates(vec![item]);
+                                Vec::new()
+                            }
+                        };
+                        cx.notify();
+                        missing
+                    }) else {
+                        break;
+                    };
+
+                    if missing.is_empty() {
+                        continue;
+                    }
+
+                    // Stories that are new to the loaded range
+                    Self::fetch_missing_stories(&entity_clone, missing, &mut async_cx).await;
+                }
+            });
+
+            entity.update(cx, |state, _| {
+                state.revalidate_task = Some(task);
+            });
+        }
+
+        /// Record the latest change feed poll and apply its updated items
+        fn apply_changes(&mut self, changes: ChangeSet) {
+            if changes.items.is_empty() {
+                tracing::debug!("Change feed: max item is now {}", changes.max_item);
+            } else {
+                tracing::info!("Change feed: {} updated items", changes.items.len());
+            }
+            self.max_item = Some(changes.max_item);
+            self.apply_item_updates(changes.items);
+        }
+
+        /// Replace loaded stories, comments and poll options with their updated versions
+        fn apply_item_updates(&mut self, items: Vec<Item>) {
+            let mut links_changed = false;
+            for item in items {
+                match item {
+                    Item::Comment(comment) => {
+                        for vm in self.comments.iter_mut().filter(|vm| vm.id == comment.id) {
+                            vm.comment = comment.clone();
+                            links_changed = true;
+                        }
+                        for submitted in
+                            self.user_comments.iter_mut().filter(|c| c.id == comment.id)
+                        {
+                            *submitted = comment.clone();
+                        }
+                    }
+                    Item::PollOpt(option) => {
+                        if let Some(existing) =
+                            self.poll_options.iter_mut().find(|o| o.id == option.id)
+                        {
+                            *existing = option;
+                        }
+                    }
+                    item => {
+                        let Some(story) = item.into_story() else {
+                            continue;
+                        };
+                        for loaded in self
+                            .stories
+                            .iter_mut()
+                            .chain(self.hn_search_results.iter_mut())
+                            .chain(self.user_submissions.iter_mut())
+                            .filter(|s| s.id == story.id)
+                        {
+                            *loaded = story.clone();
+                        }
+                        if let ViewMode::Story(selected) = &mut self.view_mode
+                            && selected.id == story.id
+                        {
+                            *selected = story;
+                            links_changed = true;
+                        }
+                    }
+                }
+            }
+            if links_changed {
+                self.update_link_index();
+            }
         }
 
         pub fn fetch_stories(entity: Entity<Self>, list_type: StoryListType, cx: &mut App) {
             tracing::info!("Fetching story IDs for: {}", list_type);
             let api_service = entity.read(cx).api_service.clone();
 
-            // Cancel any existing fetch task
-            entity.update(cx, |state, cx| {
+            // Cancel any existing fetch task and its in-flight requests
+            let token = entity.update(cx, |state, cx| {
                 state.fetch_task = None;
 
                 tracing::info!("List type changed, resetting state.");
@@ -159,10 +489,12 @@ mod imp {
                 state.loading_more = false;
                 state.all_stories_loaded = false;
                 state.current_list = list_type;
+                state.list_error = None;
                 state.stories.clear();
                 state.story_ids.clear();
                 state.loaded_count = 0;
                 cx.notify();
+                state.renew_list_token()
             });
 
             let (tx, mut rx) = mpsc::unbounded::<Vec<u32>>();
@@ -175,7 +507,7 @@ mod imp {
             let task = cx.foreground_executor().spawn(async move {
                 // Fetch IDs in background
                 let ids_result = background
-                    .spawn(async move { api_service.fetch_story_ids(list_type, None).await })
+                    .spawn(async move { api_service.fetch_story_ids(list_type, Some(token)).await })
                     .await;
 
                 match ids_result {
@@ -185,10 +517,17 @@ mod imp {
                             ids.len(),
                             list_type
                         );
+                        let _ = entity_clone.update(&mut async_cx, |state, _| {
+                            state.offline = false;
+                        });
                         let _ = tx.unbounded_send(ids);
                     }
                     Err(e) => {
                         tracing::error!("Failed to fetch story ids: {}", e);
+                        let _ = entity_clone.update(&mut async_cx, |state, _| {
+                            state.offline = ApiError::find(&e).is_some_and(ApiError::is_offline);
+                            state.list_error = Some(describe_error(&e));
+                        });
                         let _ = tx.unbounded_send(Vec::new());
                     }
                 }
@@ -207,11 +546,163 @@ mod imp {
             entity.update(cx, |state, _| {
                 state.fetch_task = Some(task);
             });
+
+            Self::stream_story_list(entity, list_type, cx);
+        }
+
+        /// Follow the ranking of `list_type` over SSE, reordering loaded stories as it changes
+        fn stream_story_list(entity: Entity<Self>, list_type: StoryListType, cx: &mut App) {
+            let (api_service, enabled) = {
+                let state = entity.read(cx);
+                (
+                    state.api_service.clone(),
+                    state.config.network.stream_updates,
+                )
+            };
+
+            if !enabled {
+                entity.update(cx, |state, _| state.list_stream = None);
+                return;
+            }
+
+            let token = CancellationToken::new();
+            let mut rx = api_service.subscribe_story_list(list_type, token.clone());
+
+            let entity_clone = entity.clone();
+            let mut async_cx = cx.to_async();
+
+            let task = cx.foreground_executor().spawn(async move {
+                while let Some(ids) = rx.next().await {
+                    let Ok(missing) = entity_clone.update(&mut async_cx, |state, cx| {
+                        let missing = state.apply_story_ranking(ids);
+                        cx.notify();
+                        missing
+                    }) else {
+                        break;
+                    };
+
+                    if missing.is_empty() {
+                        continue;
+                    }
+
+                    // Stories that moved into the loaded range
+                    Self::fetch_missing_stories(&entity_clone, missing, &mut async_cx).await;
+                }
+            });
+
+            entity.update(cx, |state, _| {
+                state.list_stream = Some(LiveStream { token, _task: task });
+            });
+        }
+
+        /// Fetch stories that entered the loaded range of the current list and rank them
+        /// in. They are dropped if the list is switched or reloaded meanwhile.
+        async fn fetch_missing_stories(
+            entity: &Entity<Self>,
+            missing: Vec<u32>,
+            cx: &mut gpui::AsyncApp,
+        ) {
+            let Ok((api_service, token, list_type)) = entity.update(cx, |state, _| {
+                (
+                    state.api_service.clone(),
+                    state.list_token.child_token(),
+                    state.current_list,
+                )
+            }) else {
+                return;
+            };
+
+            let stories = cx
+                .background_executor()
+                .spawn({
+                    let token = token.clone();
+                    async move {
+                        api_service
+                            .fetch_stories_concurrent(missing, Some(token))
+                            .await
+                    }
+                })
+                .await;
+
+            let _ = entity.update(cx, |state, cx| {
+                if token.is_cancelled() || state.current_list != list_type {
+                    return;
+                }
+                state.stories.extend(stories);
+                state.apply_story_ranking(state.story_ids.clone());
+                cx.notify();
+            });
+        }
+
+        /// Adopt a new ranking for the current list, keeping the number of loaded
+        /// stories. Returns ids in the loaded range that are not loaded yet.
+        fn apply_story_ranking(&mut self, ids: Vec<u32>) -> Vec<u32> {
+            // The initial fetch hasn't finished; it will pick up the streamed ids from the cache
+            if self.story_ids.is_empty() {
+                return Vec::new();
+            }
+
+            let loaded = self.loaded_count.min(ids.len());
+            let rank: HashMap<u32, usize> = ids[..loaded]
+                .iter()
+                .enumerate()
+                .map(|(i, id)| (*id, i))
+                .collect();
+
+            self.stories.retain(|s| rank.contains_key(&s.id));
+            self.stories.sort_by_key(|s| rank[&s.id]);
+            // A page fetched under the old ranking may repeat stories
+            self.stories.dedup_by_key(|s| s.id);
+
+            let missing = ids[..loaded]
+                .iter()
+                .filter(|id| !self.stories.iter().any(|s| s.id == **id))
+                .copied()
+                .collect();
+
+            self.loaded_count = loaded;
+            self.all_stories_loaded = loaded >= ids.len();
+            self.story_ids = ids;
+            missing
+        }
+
+        /// Follow the selected story over SSE so its score and comment count stay current
+        fn stream_selected_story(entity: Entity<Self>, story_id: u32, cx: &mut App) {
+            let (api_service, enabled) = {
+                let state = entity.read(cx);
+                (
+                    state.api_service.clone(),
+                    state.config.network.stream_updates,
+                )
+            };
+
+            if !enabled {
+                return;
+            }
+
+            let token = CancellationToken::new();
+            let mut rx = api_service.subscribe_item(story_id, token.clone());
+
+            let entity_clone = entity.clone();
+            let mut async_cx = cx.to_async();
+
+            let task = cx.foreground_executor().spawn(async move {
+                while let Some(item) = rx.next().await {
+                    let _ = entity_clone.update(&mut async_cx, |state, cx| {
+                        state.apply_item_updates(vec![item]);
+                        cx.notify();
+                    });
+                }
+            });
+
+            entity.update(cx, |state, _| {
+                state.story_stream = Some(LiveStream { token, _task: task });
+            });
         }
 
         pub async fn fetch_more_stories(entity: Entity<Self>, cx: &mut gpui::AsyncApp) {
             // Get the batch of IDs to fetch and track the batch size
-            let (api_service, ids_to_fetch, batch_size) = entity
+            let (api_service, token, ids_to_fetch, batch_size) = entity
                 .update(cx, |state, cx| {
                     // Check if we've already loaded all stories
                     if state.loaded_count >= state.story_ids.len() {
@@ -219,7 +710,7 @@ mod imp {
                         state.loading_more = false;
                         state.all_stories_loaded = true;
                         cx.notify();
-                        return (None, Vec::new(), 0);
+                        return (None, state.list_token.clone(), Vec::new(), 0);
                     }
 
                     // Use loading_more for pagination, loading for initial fetch
@@ -231,10 +722,16 @@ mod imp {
                     let ids = state.story_ids[start..end].to_vec();
                     let batch_size = ids.len();
 
-                    (Some(state.api_service.clone()), ids, batch_size)
+                    (
+                        // Later pages load behind anything the user clicks
+                        Some(state.api_service.at_priority(Priority::Visible)),
+                        state.list_token.clone(),
+                        ids,
+                        batch_size,
+                    )
                 })
                 .ok()
-                .unwrap_or((None, Vec::new(), 0));
+                .unwrap_or((None, CancellationToken::new(), Vec::new(), 0));
 
             if let Some(api_service) = api_service {
                 let ids_count = ids_to_fetch.len();
@@ -243,13 +740,21 @@ mod imp {
                 // Use concurrent fetch
                 let stories = cx
                     .background_executor()
-                    .spawn(async move {
-                        api_service
-                            .fetch_stories_concurrent(ids_to_fetch, None)
-                            .await
+                    .spawn({
+                        let token = token.clone();
+                        async move {
+                            api_service
+                                .fetch_stories_concurrent(ids_to_fetch, Some(token))
+                                .await
+                        }
                     })
                     .await;
 
+                // The list was switched while this batch was loading
+                if token.is_cancelled() {
+                    return;
+                }
+
                 let _ = entity.update(cx, |state, cx| {
                     state.stories.extend(stories);
                     // Increment loaded_count by the batch size (number of IDs attempted)
@@ -270,18 +775,34 @@ mod imp {
         pub fn select_story(entity: Entity<Self>, story_id: u32, cx: &mut App) {
             // Set initial selection & mark content loading
             entity.update(cx, |state, cx| {
-                if let Some(story) = state.stories.iter().find(|s| s.id == story_id).cloned() {
-                    // Add to history when selecting a story
-                    state.history.add(&story);
-                    state.history.save();
+                if let Some(story) = state.find_story(story_id) {
                     state.view_mode = ViewMode::Story(story);
                 }
+                // Add to history when selecting a story, including one `open_item` set
+                if let ViewMode::Story(story) = &state.view_mode
+                    && story.id == story_id
+                {
+                    state.history.add(story);
+                    state.history.save();
+                }
                 state.selected_story_content = None;
                 state.selected_story_content_loading = true;
+                state.selected_story_content_error = None;
+                state.selected_story_archive = None;
+                state.selected_story_archive_loading = false;
+                state.showing_archive = false;
                 state.comments.clear();
                 state.comment_ids.clear();
                 state.loaded_comment_count = 0;
                 state.comments_loading = false;
+                state.poll_fetch_task = None;
+                state.poll_options.clear();
+                state.poll_options_loading = false;
+                state.story_stream = None;
+                state.renew_story_token();
+                state.update_link_index();
+                // Opening a story takes priority over warming caches for the others
+                state.cancel_prefetch();
                 cx.notify();
             });
 
@@ -296,28 +817,40 @@ mod imp {
                 }
             };
 
-            // Trigger comment fetching for the selected story
-            Self::fetch_comments(entity.clone(), story.clone(), cx);
+            Self::stream_selected_story(entity.clone(), story.id, cx);
+
+            // Search hits don't carry `kids`; fetch the full item before loading comments
+            match story.kids {
+                Some(_) => Self::fetch_thread(entity.clone(), story.clone(), cx),
+                None => Self::refresh_selected_story(entity.clone(), story.id, cx),
+            }
 
             match story.url.clone() {
                 Some(url) => {
-                    let (tx, mut rx) = mpsc::unbounded::<String>();
+                    let (api_service, token, archive_enabled) = {
+                        let state = entity.read(cx);
+                        (
+                            state.api_service.clone(),
+                            state.story_token.clone(),
+                            !state.config.network.archive_sources.is_empty(),
+                        )
+                    };
+                    let (tx, mut rx) = mpsc::unbounded::<ArticleUpdate>();
                     let background = cx.background_executor().clone();
                     let foreground = cx.foreground_executor().clone();
                     let mut async_cx = cx.to_async();
 
-                    // Foreground receiver updates state when content arrives
+                    // Foreground receiver updates state as the page and its archived copy arrive
                     foreground
                         .spawn({
                             let entity_fg = entity.clone();
                             async move {
-                                if let Some(content) = rx.next().await {
+                                while let Some(update) = rx.next().await {
                                     let _ = entity_fg.update(&mut async_cx, |state, cx| {
                                         if let ViewMode::Story(sel) = &state.view_mode
                                             && sel.id == story.id
                                         {
-                                            state.selected_story_content = Some(content);
-                                            state.selected_story_content_loading = false;
+                                            state.apply_article_update(update);
                                             cx.notify();
                                         }
                                     });
@@ -326,35 +859,410 @@ mod imp {
                         })
                         .detach();
 
-                    // Background fetch
+                    // Background fetch, then the archive lookup if the page was no good
                     background
                         .spawn(async move {
-                            let fetched = crate::api::http_get(&url).await.unwrap_or_default();
-                            let text = extract_text_from_html(&fetched);
-                            let _ = tx.unbounded_send(text);
+                            let Some(fetched) = token
+                                .run_until_cancelled(api_service.fetch_article(&url))
+                                .await
+                            else {
+                                return;
+                            };
+                            let (content, try_archive) = match fetched {
+                                Ok(article) => {
+                                    let is_html = article.kind == ArticleKind::Html;
+                                    let text = article_content(article);
+                                    let stub = is_html && text.chars().count() < ARTICLE_STUB_CHARS;
+                                    (Ok(text), stub)
+                                }
+                                Err(e) => {
+                                    tracing::warn!("Failed to load {}: {:#}", url, e);
+                                    (Err(describe_article_error(&e)), archive_may_help(&e))
+                                }
+                            };
+                            let archive_lookup = archive_enabled && try_archive;
+                            let _ = tx.unbounded_send(ArticleUpdate::Original {
+                                content,
+                                archive_lookup,
+                            });
+                            if !archive_lookup {
+                                return;
+                            }
+
+                            let Some(archived) = token
+                                .run_until_cancelled(api_service.fetch_archived_article(&url))
+                                .await
+                            else {
+                                return;
+                            };
+                            let archived = match archived {
+                                Ok(archived) => Some(ArchivedContent {
+                                    snapshot: archived.snapshot,
+                                    content: article_content(archived.article),
+                                }),
+                                Err(e) => {
+                                    tracing::info!("No archived copy of {}: {:#}", url, e);
+                                    None
+                                }
+                            };
+                            let _ = tx.unbounded_send(ArticleUpdate::Archived(archived));
                         })
                         .detach();
                 }
                 None => {
-                    // No URL; mark as not loading with placeholder
+                    // No URL; the detail view shows the self-post text, if any
                     entity.update(cx, |state, cx| {
                         state.selected_story_content_loading = false;
-                        state.selected_story_content = Some("(No URL for this story)".to_string());
+                        state.selected_story_content = None;
                         cx.notify();
                     });
                 }
             }
         }
 
+        fn apply_article_update(&mut self, update: ArticleUpdate) {
+            match update {
+                ArticleUpdate::Original {
+                    content,
+                    archive_lookup,
+                } => {
+                    match content {
+                        Ok(text) => self.selected_story_content = Some(text),
+                        Err(e) => self.selected_story_content_error = Some(e),
+                    }
+                    self.selected_story_content_loading = false;
+                    self.selected_story_archive_loading = archive_lookup;
+                }
+                ArticleUpdate::Archived(archived) => {
+                    // Switch to the copy when it has more to read than the page itself
+                    self.showing_archive = archived.as_ref().is_some_and(|archived| {
+                        self.selected_story_content
+                            .as_ref()
+                            .is_none_or(|original| archived.content.len() > original.len())
+                    });
+                    self.selected_story_archive = archived;
+                    self.selected_story_archive_loading = false;
+                }
+            }
+            self.update_link_index();
+        }
+
+        /// Show the archived copy of the linked page, or the page itself.
+        pub fn show_archive(entity: Entity<Self>, show: bool, cx: &mut App) {
+            entity.update(cx, |state, cx| {
+                state.showing_archive = show && state.selected_story_archive.is_some();
+                state.update_link_index();
+                cx.notify();
+            });
+        }
+
+        /// The article shown for the selected story: the archived copy when the user
+        /// switched to it, otherwise the page itself.
+        pub fn shown_article(&self) -> Option<&String> {
+            match (&self.selected_story_archive, self.showing_archive) {
+                (Some(archive), true) => Some(&archive.content),
+                _ => self.selected_story_content.as_ref(),
+            }
+        }
+
+        /// The numbered links of the selected story.
+        pub fn link_index(&self) -> &Arc<LinkIndex> {
+            &self.link_index
+        }
+
+        /// Number the links of the selected story: those of the shown article, or of
+        /// the self-post text without one, then those of the loaded comments.
+        fn update_link_index(&mut self) {
+            let mut index = LinkIndex::default();
+            let ViewMode::Story(story) = &self.view_mode else {
+                self.link_index = Arc::new(index);
+                return;
+            };
+            match (self.shown_article(), &story.text) {
+                (Some(article), _) => index.add_markdown(article),
+                (None, Some(html)) => index.add_hn_text(html),
+                (None, None) => {}
+            }
+            for vm in self.comments.iter().filter(|vm| !vm.comment.deleted) {
+                if let Some(html) = &vm.comment.text {
+                    index.add_hn_text(html);
+                }
+            }
+            self.link_index = Arc::new(index);
+        }
+
+        /// Open `url` in the app: links to HN items open the story they belong to,
+        /// other pages open in the webview.
+        pub fn open_link(entity: Entity<Self>, url: String, cx: &mut App) {
+            match hn_item_id(&url) {
+                Some(id) => Self::open_item(entity, id, cx),
+                None => Self::show_webview(entity, url, cx),
+            }
+        }
+
+        /// Open the story HN item `id` belongs to, which for a comment is the story at
+        /// the top of its thread.
+        pub fn open_item(entity: Entity<Self>, id: u32, cx: &mut App) {
+            tracing::info!("Opening HN item {}", id);
+            let api_service = entity.read(cx).api_service.clone();
+            let async_cx = cx.to_async();
+            let background = cx.background_executor().clone();
+
+            cx.foreground_executor()
+                .spawn(async move {
+                    let result = background
+                        .spawn(async move { api_service.fetch_item_story(id).await })
+                        .await;
+
+                    match result {
+                        Ok(story) => {
+                            let _ = async_cx.update(|cx| {
+                                let story_id = story.id;
+                                // `select_story` opens the story in `view_mode` when it
+                                // isn't in any loaded list
+                                entity.update(cx, |state, _| {
+                                    state.view_mode = ViewMode::Story(story);
+                                });
+                                Self::select_story(entity, story_id, cx);
+                            });
+                        }
+                        Err(e) => tracing::error!("Failed to open item {}: {:#}", id, e),
+                    }
+                })
+                .detach();
+        }
+
+        /// Prefetch comments and articles for `visible`, the stories in the visible
+        /// range, once it has stayed put for `PREFETCH_DELAY`. Does nothing if the same
+        /// stories were already scheduled, which is checked before anything is copied.
+        pub fn schedule_prefetch(entity: Entity<Self>, visible: &[Story], cx: &mut App) {
+            let Some(token) = entity.update(cx, |state, _| {
+                if !state.prefetcher.is_enabled()
+                    || visible.is_empty()
+                    || visible
+                        .iter()
+                        .map(|s| s.id)
+                        .eq(state.prefetch_ids.iter().copied())
+                {
+                    return None;
+                }
+
+                state.cancel_prefetch();
+                state.prefetch_ids = visible.iter().map(|s| s.id).collect();
+                state.prefetch_token = state.list_token.child_token();
+                Some(state.prefetch_token.clone())
+            }) else {
+                return;
+            };
+            let stories = visible.to_vec();
+
+            let prefetcher = entity.read(cx).prefetcher.clone();
+            let background = cx.background_executor().clone();
+
+            let task = cx.foreground_executor().spawn(async move {
+                // Wait for scrolling to settle
+                background.timer(PREFETCH_DELAY).await;
+                if token.is_cancelled() {
+                    return;
+                }
+
+                let stats = background
+                    .spawn(async move { prefetcher.run(stories, token).await })
+                    .await;
+                tracing::debug!(
+                    "Prefetched {} items ({} failed, out of budget: {})",
+                    stats.fetched,
+                    stats.failed,
+                    stats.out_of_budget
+                );
+            });
+
+            entity.update(cx, |state, _| {
+                state.prefetch_task = Some(task);
+            });
+        }
+
+        /// Stop the running prefetch pass, so the next `schedule_prefetch` starts over.
+        fn cancel_prefetch(&mut self) {
+            self.prefetch_token.cancel();
+            self.prefetch_task = None;
+            self.prefetch_ids.clear();
+        }
+
+        /// Cancel requests for the current story list and return a token for the next list.
+        fn renew_list_token(&mut self) -> CancellationToken {
+            self.list_token.cancel();
+            self.list_token = self.root_token.child_token();
+            self.prefetch_ids.clear();
+            self.list_token.clone()
+        }
+
+        /// Cancel requests for the selected story and return a token for the next one.
+        fn renew_story_token(&mut self) -> CancellationToken {
+            self.story_token.cancel();
+            self.story_token = self.root_token.child_token();
+            self.story_token.clone()
+        }
+
+        /// Look up a loaded story by id in the story list, search results or a user's submissions.
+        fn find_story(&self, story_id: u32) -> Option<Story> {
+            self.stories
+                .iter()
+                .chain(self.hn_search_results.iter())
+                .chain(self.user_submissions.iter())
+                .find(|s| s.id == story_id)
+                .cloned()
+        }
+
+        /// Load the comments of the selected story, plus the options of a poll.
+        fn fetch_thread(entity: Entity<Self>, story: Story, cx: &mut App) {
+            if story.kind == StoryKind::Poll
+                && let Some(parts) = story.parts.clone()
+            {
+                Self::fetch_poll_options(entity.clone(), parts, cx);
+            }
+            Self::fetch_comments(entity, story, cx);
+        }
+
+        /// Fetch all options of the selected poll, in server order.
+        pub fn fetch_poll_options(entity: Entity<Self>, parts: Vec<u32>, cx: &mut App) {
+            let api_service = entity.update(cx, |state, cx| {
+                state.poll_options_loading = true;
+                cx.notify();
+                state.api_service.clone()
+            });
+
+            let mut async_cx = cx.to_async();
+            let background = cx.background_executor().clone();
+            let entity_clone = entity.clone();
+
+            let task = cx.foreground_executor().spawn(async move {
+                let options = background
+                    .spawn(async move { api_service.fetch_poll_options(parts).await })
+                    .await;
+
+                let _ = entity_clone.update(&mut async_cx, |state, cx| {
+                    state.poll_options = options;
+                    state.poll_options_loading = false;
+                    cx.notify();
+                });
+            });
+
+            entity.update(cx, |state, _| {
+                state.poll_fetch_task = Some(task);
+            });
+        }
+
+        /// Re-fetch a single poll option to pick up its current score.
+        pub fn refresh_poll_option(entity: Entity<Self>, option_id: u32, cx: &mut App) {
+            let api_service = entity.read(cx).api_service.clone();
+            let mut async_cx = cx.to_async();
+            let background = cx.background_executor().clone();
+
+            cx.foreground_executor()
+                .spawn(async move {
+                    let result = background
+                        .spawn(async move { api_service.fetch_poll_option(option_id, true).await })
+                        .await;
+
+                    match result {
+                        Ok(option) => {
+                            let _ = entity.update(&mut async_cx, |state, cx| {
+                                if let Some(existing) =
+                                    state.poll_options.iter_mut().find(|o| o.id == option_id)
+                                {
+                                    *existing = option;
+                                    cx.notify();
+                                }
+                            });
+                        }
+                        Err(e) => {
+                            tracing::error!("Failed to refresh poll option {}: {}", option_id, e);
+                        }
+                    }
+                })
+                .detach();
+        }
+
+        /// Re-fetch the selected story from the API, then load its comments.
+        fn refresh_selected_story(entity: Entity<Self>, story_id: u32, cx: &mut App) {
+            let api_service = entity.read(cx).api_service.clone();
+            let async_cx = cx.to_async();
+            let background = cx.background_executor().clone();
+
+            cx.foreground_executor()
+                .spawn(async move {
+                    let result = background
+                        .spawn(async move { api_service.fetch_story_content(story_id).await })
+                        .await;
+
+                    match result {
+                        Ok(story) => {
+                            let _ = async_cx.update(|cx| {
+                                let still_selected = matches!(
+                                    &entity.read(cx).view_mode,
+                                    ViewMode::Story(sel) if sel.id == story_id
+                                );
+                                if still_selected {
+                                    entity.update(cx, |state, cx| {
+                                        state.view_mode = ViewMode::Story(story.clone());
+                                        state.update_link_index();
+                                        cx.notify();
+                                    });
+                                    Self::fetch_thread(entity.clone(), story, cx);
+                                }
+                            });
+                        }
+                        Err(e) => {
+                            tracing::error!("Failed to refresh story {}: {}", story_id, e);
+                            // Distinguish a story that is gone from one we couldn't reach
+                            if let Some(ApiError::NotFound(_)) = ApiError::find(&e) {
+                                let _ = async_cx.update(|cx| {
+                                    entity.update(cx, |state, cx| {
+                                        if let ViewMode::Story(sel) = &mut state.view_mode
+                                            && sel.id == story_id
+                                        {
+                                            sel.deleted = true;
+                                            cx.notify();
+                                        }
+                                    })
+                                });
+                            }
+                        }
+                    }
+                })
+                .detach();
+        }
+
         pub fn clear_selection(entity: Entity<Self>, cx: &mut App) {
             entity.update(cx, |state, cx| {
-                state.view_mode = ViewMode::List;
+                // Stories opened from search results or a profile return there
+                let opened_from = |stories: &[Story]| {
+                    matches!(
+                        &state.view_mode,
+                        ViewMode::Story(s) if stories.iter().any(|r| r.id == s.id)
+                    )
+                };
+                state.view_mode = match &state.selected_user {
+                    _ if opened_from(&state.hn_search_results) => ViewMode::Search,
+                    Some(user) if opened_from(&state.user_submissions) => {
+                        ViewMode::User(user.id.clone())
+                    }
+                    _ => ViewMode::List,
+                };
                 state.selected_story_content = None;
                 state.selected_story_content_loading = false;
+                state.selected_story_content_error = None;
+                state.selected_story_archive = None;
+                state.selected_story_archive_loading = false;
+                state.showing_archive = false;
                 state.comments.clear();
                 state.comment_ids.clear();
                 state.loaded_comment_count = 0;
                 state.comments_loading = false;
+                state.story_stream = None;
+                state.renew_story_token();
+                state.update_link_index();
                 cx.notify();
             });
         }
@@ -399,8 +1307,6 @@ mod imp {
                 }
             };
 
-            let api_service = entity.read(cx).api_service.clone();
-
             // Cancel existing comment fetch
             entity.update(cx, |state, cx| {
                 state.comment_fetch_task = None;
@@ -410,6 +1316,112 @@ mod imp {
                 cx.notify();
             });
 
+            let first_page = comment_ids.into_iter().take(20).collect();
+            match entity.read(cx).config.network.comment_source {
+                CommentSource::Firebase => Self::stream_comment_page(entity, first_page, cx),
+                CommentSource::Algolia => {
+                    Self::fetch_whole_thread(entity, story.id, first_page, cx)
+                }
+            }
+        }
+
+        /// Load every comment of a story with one Algolia request, falling back to
+        /// fetching `first_page` from Firebase if that fails.
+        fn fetch_whole_thread(
+            entity: Entity<Self>,
+            story_id: u32,
+            first_page: Vec<u32>,
+            cx: &mut App,
+        ) {
+            let (api_service, token, ranked) = {
+                let state = entity.read(cx);
+                (
+                    state.api_service.clone(),
+                    state.story_token.clone(),
+                    state.comment_ids.clone(),
+                )
+            };
+
+            let entity_clone = entity.clone();
+            let async_cx = cx.to_async();
+            let background = cx.background_executor().clone();
+
+            let task = cx.foreground_executor().spawn(async move {
+                let result = background
+                    .spawn({
+                        let token = token.clone();
+                        async move {
+                            api_service
+                                .fetch_thread_comments(story_id, &ranked, Some(token))
+                                .await
+                        }
+                    })
+                    .await;
+
+                // The story was closed while its comments were loading
+                if token.is_cancelled() {
+                    return;
+                }
+
+                let _ = async_cx.update(|cx| match result {
+                    Ok(comments) => entity_clone.update(cx, |state, cx| {
+                        state.comments = comments;
+                        state.loaded_comment_count = state.comment_ids.len();
+                        state.comments_loading = false;
+                        state.update_link_index();
+                        cx.notify();
+                    }),
+                    Err(e) => {
+                        tracing::warn!(
+                            "Loading thread {} from Algolia failed, using Firebase: {:#}",
+                            story_id,
+                            e
+                        );
+                        Self::stream_comment_page(entity_clone.clone(), first_page, cx);
+                    }
+                });
+            });
+
+            entity.update(cx, |state, _| {
+                state.comment_fetch_task = Some(task);
+            });
+        }
+
+        pub fn fetch_more_comments(entity: Entity<Self>, cx: &mut App) {
+            let page = entity.update(cx, |state, cx| {
+                // Check if we've already loaded all comments
+                if state.loaded_comment_count >= state.comment_ids.len() {
+                    state.comments_loading = false;
+                    cx.notify();
+                    return None;
+                }
+
+                state.comments_loading = true;
+                cx.notify();
+
+                let start = state.loaded_comment_count;
+                let end = (start + 20).min(state.comment_ids.len());
+                Some(state.comment_ids[start..end].to_vec())
+            });
+
+            if let Some(page) = page {
+                Self::stream_comment_page(entity, page, cx);
+            }
+        }
+
+        /// Fetch a page of top-level comments with their replies, breadth-first, and
+        /// append it to `comments`. Each level is shown as soon as it arrives.
+        fn stream_comment_page(entity: Entity<Self>, ids: Vec<u32>, cx: &mut App) {
+            let (api_service, token, max_depth) = {
+                let state = entity.read(cx);
+                (
+                    state.api_service.clone(),
+                    state.story_token.clone(),
+                    state.config.comment_max_depth,
+                )
+            };
+            let page_size = ids.len();
+
             let (tx, mut rx) = mpsc::unbounded::<Vec<CommentViewModel>>();
 
             let entity_clone = entity.clone();
@@ -417,29 +1429,47 @@ mod imp {
             let background = cx.background_executor().clone();
 
             let task = cx.foreground_executor().spawn(async move {
-                // Fetch first batch in background
-                let comments = background
-                    .spawn(async move {
-                        Self::fetch_comments_recursive(
-                            &api_service,
-                            comment_ids.into_iter().take(20).collect(),
-                            0,
-                            3,
-                        )
-                        .await
+                // Stops after the current level once `rx` is dropped
+                background
+                    .spawn({
+                        let token = token.clone();
+                        async move {
+                            let mut levels = std::pin::pin!(
+                                api_service.stream_comment_tree(ids, 0, max_depth, token)
+                            );
+                            while let Some(level) = levels.next().await {
+                                if tx.unbounded_send(level).is_err() {
+                                    break;
+                                }
+                            }
+                        }
                     })
-                    .await;
+                    .detach();
 
-                let _ = tx.unbounded_send(comments);
+                let mut shown = 0;
+                while let Some(page) = rx.next().await {
+                    // The story was closed while its comments were loading
+                    if token.is_cancelled() {
+                        return;
+                    }
 
-                if let Some(comments) = rx.next().await {
                     let _ = entity_clone.update(&mut async_cx, |state, cx| {
-                        state.comments = comments;
-                        state.loaded_comment_count = 20.min(state.comment_ids.len());
-                        state.comments_loading = false;
+                        shown = replace_last_comments(&mut state.comments, shown, page);
+                        state.update_link_index();
                         cx.notify();
                     });
                 }
+
+                if token.is_cancelled() {
+                    return;
+                }
+
+                let _ = entity_clone.update(&mut async_cx, |state, cx| {
+                    state.loaded_comment_count =
+                        (state.loaded_comment_count + page_size).min(state.comment_ids.len());
+                    state.comments_loading = false;
+                    cx.notify();
+                });
             });
 
             entity.update(cx, |state, _| {
@@ -447,100 +1477,63 @@ mod imp {
             });
         }
 
-        async fn fetch_comments_recursive(
-            api: &ApiService,
-            ids: Vec<u32>,
-            depth: u32,
-            max_depth: u32,
-        ) -> Vec<CommentViewModel> {
-            let mut results = Vec::new();
-
-            // Fetch comments concurrently
-            let comments = api.fetch_comments_concurrent(ids, None).await;
-
-            for comment in comments {
-                let kids = comment.kids.clone();
-                let vm = CommentViewModel {
-                    id: comment.id,
-                    comment: comment.clone(),
-                    depth,
-                    collapsed: false,
-                    loading: false,
-                };
-                results.push(vm);
-
-                if depth < max_depth
-                    && let Some(kids_ids) = kids
-                {
-                    // Recursively fetch children
-                    // Note: We could parallelize this too with join_all, but let's keep it simple for now
-                    // as the depth is limited and branching factor can be high.
-                    // Actually, since we are async, we can just await.
-                    let children = Box::pin(Self::fetch_comments_recursive(
-                        api,
-                        kids_ids,
-                        depth + 1,
+        /// Load the replies of a comment whose subtree was cut off at `comment_max_depth`,
+        /// and insert them below it.
+        pub fn load_replies(entity: Entity<Self>, comment_id: u32, cx: &mut App) {
+            let Some((api_service, token, kids, depth, max_depth)) =
+                entity.update(cx, |state, cx| {
+                    let max_depth = state.config.comment_max_depth;
+                    let vm = state.comments.iter_mut().find(|vm| vm.id == comment_id)?;
+                    if vm.loading || vm.unloaded_replies() == 0 {
+                        return None;
+                    }
+                    let kids = vm.comment.kids.clone()?;
+                    let depth = vm.depth + 1;
+                    vm.loading = true;
+                    cx.notify();
+                    Some((
+                        state.api_service.clone(),
+                        state.story_token.clone(),
+                        kids,
+                        depth,
                         max_depth,
                     ))
-                    .await;
-                    results.extend(children);
-                }
-            }
-            results
-        }
-
-        pub fn fetch_more_comments(entity: Entity<Self>, cx: &mut App) {
-            let (api_service, comment_ids_to_fetch, batch_size) = entity.update(cx, |state, cx| {
-                // Check if we've already loaded all comments
-                if state.loaded_comment_count >= state.comment_ids.len() {
-                    state.comments_loading = false;
-                    cx.notify();
-                    return (None, Vec::new(), 0);
-                }
-
-                state.comments_loading = true;
-                cx.notify();
-
-                let start = state.loaded_comment_count;
-                let end = (start + 20).min(state.comment_ids.len());
-                let ids = state.comment_ids[start..end].to_vec();
-                let batch_size = end - start;
-
-                (Some(state.api_service.clone()), ids, batch_size)
-            });
-
-            if let Some(api_service) = api_service {
-                let (tx, mut rx) = mpsc::unbounded::<Vec<CommentViewModel>>();
+                })
+            else {
+                return;
+            };
 
-                let entity_clone = entity.clone();
-                let mut async_cx = cx.to_async();
-                let background = cx.background_executor().clone();
+            let background = cx.background_executor().clone();
+            let mut async_cx = cx.to_async();
 
-                let task = cx.foreground_executor().spawn(async move {
-                    let comments = background
-                        .spawn(async move {
-                            Self::fetch_comments_recursive(&api_service, comment_ids_to_fetch, 0, 3)
-                                .await
+            cx.foreground_executor()
+                .spawn(async move {
+                    // The subtree gets as many levels as a freshly opened thread
+                    let replies = background
+                        .spawn({
+                            let token = token.clone();
+                            async move {
+                                api_service
+                                    .fetch_comment_tree(kids, depth, depth + max_depth, token)
+                                    .await
+                            }
                         })
                         .await;
 
-                    let _ = tx.unbounded_send(comments);
-
-                    if let Some(new_comments) = rx.next().await {
-                        let _ = entity_clone.update(&mut async_cx, |state, cx| {
-                            state.comments.extend(new_comments);
-                            state.loaded_comment_count += batch_size;
-                            state.comments_loading = false;
-                            cx.notify();
-                        });
+                    // The story was closed while the replies were loading
+                    if token.is_cancelled() {
+                        return;
                     }
-                });
 
-                entity.update(cx, |state, _| {
-                    state.comment_fetch_task = Some(task);
-                });
-            }
+                    let _ = entity.update(&mut async_cx, |state, cx| {
+                        insert_replies(&mut state.comments, comment_id, replies);
+                        state.update_link_index();
+                        cx.notify();
+                    });
+                })
+                .detach();
         }
+
         pub fn set_zoom_level(entity: Entity<Self>, zoom: u32, cx: &mut App) {
             entity.update(cx, |state, cx| {
                 state.config.webview_zoom = zoom;
@@ -586,6 +1579,7 @@ mod imp {
                     score: None,
                     descendants: None,
                     kids: None,
+                    ..Default::default()
                 };
                 state.bookmarks.toggle(&story);
                 state.bookmarks.save();
@@ -617,6 +1611,248 @@ mod imp {
             });
         }
 
+        /// Run a full-text search for the current search bar input and show the results
+        pub fn search_hacker_news(entity: Entity<Self>, cx: &mut App) {
+            let query = SearchQuery::parse(&entity.read(cx).search_query);
+            if query.text.is_empty() && query.author.is_none() {
+                return;
+            }
+
+            entity.update(cx, |state, cx| {
+                state.hn_search = SearchQuery {
+                    tags: std::mem::take(&mut state.hn_search.tags),
+                    ..query
+                };
+                state.hn_search_results.clear();
+                state.hn_search_total_hits = 0;
+                state.hn_search_has_more = false;
+                state.view_mode = ViewMode::Search;
+                cx.notify();
+            });
+
+            Self::fetch_search_page(entity, 0, cx);
+        }
+
+        /// Fetch the next page of full-text search results
+        pub fn fetch_more_search_results(entity: Entity<Self>, cx: &mut App) {
+            let state = entity.read(cx);
+            if !state.hn_search_loading && state.hn_search_has_more {
+                let next_page = state.hn_search.page + 1;
+                Self::fetch_search_page(entity, next_page, cx);
+            }
+        }
+
+        /// Toggle an item type filter and re-run the search
+        pub fn toggle_search_tag(entity: Entity<Self>, tag: SearchTag, cx: &mut App) {
+            entity.update(cx, |state, _| {
+                let tags = &mut state.hn_search.tags;
+                match tags.iter().position(|t| *t == tag) {
+                    Some(pos) => {
+                        tags.remove(pos);
+                    }
+                    None => tags.push(tag),
+                }
+            });
+            Self::restart_search(entity, cx);
+        }
+
+        /// Switch between relevance and date ordering and re-run the search
+        pub fn set_search_sort(entity: Entity<Self>, sort: SearchSort, cx: &mut App) {
+            entity.update(cx, |state, _| {
+                state.hn_search_sort = sort;
+            });
+            Self::restart_search(entity, cx);
+        }
+
+        fn restart_search(entity: Entity<Self>, cx: &mut App) {
+            entity.update(cx, |state, cx| {
+                state.hn_search.page = 0;
+                state.hn_search_results.clear();
+                state.hn_search_total_hits = 0;
+                state.hn_search_has_more = false;
+                cx.notify();
+            });
+            Self::fetch_search_page(entity, 0, cx);
+        }
+
+        /// Fetch results page `page` of the current search. `hn_search.page` only
+        /// moves to it once it has loaded, so a failed page is fetched again next time.
+        fn fetch_search_page(entity: Entity<Self>, page: u32, cx: &mut App) {
+            let (api_service, query, sort) = entity.update(cx, |state, cx| {
+                // Replacing the task drops any search still in flight
+                state.search_task = None;
+                state.hn_search_loading = true;
+                state.hn_search_error = None;
+                cx.notify();
+                (
+                    state.api_service.clone(),
+                    SearchQuery {
+                        page,
+                        ..state.hn_search.clone()
+                    },
+                    state.hn_search_sort,
+                )
+            });
+
+            tracing::info!("Searching HN for {:?} (page {})", query.text, query.page);
+
+            let (tx, mut rx) = mpsc::unbounded::<anyhow::Result<SearchResults>>();
+
+            let entity_clone = entity.clone();
+            let mut async_cx = cx.to_async();
+            let background = cx.background_executor().clone();
+
+            let task = cx.foreground_executor().spawn(async move {
+                let result = background
+                    .spawn(async move { api_service.search_sorted(&query, sort, None).await })
+                    .await;
+
+                let _ = tx.unbounded_send(result);
+
+                if let Some(result) = rx.next().await {
+                    let _ = entity_clone.update(&mut async_cx, |state, cx| {
+                        match result {
+                            Ok(results) => {
+                                state.hn_search.page = page;
+                                // Several comment hits can point at the same story
+                                for story in results.stories() {
+                                    if !state.hn_search_results.iter().any(|s| s.id == story.id) {
+                                        state.hn_search_results.push(story);
+                                    }
+                                }
+                                state.hn_search_total_hits = results.total_hits;
+                                state.hn_search_has_more = results.has_more();
+                            }
+                            Err(e) => {
+                                tracing::error!("Search failed: {}", e);
+                                state.hn_search_error = Some(describe_error(&e));
+                            }
+                        }
+                        state.hn_search_loading = false;
+                        cx.notify();
+                    });
+                }
+            });
+
+            entity.update(cx, |state, _| {
+                state.search_task = Some(task);
+            });
+        }
+
+        /// Open the profile of `username` and load the first page of their submissions
+        pub fn show_user(entity: Entity<Self>, username: String, cx: &mut App) {
+            tracing::info!("Fetching profile for user: {}", username);
+            let api_service = entity.read(cx).api_service.clone();
+
+            entity.update(cx, |state, cx| {
+                state.user_task = None;
+                state.view_mode = ViewMode::User(username.clone());
+                state.selected_user = None;
+                state.user_loading = true;
+                state.user_error = None;
+                state.user_submissions.clear();
+                state.user_comments.clear();
+                state.user_submissions_loaded = 0;
+                state.user_submissions_loading = false;
+                cx.notify();
+            });
+
+            let (tx, mut rx) = mpsc::unbounded::<anyhow::Result<User>>();
+
+            let entity_clone = entity.clone();
+            let mut async_cx = cx.to_async();
+            let background = cx.background_executor().clone();
+
+            let task = cx.foreground_executor().spawn(async move {
+                let result = background
+                    .spawn(async move { api_service.fetch_user(&username).await })
+                    .await;
+
+                let _ = tx.unbounded_send(result);
+
+                if let Some(result) = rx.next().await {
+                    let loaded = entity_clone
+                        .update(&mut async_cx, |state, cx| {
+                            state.user_loading = false;
+                            let loaded = match result {
+                                Ok(user) => {
+                                    state.selected_user = Some(user);
+                                    true
+                                }
+                                Err(e) => {
+                                    tracing::error!("Failed to fetch user: {}", e);
+                                    state.user_error = Some(describe_error(&e));
+                                    false
+                                }
+                            };
+                            cx.notify();
+                            loaded
+                        })
+                        .unwrap_or(false);
+
+                    if loaded {
+                        let _ = async_cx.update(|cx| {
+                            Self::fetch_more_user_submissions(entity_clone, cx);
+                        });
+                    }
+                }
+            });
+
+            entity.update(cx, |state, _| {
+                state.user_task = Some(task);
+            });
+        }
+
+        /// Fetch the next page of the selected user's submitted items
+        pub fn fetch_more_user_submissions(entity: Entity<Self>, cx: &mut App) {
+            let batch = entity.update(cx, |state, cx| {
+                let user = state.selected_user.as_ref()?;
+                if state.user_submissions_loading
+                    || state.user_submissions_loaded >= user.submitted.len()
+                {
+                    return None;
+                }
+
+                let start = state.user_submissions_loaded;
+                let end = (start + 20).min(user.submitted.len());
+                let ids = user.submitted[start..end].to_vec();
+
+                state.user_submissions_loading = true;
+                cx.notify();
+                Some((state.api_service.clone(), ids))
+            });
+
+            let Some((api_service, ids)) = batch else {
+                return;
+            };
+
+            let batch_size = ids.len();
+            tracing::info!("Fetching {} user submissions concurrently...", batch_size);
+
+            let entity_clone = entity.clone();
+            let mut async_cx = cx.to_async();
+            let background = cx.background_executor().clone();
+
+            let task = cx.foreground_executor().spawn(async move {
+                let items = background
+                    .spawn(async move { api_service.fetch_items_concurrent(ids, None).await })
+                    .await;
+                let (stories, comments) = split_submissions(items);
+
+                let _ = entity_clone.update(&mut async_cx, |state, cx| {
+                    state.user_submissions.extend(stories);
+                    state.user_comments.extend(comments);
+                    state.user_submissions_loaded += batch_size;
+                    state.user_submissions_loading = false;
+                    cx.notify();
+                });
+            });
+
+            entity.update(cx, |state, _| {
+                state.user_task = Some(task);
+            });
+        }
+
         /// Clear history
         pub fn clear_history(entity: Entity<Self>, cx: &mut App) {
             entity.update(cx, |state, cx| {
@@ -626,6 +1862,20 @@ mod imp {
             });
         }
 
+        /// Empty the memory and disk caches and reload the current list from the network
+        pub fn clear_cache(entity: Entity<Self>, cx: &mut App) {
+            let (api_service, list_type) = {
+                let state = entity.read(cx);
+                (state.api_service.clone(), state.current_list)
+            };
+
+            match api_service.clear_cache() {
+                Ok(()) => tracing::info!("Cleared cache"),
+                Err(e) => tracing::error!("Failed to clear cache: {:#}", e),
+            }
+            Self::fetch_stories(entity, list_type, cx);
+        }
+
         pub fn set_search_query(entity: Entity<Self>, query: String, cx: &mut App) {
             entity.update(cx, |state, cx| {
                 state.search_query = query;
@@ -735,6 +1985,11 @@ mod imp {
             }
         }
 
+        /// Sizes and hit counters of the API caches, for the log viewer
+        pub fn cache_stats(&self) -> ApiCacheStats {
+            self.api_service.cache_stats()
+        }
+
         /// Get saved scroll position for the current view
         pub fn get_scroll_position(&self) -> f32 {
             match self.view_mode {
@@ -745,6 +2000,138 @@ mod imp {
         }
     }
 
+    /// Replace the last `previous` comments, an earlier snapshot of a page that is still
+    /// loading, with `page`. Returns the number of comments now belonging to the page.
+    fn replace_last_comments(
+        comments: &mut Vec<CommentViewModel>,
+        previous: usize,
+        page: Vec<CommentViewModel>,
+    ) -> usize {
+        comments.truncate(comments.len().saturating_sub(previous));
+        let shown = page.len();
+        comments.extend(page);
+        shown
+    }
+
+    /// Split a page of a user's submitted items into stories and comments, each newest
+    /// first. Poll options are left out; they are shown with their poll.
+    fn split_submissions(mut items: Vec<Item>) -> (Vec<Story>, Vec<Comment>) {
+        // Concurrent fetches complete out of order, and ids grow over time
+        items.sort_by_key(|item| std::cmp::Reverse(item.id()));
+        let mut stories = Vec::new();
+        let mut comments = Vec::new();
+        for item in items {
+            match item {
+                Item::Comment(comment) => comments.push(comment),
+                Item::PollOpt(_) => {}
+                item => stories.extend(item.into_story()),
+            }
+        }
+        (stories, comments)
+    }
+
+    /// Put `replies` directly below the comment `parent_id` in a flattened thread.
+    /// If none could be fetched, the parent keeps its "more replies" button for a retry.
+    fn insert_replies(
+        comments: &mut Vec<CommentViewModel>,
+        parent_id: u32,
+        replies: Vec<CommentViewModel>,
+    ) {
+        let Some(index) = comments.iter().position(|vm| vm.id == parent_id) else {
+            return;
+        };
+
+        let parent = &mut comments[index];
+        parent.loading = false;
+        parent.replies_loaded = !replies.is_empty();
+        comments.splice(index + 1..index + 1, replies);
+    }
+
+    /// User-facing message for a failed request, separating "couldn't reach HN" from
+    /// answers like "that item doesn't exist".
+    fn describe_error(err: &anyhow::Error) -> String {
+        match ApiError::find(err) {
+            Some(e) if e.is_offline() => {
+                "Can't reach Hacker News. Check your connection and try again.".to_string()
+            }
+            Some(ApiError::RateLimited { .. }) => {
+                "Hacker News is rate limiting requests. Try again shortly.".to_string()
+            }
+            Some(ApiError::NotFound(what)) => format!("{} doesn't exist or was deleted", what),
+            Some(e) => e.to_string(),
+            None => err.to_string(),
+        }
+    }
+
+    /// A fetched page as Markdown, reduced to the article text for HTML.
+    fn article_content(article: Article) -> String {
+        match article.kind {
+            ArticleKind::Html => match extract_article(&article.body) {
+                Some(extracted) => article_markdown(&extracted, &article.url),
+                None => html_to_markdown(&article.body, Some(&article.url)),
+            },
+            ArticleKind::Text | ArticleKind::Pdf => article.body,
+        }
+    }
+
+    /// Whether a web archive might have a readable copy of a page that failed with
+    /// `err`. It won't help with content we can't show or a cancelled request.
+    fn archive_may_help(err: &anyhow::Error) -> bool {
+        !matches!(
+            ApiError::find(err),
+            Some(
+                ApiError::Cancelled
+                    | ApiError::TooLarge { .. }
+                    | ApiError::UnsupportedContent(_)
+                    | ApiError::Decode(_)
+            )
+        )
+    }
+
+    /// The article body as Markdown, below a line with its byline and date if known.
+    fn article_markdown(article: &ExtractedArticle, url: &str) -> String {
+        let published = article
+            .published
+            .map(|date| date.strftime("%-d %b %Y").to_string());
+        let credits: Vec<String> = article
+            .byline
+            .iter()
+            .map(|byline| format!("By {}", escape_text(byline)))
+            .chain(published)
+            .collect();
+        let body = article.markdown(Some(url));
+        match credits.is_empty() {
+            true => body,
+            false => format!("*{}*\n\n{}", credits.join(" · "), body),
+        }
+    }
+
+    /// User-facing message for a linked page that couldn't be shown.
+    fn describe_article_error(err: &anyhow::Error) -> String {
+        match ApiError::find(err) {
+            Some(e) if e.is_offline() => {
+                "Can't reach the site. Check your connection or open it in the browser.".to_string()
+            }
+            Some(ApiError::Http { status }) => format!("The site answered with HTTP {}", status),
+            Some(ApiError::TooLarge { limit }) => format!(
+                "The page is larger than {} KiB. Open it in the browser instead.",
+                limit / 1024
+            ),
+            Some(ApiError::UnsupportedContent(mime)) => {
+                format!(
+                    "Can't show {} content. Open it in the browser instead.",
+                    mime
+                )
+            }
+            // Only PDFs fail to decode once downloaded
+            Some(ApiError::Decode(reason)) => format!(
+                "Can't read this document: {}. Open it in the browser instead.",
+                reason
+            ),
+            _ => describe_error(err),
+        }
+    }
+
     pub fn filter_and_sort_stories(
         stories: &[Story],
         search_query: &str,
@@ -831,6 +2218,7 @@ mod imp {
     #[cfg(test)]
     mod tests {
         use super::*;
+        use crate::api::FixtureBackend;
         use crate::internal::models::Story;
 
         fn create_story(id: u32, title: &str, score: u32, comments: u32, time: u64) -> Story {
@@ -843,6 +2231,7 @@ mod imp {
                 time: Some(time as i64),
                 descendants: Some(comments),
                 kids: None,
+                ..Default::default()
             }
         }
 
@@ -927,7 +2316,196 @@ mod imp {
             assert_eq!(sorted[1].id, 1); // 100
             assert_eq!(sorted[2].id, 3); // 200
         }
+
+        #[test]
+        fn test_describe_error_separates_offline_from_not_found() {
+            let offline = anyhow::Error::new(ApiError::Timeout).context("fetch_item failed");
+            let gone = anyhow::Error::new(ApiError::NotFound("item 7".to_string()));
+
+            assert!(describe_error(&offline).contains("Can't reach Hacker News"));
+            assert_eq!(describe_error(&gone), "item 7 doesn't exist or was deleted");
+            assert_eq!(describe_error(&anyhow::anyhow!("boom")), "boom");
+        }
+
+        #[test]
+        fn test_article_markdown_puts_credits_first() {
+            let mut article = ExtractedArticle {
+                title: None,
+                byline: Some("Ada Example".to_string()),
+                published: Some(jiff::civil::date(2024, 3, 5)),
+                content: "<p>Body with <a href=\"/next\">a link</a></p>".to_string(),
+            };
+            let url = "https://example.com/post";
+            assert_eq!(
+                article_markdown(&article, url),
+                "*By Ada Example · 5 Mar 2024*\n\nBody with [a link](https://example.com/next)"
+            );
+
+            article.byline = None;
+            article.published = None;
+            assert!(article_markdown(&article, url).starts_with("Body"));
+        }
+
+        #[test]
+        fn test_describe_article_error() {
+            let too_large = anyhow::Error::new(ApiError::TooLarge {
+                limit: 5 * 1024 * 1024,
+            })
+            .context("GET https://example.com failed");
+            let image = anyhow::Error::new(ApiError::UnsupportedContent("image/png".to_string()));
+            let scanned = anyhow::Error::new(ApiError::Decode(
+                "the PDF has no text, it may be scanned".to_string(),
+            ));
+            let offline = anyhow::Error::new(ApiError::Network("reset".to_string()));
+
+            assert!(describe_article_error(&too_large).contains("larger than 5120 KiB"));
+            assert!(describe_article_error(&image).contains("image/png"));
+            assert!(describe_article_error(&scanned).contains("may be scanned"));
+            assert!(describe_article_error(&offline).contains("Can't reach the site"));
+            assert_eq!(
+                describe_article_error(&anyhow::Error::new(ApiError::Http { status: 403 })),
+                "The site answered with HTTP 403"
+            );
+        }
+
+        #[test]
+        fn test_archive_is_tried_for_dead_and_blocked_pages() {
+            let dead = anyhow::Error::new(ApiError::Network("dns error".to_string()))
+                .context("GET https://example.com failed");
+            let paywalled = anyhow::Error::new(ApiError::Http { status: 403 });
+            let image = anyhow::Error::new(ApiError::UnsupportedContent("image/png".to_string()));
+            let cancelled = anyhow::Error::new(ApiError::Cancelled);
+
+            assert!(archive_may_help(&dead));
+            assert!(archive_may_help(&paywalled));
+            assert!(!archive_may_help(&image));
+            assert!(!archive_may_help(&cancelled));
+        }
+
+        fn comment_vm(id: u32, depth: u32, kids: Option<Vec<u32>>) -> CommentViewModel {
+            CommentViewModel {
+                id,
+                comment: crate::internal::models::Comment {
+                    id,
+                    by: None,
+                    text: None,
+                    time: None,
+                    kids,
+                    deleted: false,
+                    dead: false,
+                    parent: None,
+                },
+                depth,
+                collapsed: false,
+                loading: true,
+                replies_loaded: false,
+            }
+        }
+
+        #[test]
+        fn test_insert_replies_below_parent() {
+            let mut comments = vec![
+                comment_vm(1, 0, None),
+                comment_vm(2, 1, Some(vec![4, 5])),
+                comment_vm(3, 0, None),
+            ];
+            assert_eq!(comments[1].unloaded_replies(), 2);
+
+            insert_replies(
+                &mut comments,
+                2,
+                vec![comment_vm(4, 2, None), comment_vm(5, 2, None)],
+            );
+
+            let order: Vec<u32> = comments.iter().map(|vm| vm.id).collect();
+            assert_eq!(order, vec![1, 2, 4, 5, 3]);
+            assert!(!comments[1].loading);
+            assert_eq!(comments[1].unloaded_replies(), 0);
+        }
+
+        #[test]
+        fn test_replace_last_comments_swaps_page_snapshot() {
+            let mut comments = vec![comment_vm(1, 0, None)];
+
+            let shown = replace_last_comments(&mut comments, 0, vec![comment_vm(2, 0, None)]);
+            assert_eq!(shown, 1);
+
+            let next_level = vec![comment_vm(2, 0, None), comment_vm(3, 1, None)];
+            let shown = replace_last_comments(&mut comments, shown, next_level);
+            assert_eq!(shown, 2);
+
+            let order: Vec<u32> = comments.iter().map(|vm| vm.id).collect();
+            assert_eq!(order, vec![1, 2, 3]);
+        }
+
+        #[test]
+        fn test_insert_no_replies_allows_retry() {
+            let mut comments = vec![comment_vm(1, 0, Some(vec![2]))];
+            insert_replies(&mut comments, 1, Vec::new());
+
+            assert!(!comments[0].loading);
+            assert_eq!(comments[0].unloaded_replies(), 1);
+        }
+
+        #[test]
+        fn test_split_submissions_keeps_comments() {
+            let comment = |id| comment_vm(id, 0, None).comment;
+            let items = vec![
+                Item::Comment(comment(30)),
+                Item::Story(create_story(10, "Show HN: A thing", 5, 2, 1000)),
+                Item::Comment(comment(40)),
+                Item::Poll(create_story(20, "Tabs or spaces?", 9, 4, 2000)),
+                Item::PollOpt(PollOpt {
+                    id: 21,
+                    poll: 20,
+                    by: None,
+                    score: None,
+                    text: None,
+                    time: None,
+                    dead: false,
+                    deleted: false,
+                }),
+            ];
+
+            let (stories, comments) = split_submissions(items);
+
+            let stories: Vec<(u32, StoryKind)> = stories.iter().map(|s| (s.id, s.kind)).collect();
+            assert_eq!(stories, vec![(20, StoryKind::Poll), (10, StoryKind::Story)]);
+            let comments: Vec<u32> = comments.iter().map(|c| c.id).collect();
+            assert_eq!(comments, vec![40, 30]);
+        }
+
+        #[gpui::test]
+        fn test_story_list_loads_from_fixtures(cx: &mut gpui::TestAppContext) {
+            let backend = FixtureBackend::new()
+                .with_base_url("http://127.0.0.1:8080/v0/")
+                .with_story_list(StoryListType::Top, &[2, 1])
+                .with_item(serde_json::json!({ "id": 1, "type": "story", "title": "First" }))
+                .with_item(serde_json::json!({ "id": 2, "type": "story", "title": "Second" }));
+            let mut config = crate::config::AppConfig::default();
+            config.network.api_base_url = Some("http://127.0.0.1:8080/v0/".to_string());
+            config.network.update_poll_interval_secs = 0;
+            config.network.stream_updates = false;
+            let api_service = Arc::new(ApiService::with_backend(
+                config.network.clone(),
+                Arc::new(backend),
+            ));
+
+            let state = cx.update(|cx| {
+                AppState::with_api_service(config, LogBuffer::new(100), api_service, cx)
+            });
+            cx.update(|cx| AppState::fetch_stories(state.clone(), StoryListType::Top, cx));
+            cx.run_until_parked();
+
+            state.read_with(cx, |state, _| {
+                assert!(!state.loading);
+                assert_eq!(state.list_error, None);
+                let mut titles: Vec<_> = state.stories.iter().map(|s| s.title.as_deref()).collect();
+                titles.sort();
+                assert_eq!(titles, vec![Some("First"), Some("Second")]);
+            });
+        }
     }
 }
 
-pub use imp::{AppState, SearchMode, SortOption, ViewMode};
+pub use imp::{AppState, ArchivedContent, SearchMode, SortOption, ViewMode};
@@ -0,0 +1,331 @@
+//! Parser for the HTML dialect of Hacker News comments and self-posts.
+//!
+//! HN emits only a handful of tags: `<p>` before every paragraph but the first,
+//! `<i>`, `<a href>` and `<pre><code>` for indented code, with all other markup
+//! escaped as entities. Unknown tags are dropped and their text kept.
+
+/// A run of text with the same styling.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub italic: bool,
+    /// Target of the link this text is part of
+    pub link: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommentBlock {
+    Paragraph(Vec<Span>),
+    /// A paragraph starting with `>`, by convention a quote of the parent comment
+    Quote(Vec<Span>),
+    /// Preformatted text, with the indentation common to all lines removed
+    Code(String),
+}
+
+/// Split comment HTML into paragraphs, quotes and code blocks.
+pub fn parse_comment(html: &str) -> Vec<CommentBlock> {
+    let mut parser = Parser::default();
+    let mut rest = html;
+    while !rest.is_empty() {
+        match rest.find('<') {
+            Some(0) => match rest.find('>') {
+                Some(end) => {
+                    parser.tag(&rest[1..end]);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    parser.text(rest);
+                    break;
+                }
+            },
+            Some(start) => {
+                parser.text(&rest[..start]);
+                rest = &rest[start..];
+            }
+            None => {
+                parser.text(rest);
+                break;
+            }
+        }
+    }
+    parser.finish()
+}
+
+#[derive(Default)]
+struct Parser {
+    blocks: Vec<CommentBlock>,
+    spans: Vec<Span>,
+    /// Depth of open `<i>` tags
+    italic: usize,
+    link: Option<String>,
+    /// Text of the open `<pre>`, if any
+    code: Option<String>,
+}
+
+impl Parser {
+    fn tag(&mut self, tag: &str) {
+        let tag = tag.trim();
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        match (name.as_str(), closing) {
+            ("p", _) => self.end_paragraph(),
+            ("i" | "em", false) => self.italic += 1,
+            ("i" | "em", true) => self.italic = self.italic.saturating_sub(1),
+            ("a", false) => self.link = attribute(tag, "href").map(|href| decode_entities(&href)),
+            ("a", true) => self.link = None,
+            ("pre", false) => {
+                self.end_paragraph();
+                self.code = Some(String::new());
+            }
+            ("pre", true) => self.end_code(),
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        let text = decode_entities(text);
+        if let Some(code) = &mut self.code {
+            code.push_str(&text);
+            return;
+        }
+
+        let text = text.replace('\n', " ");
+        if text.is_empty() {
+            return;
+        }
+        let italic = self.italic > 0;
+        match self.spans.last_mut() {
+            Some(last) if last.italic == italic && last.link == self.link => {
+                last.text.push_str(&text)
+            }
+            _ => self.spans.push(Span {
+                text,
+                italic,
+                link: self.link.clone(),
+            }),
+        }
+    }
+
+    fn end_paragraph(&mut self) {
+        let mut spans = std::mem::take(&mut self.spans);
+        if spans.iter().all(|span| span.text.trim().is_empty()) {
+            return;
+        }
+        if let Some(first) = spans.first_mut() {
+            first.text = first.text.trim_start().to_string();
+        }
+        if let Some(last) = spans.last_mut() {
+            last.text = last.text.trim_end().to_string();
+        }
+
+        let quoted = spans
+            .first_mut()
+            .and_then(|first| {
+                let text = first.text.strip_prefix('>')?.trim_start().to_string();
+                first.text = text;
+                Some(())
+            })
+            .is_some();
+        spans.retain(|span| !span.text.is_empty());
+        self.blocks.push(match quoted {
+            true => CommentBlock::Quote(spans),
+            false => CommentBlock::Paragraph(spans),
+        });
+    }
+
+    fn end_code(&mut self) {
+        let Some(code) = self.code.take() else {
+            return;
+        };
+        let code = dedent(code.trim_end());
+        if !code.trim().is_empty() {
+            self.blocks.push(CommentBlock::Code(code));
+        }
+    }
+
+    fn finish(mut self) -> Vec<CommentBlock> {
+        self.end_code();
+        self.end_paragraph();
+        self.blocks
+    }
+}
+
+/// The value of attribute `name` in the inside of a start tag.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(found) = lower[from..].find(name) {
+        let start = from + found;
+        from = start + name.len();
+        let preceded_by_space = lower[..start].ends_with(char::is_whitespace);
+        let rest = lower[from..].trim_start();
+        if !preceded_by_space || !rest.starts_with('=') {
+            continue;
+        }
+
+        let value = tag[tag.len() - rest.len() + 1..].trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next(),
+            _ => value.split(char::is_whitespace).next(),
+        };
+        return value.map(str::to_string);
+    }
+    None
+}
+
+/// Decode the character references HN uses, leaving unknown ones as they are.
+pub fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        // The longest reference HN sends is `&#x1F600;`
+        let decoded = rest.as_bytes()[1..rest.len().min(12)]
+            .iter()
+            .position(|&b| b == b';')
+            .and_then(|semi| Some((decode_entity(&rest[1..semi + 1])?, semi + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => {
+            let code = match entity.strip_prefix('#')? {
+                hex if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok()?,
+                decimal => decimal.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+/// Remove the indentation shared by all non-blank lines.
+fn dedent(code: &str) -> String {
+    let indent = code
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start_matches(' ').len())
+        .min()
+        .unwrap_or(0);
+    code.lines()
+        .map(|line| line.get(indent..).unwrap_or_else(|| line.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_start_matches('\n')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Span {
+        Span {
+            text: text.to_string(),
+            italic: false,
+            link: None,
+        }
+    }
+
+    #[test]
+    fn test_paragraphs_italics_and_entities() {
+        let html = "It&#x27;s <i>really</i> fast &amp; small.<p>Second &quot;one&quot;";
+        assert_eq!(
+            parse_comment(html),
+            vec![
+                CommentBlock::Paragraph(vec![
+                    text("It's "),
+                    Span {
+                        text: "really".to_string(),
+                        italic: true,
+                        link: None,
+                    },
+                    text(" fast & small."),
+                ]),
+                CommentBlock::Paragraph(vec![text("Second \"one\"")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_links_keep_their_target() {
+        let html = r#"See <a href="https:&#x2F;&#x2F;example.com&#x2F;a?b=1&amp;c=2" rel="nofollow">https:&#x2F;&#x2F;example.com&#x2F;a?b=1...</a> for more"#;
+        assert_eq!(
+            parse_comment(html),
+            vec![CommentBlock::Paragraph(vec![
+                text("See "),
+                Span {
+                    text: "https://example.com/a?b=1...".to_string(),
+                    italic: false,
+                    link: Some("https://example.com/a?b=1&c=2".to_string()),
+                },
+                text(" for more"),
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_code_blocks_keep_whitespace() {
+        let html = "Try this:<p><pre><code>  fn main() {\n      println!(&quot;&lt;hi&gt;&quot;);\n  }\n</code></pre>Works?";
+        assert_eq!(
+            parse_comment(html),
+            vec![
+                CommentBlock::Paragraph(vec![text("Try this:")]),
+                CommentBlock::Code("fn main() {\n    println!(\"<hi>\");\n}".to_string()),
+                CommentBlock::Paragraph(vec![text("Works?")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quoted_paragraphs() {
+        let html = "&gt; the parent said this<p>And I disagree &gt;:(";
+        assert_eq!(
+            parse_comment(html),
+            vec![
+                CommentBlock::Quote(vec![text("the parent said this")]),
+                CommentBlock::Paragraph(vec![text("And I disagree >:(")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_input_is_kept_as_text() {
+        assert_eq!(
+            parse_comment("1 < 2 &bogus; &#xZZ; &amp;é"),
+            vec![CommentBlock::Paragraph(vec![text(
+                "1 < 2 &bogus; &#xZZ; &é"
+            )])]
+        );
+        assert!(parse_comment("<p></p>").is_empty());
+    }
+}
@@ -40,9 +40,29 @@ pub fn format_relative_time(timestamp: i64) -> String {
     format_timestamp(&timestamp)
 }
 
+/// Format how long ago an account was created, e.g. "12 years", "3 months" or "5 days".
+pub fn format_account_age(created: i64) -> String {
+    let now_secs = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(dur) => dur.as_secs() as i64,
+        Err(_) => return "unknown".to_string(),
+    };
+
+    let days = (now_secs - created).max(0) / 86_400;
+    let (count, unit) = match days {
+        d if d >= 365 => (d / 365, "year"),
+        d if d >= 30 => (d / 30, "month"),
+        d => (d, "day"),
+    };
+
+    match count {
+        1 => format!("1 {}", unit),
+        n => format!("{} {}s", n, unit),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::format_timestamp;
+    use super::{format_account_age, format_timestamp};
     use std::time::{SystemTime, UNIX_EPOCH};
 
     // Helper to get current unix seconds
@@ -78,4 +98,14 @@ mod tests {
         // 3 days ago
         assert_eq!(format_timestamp(&(now - 3 * 86_400)), "3d ago");
     }
+
+    #[test]
+    fn formats_account_age() {
+        let now = now_secs();
+
+        assert_eq!(format_account_age(now), "0 days");
+        assert_eq!(format_account_age(now - 86_400), "1 day");
+        assert_eq!(format_account_age(now - 45 * 86_400), "1 month");
+        assert_eq!(format_account_age(now - 3 * 365 * 86_400), "3 years");
+    }
 }
@@ -0,0 +1,636 @@
+//! Conversion of article HTML to CommonMark for `internal::markdown` to render.
+//!
+//! Headings, emphasis, lists, block quotes, code blocks and links are kept as
+//! Markdown, tables become GFM tables, and other elements are reduced to their
+//! text. Relative links and images are resolved against the page URL so they
+//! can be opened from the article view.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::Url;
+use scraper::{ElementRef, Html, Node};
+
+/// Elements with nothing to read in them
+const SKIPPED_TAGS: [&str; 14] = [
+    "head", "script", "style", "noscript", "template", "title", "svg", "canvas", "iframe",
+    "button", "input", "select", "textarea", "object",
+];
+
+/// Elements that start a new block rather than continuing the current paragraph
+const BLOCK_TAGS: [&str; 38] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "center",
+    "details",
+    "dialog",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Class prefixes that name the language of a code block
+const LANGUAGE_PREFIXES: [&str; 4] = ["language-", "lang-", "highlight-source-", "highlight-"];
+
+/// Stands in for `<br>` until whitespace in a paragraph has been collapsed
+const HARD_BREAK: char = '\u{E000}';
+
+static WHITESPACE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").expect("valid regex"));
+static ORDERED_MARKER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\d{1,9})([.)])(\s|$)").expect("valid regex"));
+
+/// Convert `html`, a whole page or a fragment, to Markdown. Relative URLs are
+/// resolved against `base_url` when given.
+pub fn html_to_markdown(html: &str, base_url: Option<&str>) -> String {
+    let document = Html::parse_document(html);
+    let converter = Converter {
+        base: base_url.and_then(|url| Url::parse(url).ok()),
+    };
+    join_blocks(&converter.blocks(document.root_element()))
+}
+
+/// Escape the characters of plain `text` that Markdown would read as markup.
+pub fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '~' | '&') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+struct Block {
+    markdown: String,
+    is_list: bool,
+}
+
+impl Block {
+    fn new(markdown: String) -> Self {
+        Self {
+            markdown,
+            is_list: false,
+        }
+    }
+}
+
+fn join_blocks(blocks: &[Block]) -> String {
+    blocks
+        .iter()
+        .map(|block| block.markdown.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+struct Converter {
+    base: Option<Url>,
+}
+
+impl Converter {
+    /// Convert the children of `el` to a sequence of blocks. Text and inline
+    /// elements between block elements become paragraphs.
+    fn blocks(&self, el: ElementRef) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut paragraph = String::new();
+        for child in el.children() {
+            match child.value() {
+                Node::Text(text) => paragraph.push_str(&escape_text(text)),
+                Node::Element(_) => {
+                    let Some(child) = ElementRef::wrap(child) else {
+                        continue;
+                    };
+                    let tag = child.value().name();
+                    if SKIPPED_TAGS.contains(&tag) {
+                        continue;
+                    }
+                    match BLOCK_TAGS.contains(&tag) {
+                        true => {
+                            flush_paragraph(&mut paragraph, &mut blocks);
+                            self.block(child, &mut blocks);
+                        }
+                        false => self.inline(child, &mut paragraph),
+                    }
+                }
+                _ => {}
+            }
+        }
+        flush_paragraph(&mut paragraph, &mut blocks);
+        blocks
+    }
+
+    fn block(&self, el: ElementRef, blocks: &mut Vec<Block>) {
+        match el.value().name() {
+            tag @ ("h1" | "h2" | "h3" | "h4" | "h5" | "h6") => {
+                let level = tag[1..].parse().unwrap_or(1);
+                let text = self.inline_text(el);
+                if !text.is_empty() {
+                    blocks.push(Block::new(format!("{} {}", "#".repeat(level), text)));
+                }
+            }
+            "pre" => blocks.extend(code_block(el).map(Block::new)),
+            "blockquote" => {
+                let quoted = join_blocks(&self.blocks(el));
+                if !quoted.is_empty() {
+                    blocks.push(Block::new(prefix_lines(&quoted, "> ", ">")));
+                }
+            }
+            "ul" | "ol" => blocks.extend(self.list(el)),
+            "table" => self.table(el, blocks),
+            "hr" => blocks.push(Block::new("---".to_string())),
+            _ => blocks.extend(self.blocks(el)),
+        }
+    }
+
+    /// Append the Markdown for inline element `el` to `out`.
+    fn inline(&self, el: ElementRef, out: &mut String) {
+        let tag = el.value().name();
+        match tag {
+            _ if SKIPPED_TAGS.contains(&tag) => {}
+            "br" => out.push(HARD_BREAK),
+            "strong" | "b" => out.push_str(&self.wrap_children(el, "**")),
+            "em" | "i" => out.push_str(&self.wrap_children(el, "*")),
+            "del" | "s" | "strike" => out.push_str(&self.wrap_children(el, "~~")),
+            "code" | "kbd" | "samp" | "tt" => {
+                let code: String = el.text().collect();
+                out.push_str(&code_span(&WHITESPACE.replace_all(&code, " ")));
+            }
+            "a" => self.link(el, out),
+            "img" => {
+                if let Some(src) = el.value().attr("src") {
+                    let alt = escape_text(el.value().attr("alt").unwrap_or_default());
+                    out.push_str(&format!("![{}]({})", alt.trim(), self.destination(src)));
+                }
+            }
+            // A block inside an inline element, such as a `div` in a link
+            _ if BLOCK_TAGS.contains(&tag) => {
+                out.push(' ');
+                self.inline_children(el, out);
+                out.push(' ');
+            }
+            _ => self.inline_children(el, out),
+        }
+    }
+
+    fn inline_children(&self, el: ElementRef, out: &mut String) {
+        for child in el.children() {
+            match child.value() {
+                Node::Text(text) => out.push_str(&escape_text(text)),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.inline(child, out);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The inline content of `el` on a single line.
+    fn inline_text(&self, el: ElementRef) -> String {
+        let mut text = String::new();
+        self.inline_children(el, &mut text);
+        collapse_whitespace(&text.replace(HARD_BREAK, " "))
+    }
+
+    /// Surround the content of `el` with `marker`, keeping outer whitespace
+    /// outside, where CommonMark needs it for the emphasis to count.
+    fn wrap_children(&self, el: ElementRef, marker: &str) -> String {
+        let mut inner = String::new();
+        self.inline_children(el, &mut inner);
+        let trimmed = inner.trim();
+        if trimmed.is_empty() {
+            return inner;
+        }
+        let lead = if inner.starts_with(char::is_whitespace) {
+            " "
+        } else {
+            ""
+        };
+        let trail = if inner.ends_with(char::is_whitespace) {
+            " "
+        } else {
+            ""
+        };
+        format!("{}{}{}{}{}", lead, marker, trimmed, marker, trail)
+    }
+
+    fn link(&self, el: ElementRef, out: &mut String) {
+        let href = el.value().attr("href").unwrap_or_default().trim();
+        // In-page anchors, scripts and app schemes lead nowhere outside the original page
+        if href.is_empty() || href.starts_with('#') || !is_followable(href) {
+            self.inline_children(el, out);
+            return;
+        }
+
+        let destination = self.destination(href);
+        let mut text = String::new();
+        self.inline_children(el, &mut text);
+        let trimmed = collapse_whitespace(&text.replace(HARD_BREAK, " "));
+        if trimmed.is_empty() {
+            out.push_str(&format!("[{}]({})", escape_text(href), destination));
+            return;
+        }
+        if text.starts_with(char::is_whitespace) {
+            out.push(' ');
+        }
+        out.push_str(&format!("[{}]({})", trimmed, destination));
+        if text.ends_with(char::is_whitespace) {
+            out.push(' ');
+        }
+    }
+
+    /// `url` resolved against the page, in a form that is safe as a link destination.
+    fn destination(&self, url: &str) -> String {
+        let url = match &self.base {
+            Some(base) => base
+                .join(url)
+                .map(String::from)
+                .unwrap_or_else(|_| url.to_string()),
+            None => url.to_string(),
+        };
+        match url.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+            true => format!("<{}>", url.replace('<', "%3C").replace('>', "%3E")),
+            false => url,
+        }
+    }
+
+    fn list(&self, el: ElementRef) -> Option<Block> {
+        let ordered = el.value().name() == "ol";
+        let start: usize = el
+            .value()
+            .attr("start")
+            .and_then(|start| start.trim().parse().ok())
+            .unwrap_or(1);
+
+        let mut items: Vec<Vec<Block>> = Vec::new();
+        for child in el.child_elements() {
+            match child.value().name() {
+                "li" => items.push(self.blocks(child)),
+                // A list nested directly in a list belongs to the item before it
+                "ul" | "ol" => {
+                    if let (Some(item), Some(nested)) = (items.last_mut(), self.list(child)) {
+                        item.push(nested);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let lines: Vec<String> = items
+            .iter()
+            .filter(|blocks| !blocks.is_empty())
+            .enumerate()
+            .map(|(index, blocks)| {
+                let marker = match ordered {
+                    true => format!("{}. ", start + index),
+                    false => "- ".to_string(),
+                };
+                // Keep the list tight unless an item has several paragraphs
+                let separator = match blocks.iter().skip(1).all(|block| block.is_list) {
+                    true => "\n",
+                    false => "\n\n",
+                };
+                let content = blocks
+                    .iter()
+                    .map(|block| block.markdown.as_str())
+                    .collect::<Vec<_>>()
+                    .join(separator);
+                let indent = " ".repeat(marker.len());
+                format!(
+                    "{}{}",
+                    marker,
+                    prefix_lines(&content, &indent, "").trim_start()
+                )
+            })
+            .collect();
+
+        (!lines.is_empty()).then(|| Block {
+            markdown: lines.join("\n"),
+            is_list: true,
+        })
+    }
+
+    fn table(&self, el: ElementRef, blocks: &mut Vec<Block>) {
+        let rows: Vec<Vec<ElementRef>> = table_rows(el)
+            .into_iter()
+            .map(|row| {
+                row.child_elements()
+                    .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+                    .collect()
+            })
+            .collect();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+
+        // Tables used for page layout hold blocks rather than values
+        let is_layout = columns < 2
+            || rows.iter().flatten().any(|cell| {
+                cell.descendent_elements()
+                    .skip(1)
+                    .any(|el| BLOCK_TAGS.contains(&el.value().name()))
+            });
+        if is_layout {
+            for cell in rows.iter().flatten() {
+                blocks.extend(self.blocks(*cell));
+            }
+            return;
+        }
+
+        if let Some(caption) = el
+            .child_elements()
+            .find(|el| el.value().name() == "caption")
+        {
+            let caption = self.inline_text(caption);
+            if !caption.is_empty() {
+                blocks.push(Block::new(caption));
+            }
+        }
+
+        let mut lines = Vec::with_capacity(rows.len() + 1);
+        for (index, row) in rows.iter().enumerate() {
+            let mut cells: Vec<String> = row
+                .iter()
+                .map(|cell| self.inline_text(*cell).replace('|', "\\|"))
+                .collect();
+            cells.resize(columns, String::new());
+            lines.push(format!("| {} |", cells.join(" | ")));
+            if index == 0 {
+                lines.push(format!("|{}", " --- |".repeat(columns)));
+            }
+        }
+        blocks.push(Block::new(lines.join("\n")));
+    }
+}
+
+/// The rows of `table`, without those of tables nested in it.
+fn table_rows(table: ElementRef<'_>) -> Vec<ElementRef<'_>> {
+    table
+        .child_elements()
+        .flat_map(|child| match child.value().name() {
+            "tr" => vec![child],
+            "thead" | "tbody" | "tfoot" => child
+                .child_elements()
+                .filter(|row| row.value().name() == "tr")
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+/// A fenced code block, with the language if the page names one.
+fn code_block(pre: ElementRef) -> Option<String> {
+    let mut code = String::new();
+    pre_text(pre, &mut code);
+    let code = code.trim_end().trim_start_matches('\n');
+    if code.trim().is_empty() {
+        return None;
+    }
+
+    let fence = "`".repeat((longest_run(code, '`') + 1).max(3));
+    let language = code_language(pre).unwrap_or_default();
+    Some(format!("{}{}\n{}\n{}", fence, language, code, fence))
+}
+
+/// The text of a `pre`, with whitespace kept and `<br>` as line breaks.
+fn pre_text(el: ElementRef, out: &mut String) {
+    for child in el.children() {
+        match child.value() {
+            Node::Text(text) => out.push_str(text),
+            Node::Element(element) if element.name() == "br" => out.push('\n'),
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    pre_text(child, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The language named by `pre`, its `code` element or the wrappers syntax
+/// highlighters put around it, e.g. `class="language-rust"` or `data-lang="go"`.
+fn code_language(pre: ElementRef) -> Option<String> {
+    let code = pre
+        .child_elements()
+        .find(|child| child.value().name() == "code");
+    let parent = pre.parent().and_then(ElementRef::wrap);
+    let grandparent = parent.and_then(|p| p.parent()).and_then(ElementRef::wrap);
+
+    [Some(pre), code, parent, grandparent]
+        .into_iter()
+        .flatten()
+        .find_map(|el| {
+            let value = el.value();
+            value
+                .attr("data-lang")
+                .or_else(|| value.attr("data-language"))
+                .or_else(|| {
+                    value.classes().find_map(|class| {
+                        LANGUAGE_PREFIXES
+                            .iter()
+                            .find_map(|prefix| class.strip_prefix(prefix))
+                    })
+                })
+                .map(|language| {
+                    language
+                        .chars()
+                        .filter(|c| c.is_ascii_alphanumeric() || "+#-_.".contains(*c))
+                        .collect::<String>()
+                        .to_ascii_lowercase()
+                })
+                .filter(|language| !language.is_empty())
+        })
+}
+
+/// An inline code span, fenced with enough backticks to hold `code`.
+fn code_span(code: &str) -> String {
+    let trimmed = code.trim();
+    if trimmed.is_empty() {
+        return code.to_string();
+    }
+    let fence = "`".repeat(longest_run(trimmed, '`') + 1);
+    match trimmed.starts_with('`') || trimmed.ends_with('`') {
+        true => format!("{} {} {}", fence, trimmed, fence),
+        false => format!("{}{}{}", fence, trimmed, fence),
+    }
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    text.split(|ch| ch != c).map(str::len).max().unwrap_or(0)
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    WHITESPACE.replace_all(text, " ").trim().to_string()
+}
+
+/// Close the paragraph collected so far, if it has any text.
+/// Whether `href` is a web or mail link, or a path relative to the page.
+fn is_followable(href: &str) -> bool {
+    // Browsers drop tabs and newlines inside URLs, so `java\tscript:` is a script too
+    let href: String = href
+        .trim()
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match href.find([':', '/', '?', '#']) {
+        Some(end) if href[end..].starts_with(':') => {
+            matches!(&href[..end], "http" | "https" | "mailto")
+        }
+        _ => true,
+    }
+}
+
+fn flush_paragraph(paragraph: &mut String, blocks: &mut Vec<Block>) {
+    let text = collapse_whitespace(paragraph);
+    paragraph.clear();
+    let lines: Vec<String> = text
+        .split(HARD_BREAK)
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(escape_line_start)
+        .collect();
+    if !lines.is_empty() {
+        blocks.push(Block::new(lines.join("\\\n")));
+    }
+}
+
+/// Escape text at the start of a line that would otherwise begin a heading,
+/// quote, list or thematic break.
+pub(crate) fn escape_line_start(line: &str) -> String {
+    if line.starts_with(['#', '>', '-', '+', '=']) {
+        return format!("\\{}", line);
+    }
+    ORDERED_MARKER.replace(line, "$1\\$2$3").into_owned()
+}
+
+fn prefix_lines(text: &str, prefix: &str, blank_prefix: &str) -> String {
+    text.lines()
+        .map(|line| match line.is_empty() {
+            true => blank_prefix.to_string(),
+            false => format!("{}{}", prefix, line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(html: &str) -> String {
+        html_to_markdown(html, Some("https://example.com/blog/post.html"))
+    }
+
+    #[test]
+    fn test_headings_emphasis_and_links() {
+        let html = r#"<h1>Title</h1>
+            <p>Some <strong>bold</strong>, <em>italic </em>text and a
+            <a href="/about">relative link</a>.<br>Next line</p>
+            <h3>Section <code>x</code></h3>"#;
+        assert_eq!(
+            convert(html),
+            "# Title\n\n\
+             Some **bold**, *italic* text and a [relative link](https://example.com/about).\\\n\
+             Next line\n\n\
+             ### Section `x`"
+        );
+    }
+
+    #[test]
+    fn test_code_blocks_keep_whitespace_and_language() {
+        let html = "<pre><code class=\"language-rust\">fn main() {\n    println!(\"&lt;hi&gt;\");\n}\n</code></pre>\
+            <div class=\"highlight-python\"><div class=\"highlight\"><pre>x = 1</pre></div></div>\
+            <pre>```\nnested fence\n```</pre>";
+        assert_eq!(
+            convert(html),
+            "```rust\nfn main() {\n    println!(\"<hi>\");\n}\n```\n\n\
+             ```python\nx = 1\n```\n\n\
+             ````\n```\nnested fence\n```\n````"
+        );
+    }
+
+    #[test]
+    fn test_nested_lists_and_quotes() {
+        let html = r#"<ul><li>One</li><li>Two<ol start="3"><li>Three</li><li>Four</li></ol></li></ul>
+            <blockquote><p>Quoted</p><p>Twice</p></blockquote>"#;
+        assert_eq!(
+            convert(html),
+            "- One\n- Two\n  3. Three\n  4. Four\n\n> Quoted\n>\n> Twice"
+        );
+    }
+
+    #[test]
+    fn test_tables() {
+        let html = r#"<table><thead><tr><th>Name</th><th>Value</th></tr></thead>
+            <tbody><tr><td>a|b</td><td><b>1</b></td></tr><tr><td>c</td></tr></tbody></table>"#;
+        assert_eq!(
+            convert(html),
+            "| Name | Value |\n| --- | --- |\n| a\\|b | **1** |\n| c |  |"
+        );
+
+        // Layout tables are read as their contents
+        let layout = "<table><tr><td><p>Left</p></td><td><p>Right</p></td></tr></table>";
+        assert_eq!(convert(layout), "Left\n\nRight");
+    }
+
+    #[test]
+    fn test_markdown_in_text_is_escaped() {
+        let html = "<p># not a heading, *not emphasis* and 1. not a list</p><p>2019. A year</p>";
+        assert_eq!(
+            convert(html),
+            "\\# not a heading, \\*not emphasis\\* and 1. not a list\n\n2019\\. A year"
+        );
+    }
+
+    #[test]
+    fn test_scripts_and_anchor_links_are_dropped() {
+        let html = r##"<p>Read <a href="#notes">the notes</a><script>track()</script></p>
+            <p><img src="fig.png" alt="Figure 1"></p>"##;
+        assert_eq!(
+            convert(html),
+            "Read the notes\n\n![Figure 1](https://example.com/blog/fig.png)"
+        );
+    }
+
+    #[test]
+    fn test_only_web_and_mail_links_are_kept() {
+        let html = r#"<p><a href=" JavaScript:alert(1)">a</a> <a href="java&#9;script:alert(1)">b</a>
+            <a href="data:text/html,hi">c</a> <a href="HTTPS://example.org/x">d</a>
+            <a href="mailto:me@example.org">e</a> <a href="notes/x:y">f</a></p>"#;
+        assert_eq!(
+            convert(html),
+            "a b c [d](https://example.org/x) [e](mailto:me@example.org) \
+             [f](https://example.com/blog/notes/x:y)"
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! Numbered index of the links in an open story.
+//!
+//! Links are numbered in reading order, the article or self-post first and then
+//! the comments as they are loaded, so the numbers shown as footnote markers stay
+//! put while more comments come in. A page linked more than once keeps the number
+//! of its first link. Only web links are numbered.
+
+use crate::utils::comment_html::{CommentBlock, parse_comment};
+use comrak::nodes::{AstNode, NodeValue};
+use comrak::{Arena, Options, parse_document};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    pub url: String,
+    /// Text of the first link to `url`, empty if it had none
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LinkIndex {
+    links: Vec<Link>,
+    numbers: HashMap<String, usize>,
+}
+
+impl LinkIndex {
+    /// Add a link to `url`, returning its number, or `None` for links that aren't
+    /// to web pages.
+    pub fn add(&mut self, url: &str, text: &str) -> Option<usize> {
+        let scheme = url.split_once(':')?.0.to_ascii_lowercase();
+        if scheme != "http" && scheme != "https" {
+            return None;
+        }
+        if let Some(&number) = self.numbers.get(url) {
+            return Some(number);
+        }
+
+        self.links.push(Link {
+            url: url.to_string(),
+            text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+        });
+        let number = self.links.len();
+        self.numbers.insert(url.to_string(), number);
+        Some(number)
+    }
+
+    /// Add the links of Markdown text, as rendered by `render_markdown`.
+    pub fn add_markdown(&mut self, markdown: &str) {
+        let arena = Arena::new();
+        let mut options = Options::default();
+        options.extension.table = true;
+        options.extension.strikethrough = true;
+        let root = parse_document(&arena, markdown, &options);
+
+        for node in root.descendants() {
+            if let NodeValue::Link(link) = &node.data.borrow().value {
+                self.add(&link.url, &node_text(node));
+            }
+        }
+    }
+
+    /// Add the links of comment or self-post HTML from the HN API.
+    pub fn add_hn_text(&mut self, html: &str) {
+        for block in parse_comment(html) {
+            let (CommentBlock::Paragraph(spans) | CommentBlock::Quote(spans)) = block else {
+                continue;
+            };
+            for span in spans {
+                if let Some(url) = &span.link {
+                    self.add(url, &span.text);
+                }
+            }
+        }
+    }
+
+    /// The number of the link to `url`, if it is in the index.
+    pub fn number(&self, url: &str) -> Option<usize> {
+        self.numbers.get(url).copied()
+    }
+
+    /// Link `number`, counting from 1.
+    pub fn get(&self, number: usize) -> Option<&Link> {
+        self.links.get(number.checked_sub(1)?)
+    }
+
+    /// The links with their numbers, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Link)> {
+        self.links
+            .iter()
+            .enumerate()
+            .map(|(index, link)| (index + 1, link))
+    }
+
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+}
+
+fn node_text<'a>(node: &'a AstNode<'a>) -> String {
+    node.descendants()
+        .filter_map(|child| match &child.data.borrow().value {
+            NodeValue::Text(text) => Some(text.to_string()),
+            NodeValue::Code(code) => Some(code.literal.clone()),
+            NodeValue::SoftBreak | NodeValue::LineBreak => Some(" ".to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_links_are_numbered_in_order_without_duplicates() {
+        let mut index = LinkIndex::default();
+        index.add_markdown(
+            "See [the *docs*](https://example.com/docs) and ![a chart](https://example.com/chart.png).\n\n\
+             [Mail me](mailto:me@example.com), or read [them again](https://example.com/docs).",
+        );
+        index.add_hn_text(
+            r#"<i>&gt; quoted</i><p>Also <a href="https:&#x2F;&#x2F;news.ycombinator.com&#x2F;item?id=1">https:&#x2F;&#x2F;news.ycombinator.com&#x2F;item?id=1</a>"#,
+        );
+
+        assert_eq!(index.len(), 2);
+        assert_eq!(
+            index.get(1),
+            Some(&Link {
+                url: "https://example.com/docs".to_string(),
+                text: "the docs".to_string(),
+            })
+        );
+        assert_eq!(
+            index.number("https://news.ycombinator.com/item?id=1"),
+            Some(2)
+        );
+        assert_eq!(index.number("mailto:me@example.com"), None);
+        assert_eq!(index.get(0), None);
+        assert_eq!(index.get(3), None);
+    }
+}
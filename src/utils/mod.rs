@@ -1,5 +1,10 @@
+pub mod comment_html;
 pub mod datetime;
 pub mod html;
+pub mod html_to_markdown;
+pub mod links;
+pub mod pdf;
+pub mod readability;
 pub mod theme;
 pub mod theme_export;
 pub mod url;
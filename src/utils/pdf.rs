@@ -0,0 +1,159 @@
+//! Text extraction from PDF documents such as papers and slide decks.
+//!
+//! Text is taken from each page's content stream in drawing order, which for
+//! most generated PDFs is reading order. Scanned documents have no text to take.
+//! The outline, the bookmarks a viewer shows in its sidebar, is kept with the
+//! page each entry points to.
+
+use crate::utils::html_to_markdown::{escape_line_start, escape_text};
+use anyhow::{Context, Result, anyhow};
+use pdf_extract::{Document, OutputError, PlainTextOutput};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+/// An entry of the document outline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineEntry {
+    pub title: String,
+    /// Nesting depth, 1 for top-level entries
+    pub level: usize,
+    /// 1-based number of the page the entry points to
+    pub page: usize,
+}
+
+/// The text of a PDF, page by page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfDocument {
+    pub outline: Vec<OutlineEntry>,
+    /// Text of each page, empty for pages without any
+    pub pages: Vec<String>,
+}
+
+/// Extract the text and outline of the PDF in `bytes`. Documents encrypted with
+/// an empty user password, as many "copy protected" papers are, are decrypted.
+pub fn extract_pdf(bytes: &[u8]) -> Result<PdfDocument> {
+    let mut document = Document::load_mem(bytes).context("not a valid PDF")?;
+    if document.is_encrypted() {
+        document
+            .decrypt("")
+            .map_err(|_| anyhow!("the PDF is password protected"))?;
+    }
+
+    // The extractor panics on some malformed fonts rather than returning an error
+    let pages = catch_unwind(AssertUnwindSafe(|| {
+        document
+            .get_pages()
+            .keys()
+            .map(|&number| page_text(&document, number))
+            .collect::<Result<Vec<_>, _>>()
+    }))
+    .map_err(|_| anyhow!("the PDF uses features that can't be read"))?
+    .context("can't read the PDF text")?;
+
+    // A document without outline is an error to lopdf
+    let outline = document
+        .get_toc()
+        .map(|toc| {
+            toc.toc
+                .into_iter()
+                .map(|entry| OutlineEntry {
+                    title: entry.title.trim().to_string(),
+                    level: entry.level,
+                    page: entry.page,
+                })
+                .filter(|entry| !entry.title.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(PdfDocument { outline, pages })
+}
+
+fn page_text(document: &Document, number: u32) -> Result<String, OutputError> {
+    let mut text = String::new();
+    let mut output = PlainTextOutput::new(&mut text);
+    pdf_extract::output_doc_page(document, &mut output, number)?;
+    Ok(text.trim().to_string())
+}
+
+impl PdfDocument {
+    /// Whether no page has any text, as in a scanned document.
+    pub fn is_empty(&self) -> bool {
+        self.pages.iter().all(|page| page.is_empty())
+    }
+
+    /// The document as Markdown: the outline, if any, followed by the pages, each
+    /// below a marker with its number.
+    pub fn markdown(&self) -> String {
+        let mut blocks = Vec::new();
+
+        if !self.outline.is_empty() {
+            blocks.push("**Contents**".to_string());
+            let lowest = self.outline.iter().map(|entry| entry.level).min();
+            let entries: Vec<String> = self
+                .outline
+                .iter()
+                .map(|entry| {
+                    let depth = entry.level - lowest.unwrap_or(entry.level);
+                    format!(
+                        "{}- {} · p. {}",
+                        "  ".repeat(depth),
+                        escape_text(&entry.title),
+                        entry.page
+                    )
+                })
+                .collect();
+            blocks.push(entries.join("\n"));
+        }
+
+        let count = self.pages.len();
+        for (index, page) in self.pages.iter().enumerate() {
+            if !blocks.is_empty() {
+                blocks.push("---".to_string());
+            }
+            blocks.push(format!("*Page {} of {}*", index + 1, count));
+            match page.is_empty() {
+                true => blocks.push("*No text on this page*".to_string()),
+                false => blocks.extend(paragraphs(page)),
+            }
+        }
+        blocks.join("\n\n")
+    }
+}
+
+/// Split page text into Markdown paragraphs at blank lines. Lines within a
+/// paragraph are the PDF's own line breaks, so they are joined and reflowed,
+/// undoing hyphenation at line ends.
+fn paragraphs(text: &str) -> Vec<String> {
+    text.split("\n\n")
+        .filter_map(|paragraph| {
+            let mut joined = String::new();
+            for line in paragraph.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                if joined.ends_with('-') && line.starts_with(char::is_lowercase) {
+                    joined.pop();
+                } else if !joined.is_empty() {
+                    joined.push(' ');
+                }
+                joined.push_str(line);
+            }
+            (!joined.is_empty()).then(|| escape_line_start(&escape_text(&joined)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paragraphs_are_reflowed_and_escaped() {
+        assert_eq!(
+            paragraphs("Cache evic-\ntion is\nhard.\n\n# of hits\n\n2. Method"),
+            vec![
+                "Cache eviction is hard.".to_string(),
+                "\\# of hits".to_string(),
+                "2\\. Method".to_string(),
+            ]
+        );
+        assert_eq!(paragraphs("long-\nTerm"), vec!["long- Term".to_string()]);
+    }
+}
@@ -0,0 +1,443 @@
+//! Readability-style extraction of the main content of an article page.
+//!
+//! Every paragraph-like element with enough text scores points for its parent and,
+//! less so, its further ancestors. Points depend on the amount of text and commas,
+//! ancestors gain or lose points for their tag and class names, and a high share
+//! of link text counts against them. The best scoring element, together with
+//! siblings that look like part of the same text, is taken as the article body.
+//! Navigation, sidebars, banners and similar page chrome inside it are dropped.
+
+use crate::utils::html_to_markdown::html_to_markdown;
+use jiff::civil::Date;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashMap;
+
+/// Paragraphs shorter than this don't count towards their ancestors' scores
+const MIN_PARAGRAPH_CHARS: usize = 25;
+/// How many ancestors of a paragraph share in its score
+const SCORED_ANCESTORS: usize = 5;
+/// Bylines longer than this are probably a paragraph with "author" in its class
+const MAX_BYLINE_CHARS: usize = 100;
+
+/// Elements that never hold article text
+const SKIPPED_TAGS: [&str; 15] = [
+    "script", "style", "noscript", "template", "nav", "aside", "footer", "header", "form",
+    "button", "input", "select", "iframe", "svg", "dialog",
+];
+
+/// ARIA roles of page chrome
+const SKIPPED_ROLES: [&str; 6] = [
+    "navigation",
+    "banner",
+    "complementary",
+    "contentinfo",
+    "dialog",
+    "alert",
+];
+
+/// Elements that start a block, so a `div` holding one isn't a paragraph itself
+const BLOCK_TAGS: [&str; 14] = [
+    "address",
+    "article",
+    "blockquote",
+    "div",
+    "dl",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+];
+
+static PARAGRAPHS: Lazy<Selector> =
+    Lazy::new(|| Selector::parse("p, pre, td, blockquote, div, section").expect("valid selector"));
+static LINKS: Lazy<Selector> = Lazy::new(|| Selector::parse("a").expect("valid selector"));
+static WHITESPACE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").expect("valid regex"));
+
+/// Class or id of elements that are almost never part of an article
+static UNLIKELY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)-ad-|\bads?\b|banner|breadcrumb|combx|comment|consent|cookie|disqus|footer|gdpr|header|menu|modal|\bnav|newsletter|popup|promo|related|share|sidebar|skyscraper|social|sponsor|subscribe|toolbar|widget",
+    )
+    .expect("valid regex")
+});
+/// Class or id that rescues an otherwise unlikely element
+static MAYBE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)article|body|column|content|main|story").expect("valid regex"));
+static POSITIVE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)article|body|content|entry|hentry|h-entry|main|page|post|text|blog|story")
+        .expect("valid regex")
+});
+static NEGATIVE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)-ad-|hidden|banner|combx|comment|contact|cookie|footer|footnote|masthead|meta|modal|\bnav|outbrain|promo|related|share|sidebar|skyscraper|social|sponsor|subscribe|tags|widget",
+    )
+    .expect("valid regex")
+});
+
+/// The main content of an article page and what the page says about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedArticle {
+    pub title: Option<String>,
+    pub byline: Option<String>,
+    pub published: Option<Date>,
+    /// HTML of the article body, without the surrounding page
+    pub content: String,
+}
+
+impl ExtractedArticle {
+    /// The article body as Markdown, with links resolved against `base_url`.
+    pub fn markdown(&self, base_url: Option<&str>) -> String {
+        html_to_markdown(&self.content, base_url)
+    }
+}
+
+/// Find the main content of `html`. Returns `None` if the page has no text to show.
+pub fn extract_article(html: &str) -> Option<ExtractedArticle> {
+    let mut document = Html::parse_document(html);
+    let title = extract_title(&document);
+    let byline = extract_byline(&document);
+    let published = extract_published(&document);
+
+    let (kept, removed) = {
+        let kept = find_content(&document)?;
+        let removed: Vec<_> = kept
+            .iter()
+            .flat_map(|el| el.descendent_elements().skip(1))
+            .filter(|el| is_clutter(*el, title.as_deref(), byline.as_deref()))
+            .map(|el| el.id())
+            .collect();
+        let kept: Vec<_> = kept.iter().map(|el| el.id()).collect();
+        (kept, removed)
+    };
+    for id in removed {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+
+    let kept: Vec<ElementRef> = kept
+        .iter()
+        .filter_map(|&id| document.tree.get(id).and_then(ElementRef::wrap))
+        .collect();
+    if kept.iter().all(|el| normalized_text(*el).is_empty()) {
+        return None;
+    }
+
+    let content = kept
+        .iter()
+        .map(|el| match el.value().name() {
+            "body" => el.inner_html(),
+            _ => el.html(),
+        })
+        .collect::<String>();
+    Some(ExtractedArticle {
+        title,
+        byline,
+        published,
+        content: format!("<div>{}</div>", content),
+    })
+}
+
+/// Pick the best scoring element and the siblings that continue its text.
+fn find_content(document: &Html) -> Option<Vec<ElementRef<'_>>> {
+    let mut scores = HashMap::new();
+    for paragraph in document.select(&PARAGRAPHS) {
+        if !is_paragraph(paragraph) || is_unlikely(paragraph) {
+            continue;
+        }
+        let text = normalized_text(paragraph);
+        let chars = text.chars().count();
+        if chars < MIN_PARAGRAPH_CHARS {
+            continue;
+        }
+
+        let score = 1.0 + text.matches(',').count() as f64 + (chars / 100).min(3) as f64;
+        let ancestors = paragraph
+            .ancestors()
+            .filter_map(ElementRef::wrap)
+            .take(SCORED_ANCESTORS);
+        for (level, ancestor) in ancestors.enumerate() {
+            let divider = match level {
+                0 => 1.0,
+                1 => 2.0,
+                level => level as f64 * 3.0,
+            };
+            *scores
+                .entry(ancestor.id())
+                .or_insert_with(|| initial_score(ancestor)) += score / divider;
+        }
+    }
+
+    let scored: Vec<(ElementRef, f64)> = scores
+        .into_iter()
+        .filter_map(|(id, score)| {
+            let el = document.tree.get(id).and_then(ElementRef::wrap)?;
+            Some((el, score * (1.0 - link_density(el))))
+        })
+        .collect();
+    let Some(&(top, top_score)) = scored.iter().max_by(|a, b| a.1.total_cmp(&b.1)) else {
+        return fallback_content(document).map(|el| vec![el]);
+    };
+
+    let Some(parent) = top.parent().and_then(ElementRef::wrap) else {
+        return Some(vec![top]);
+    };
+    let scores: HashMap<_, f64> = scored.iter().map(|(el, score)| (el.id(), *score)).collect();
+    let threshold = (top_score * 0.2).max(10.0);
+    let kept = parent
+        .child_elements()
+        .filter(|sibling| {
+            sibling.id() == top.id()
+                || scores.get(&sibling.id()).is_some_and(|&s| s >= threshold)
+                || continues_text(*sibling)
+        })
+        .collect();
+    Some(kept)
+}
+
+/// A paragraph next to the top candidate that reads like part of the article.
+fn continues_text(el: ElementRef) -> bool {
+    if el.value().name() != "p" || is_unlikely(el) {
+        return false;
+    }
+    let text = normalized_text(el);
+    let density = link_density(el);
+    match text.chars().count() {
+        n if n > 80 => density < 0.25,
+        n => n > 0 && density == 0.0 && text.contains(". "),
+    }
+}
+
+/// Without any scored paragraphs, use the page's semantic container.
+fn fallback_content(document: &Html) -> Option<ElementRef<'_>> {
+    ["article", "main", "[role=main]", "body"]
+        .iter()
+        .filter_map(|selector| Selector::parse(selector).ok())
+        .find_map(|selector| document.select(&selector).next())
+}
+
+/// Whether the element holds text of its own rather than other blocks.
+fn is_paragraph(el: ElementRef) -> bool {
+    match el.value().name() {
+        "div" | "section" => !el
+            .child_elements()
+            .any(|child| BLOCK_TAGS.contains(&child.value().name())),
+        _ => true,
+    }
+}
+
+/// Whether the element or one of its ancestors below `body` is page chrome.
+fn is_unlikely(el: ElementRef) -> bool {
+    std::iter::once(el)
+        .chain(el.ancestors().filter_map(ElementRef::wrap))
+        .take_while(|el| !matches!(el.value().name(), "body" | "html"))
+        .any(is_chrome)
+}
+
+/// Page chrome by its tag, ARIA role or class names.
+fn is_chrome(el: ElementRef) -> bool {
+    let tag = el.value().name();
+    let role = el.value().attr("role").unwrap_or_default();
+    let names = class_and_id(el);
+    SKIPPED_TAGS.contains(&tag)
+        || SKIPPED_ROLES.contains(&role)
+        || (!matches!(tag, "article" | "main")
+            && UNLIKELY.is_match(&names)
+            && !MAYBE.is_match(&names))
+}
+
+/// Whether an element inside the chosen content should be dropped. The title and
+/// byline are shown separately.
+fn is_clutter(el: ElementRef, title: Option<&str>, byline: Option<&str>) -> bool {
+    if is_chrome(el) || class_weight(el) < 0.0 {
+        return true;
+    }
+    let text = normalized_text(el);
+    if byline.is_some_and(|byline| strip_by(&text) == byline) {
+        return true;
+    }
+    match el.value().name() {
+        // Lists and boxes made mostly of links are menus and "read next" blocks
+        "ul" | "ol" | "div" | "section" | "table" => link_density(el) > 0.5,
+        "h1" | "h2" => title.is_some_and(|title| text == title),
+        _ => false,
+    }
+}
+
+fn initial_score(el: ElementRef) -> f64 {
+    let tag_score = match el.value().name() {
+        "article" => 25.0,
+        "main" => 20.0,
+        "div" => 5.0,
+        "section" | "pre" | "td" | "blockquote" => 3.0,
+        "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+    tag_score + class_weight(el)
+}
+
+/// Points for class, id and `itemprop` names that suggest content or chrome.
+fn class_weight(el: ElementRef) -> f64 {
+    let names = format!(
+        "{} {}",
+        class_and_id(el),
+        el.value().attr("itemprop").unwrap_or_default()
+    );
+    let mut weight = 0.0;
+    if POSITIVE.is_match(&names) {
+        weight += 25.0;
+    }
+    if NEGATIVE.is_match(&names) {
+        weight -= 25.0;
+    }
+    weight
+}
+
+fn class_and_id(el: ElementRef) -> String {
+    format!(
+        "{} {}",
+        el.value().attr("class").unwrap_or_default(),
+        el.value().id().unwrap_or_default()
+    )
+}
+
+/// Share of the element's text that is link text.
+fn link_density(el: ElementRef) -> f64 {
+    let chars = normalized_text(el).chars().count();
+    if chars == 0 {
+        return 0.0;
+    }
+    let link_chars: usize = el
+        .select(&LINKS)
+        .map(|a| normalized_text(a).chars().count())
+        .sum();
+    (link_chars as f64 / chars as f64).min(1.0)
+}
+
+/// Text content with runs of whitespace collapsed.
+fn normalized_text(el: ElementRef) -> String {
+    WHITESPACE
+        .replace_all(&el.text().collect::<String>(), " ")
+        .trim()
+        .to_string()
+}
+
+/// The `content` of the first `<meta>` with one of `names` as its `property` or `name`.
+fn meta_content(document: &Html, names: &[&str]) -> Option<String> {
+    names.iter().find_map(|name| {
+        let selector = Selector::parse(&format!(
+            r#"meta[property="{0}"], meta[name="{0}" i]"#,
+            name
+        ))
+        .ok()?;
+        document
+            .select(&selector)
+            .filter_map(|meta| meta.value().attr("content"))
+            .map(|content| WHITESPACE.replace_all(content, " ").trim().to_string())
+            .find(|content| !content.is_empty())
+    })
+}
+
+fn first_text(document: &Html, selector: &str) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+    document
+        .select(&selector)
+        .map(normalized_text)
+        .find(|text| !text.is_empty())
+}
+
+fn extract_title(document: &Html) -> Option<String> {
+    if let Some(title) = meta_content(document, &["og:title", "twitter:title"]) {
+        return Some(title);
+    }
+    match first_text(document, "title") {
+        Some(title) => Some(strip_site_name(&title)),
+        None => first_text(document, "h1"),
+    }
+}
+
+/// "How we built it | Example Blog" -> "How we built it", unless that leaves too little.
+fn strip_site_name(title: &str) -> String {
+    [" | ", " – ", " — ", " - ", " :: ", " · "]
+        .iter()
+        .find_map(|separator| {
+            let (head, _) = title.rsplit_once(separator)?;
+            (head.split_whitespace().count() >= 3).then(|| head.trim().to_string())
+        })
+        .unwrap_or_else(|| title.to_string())
+}
+
+fn extract_byline(document: &Html) -> Option<String> {
+    let from_meta = meta_content(document, &["author", "article:author", "parsely-author"])
+        .filter(|author| !author.starts_with("http"));
+    let byline = from_meta.or_else(|| {
+        [
+            r#"[itemprop~="author"] [itemprop="name"]"#,
+            r#"[itemprop~="author"]"#,
+            r#"[rel="author"]"#,
+            ".byline",
+            ".author",
+            ".post-author",
+        ]
+        .iter()
+        .find_map(|selector| first_text(document, selector))
+    })?;
+
+    let byline = strip_by(&byline);
+    (!byline.is_empty() && byline.chars().count() <= MAX_BYLINE_CHARS).then(|| byline.to_string())
+}
+
+/// "By Jane Doe" -> "Jane Doe"
+fn strip_by(byline: &str) -> &str {
+    match byline.get(..3) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("by ") => byline[3..].trim(),
+        _ => byline,
+    }
+}
+
+fn extract_published(document: &Html) -> Option<Date> {
+    let from_meta = meta_content(
+        document,
+        &[
+            "article:published_time",
+            "og:published_time",
+            "date",
+            "pubdate",
+            "publishdate",
+            "publish-date",
+            "dc.date.issued",
+            "dc.date",
+        ],
+    );
+    let from_markup = || {
+        [
+            r#"[itemprop="datePublished"]"#,
+            "time[pubdate]",
+            "article time[datetime]",
+            "time[datetime]",
+        ]
+        .iter()
+        .filter_map(|selector| Selector::parse(selector).ok())
+        .find_map(|selector| {
+            document.select(&selector).find_map(|el| {
+                let value = el.value();
+                value
+                    .attr("datetime")
+                    .or_else(|| value.attr("content"))
+                    .map(str::to_string)
+            })
+        })
+    };
+
+    // Keep the date as written, in the publisher's time zone
+    let value = from_meta.or_else(from_markup)?;
+    value.trim().get(..10)?.parse().ok()
+}
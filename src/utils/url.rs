@@ -5,6 +5,18 @@ pub fn extract_domain(url: &str) -> Option<String> {
         .and_then(|u| u.host_str().map(|h| h.to_string()))
 }
 
+/// The id of the item a Hacker News item page URL points to, as in
+/// `https://news.ycombinator.com/item?id=123`
+pub fn hn_item_id(url: &str) -> Option<u32> {
+    let url = reqwest::Url::parse(url).ok()?;
+    if url.host_str()? != "news.ycombinator.com" || url.path() != "/item" {
+        return None;
+    }
+    url.query_pairs()
+        .find(|(key, _)| key == "id")
+        .and_then(|(_, id)| id.parse().ok())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -25,4 +37,19 @@ mod tests {
         );
         assert_eq!(extract_domain("not-a-url"), None);
     }
+
+    #[test]
+    fn test_hn_item_id() {
+        assert_eq!(
+            hn_item_id("https://news.ycombinator.com/item?id=123"),
+            Some(123)
+        );
+        assert_eq!(
+            hn_item_id("http://news.ycombinator.com/item?p=2&id=42#42"),
+            Some(42)
+        );
+        assert_eq!(hn_item_id("https://news.ycombinator.com/user?id=pg"), None);
+        assert_eq!(hn_item_id("https://example.com/item?id=123"), None);
+        assert_eq!(hn_item_id("https://news.ycombinator.com/item?id=abc"), None);
+    }
 }
@@ -0,0 +1,84 @@
+use gpui_hn_app::utils::readability::extract_article;
+use jiff::civil::date;
+
+#[test]
+fn should_keep_only_the_post_body_of_a_blog_page() {
+    let html = include_str!("fixtures/articles/blog_post.html");
+    let article = extract_article(html).expect("blog post has content");
+    let text = article.markdown(None);
+
+    assert_eq!(
+        article.title.as_deref(),
+        Some("Why We Rewrote Our Build System")
+    );
+    assert_eq!(article.byline.as_deref(), Some("Ada Example"));
+    assert_eq!(article.published, Some(date(2024, 3, 5)));
+
+    assert!(text.contains("pile of shell scripts"));
+    assert!(text.contains("What we tried first"));
+    assert!(text.contains("make -j16 all"));
+    assert!(text.contains("under eight minutes"));
+
+    assert!(!text.contains("cookies"), "cookie banner should be dropped");
+    assert!(!text.contains("Careers"), "navigation should be dropped");
+    assert!(!text.contains("Popular posts"), "sidebar should be dropped");
+    assert!(!text.contains("Copyright"), "footer should be dropped");
+    assert!(!text.contains("analytics"), "scripts should be dropped");
+}
+
+#[test]
+fn should_find_the_story_in_a_page_without_semantic_tags() {
+    let html = include_str!("fixtures/articles/news_article.html");
+    let article = extract_article(html).expect("news page has content");
+    let text = article.markdown(None);
+
+    assert_eq!(
+        article.title.as_deref(),
+        Some("City Council Approves New Bike Lanes")
+    );
+    assert_eq!(article.byline.as_deref(), Some("Jane Reporter"));
+    assert_eq!(article.published, Some(date(2023, 11, 20)));
+
+    assert!(text.contains("seven to two"));
+    assert!(
+        !text.contains("By Jane Reporter"),
+        "byline is shown separately"
+    );
+    assert!(text.contains("Construction is expected to begin next spring"));
+
+    assert!(!text.contains("Weather"), "menu should be dropped");
+    assert!(
+        !text.contains("transit funding"),
+        "related links should be dropped"
+    );
+    assert!(
+        !text.contains("waste of money"),
+        "comments should be dropped"
+    );
+    assert!(!text.contains("newsletter"), "signup box should be dropped");
+}
+
+#[test]
+fn should_treat_text_only_divs_as_paragraphs() {
+    let html = include_str!("fixtures/articles/div_layout.html");
+    let article = extract_article(html).expect("div layout has content");
+    let text = article.markdown(None);
+
+    assert_eq!(
+        article.title.as_deref(),
+        Some("Notes on Writing a Tiny Allocator")
+    );
+    assert_eq!(article.byline.as_deref(), Some("Sam Writer"));
+    assert_eq!(article.published, None);
+
+    assert!(text.contains("bump allocator"));
+    assert!(text.contains("two hundred lines"));
+    assert!(!text.contains("Share on Twitter"));
+}
+
+#[test]
+fn should_return_none_for_a_page_without_text() {
+    let html =
+        "<html><head><title>Loading</title></head><body><script>boot()</script></body></html>";
+    assert_eq!(extract_article(html), None);
+}
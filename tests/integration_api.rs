@@ -48,3 +48,444 @@ async fn test_fetch_story_details_integration() {
     assert_eq!(story.id, 100);
     assert_eq!(story.title, Some("Integration Test Story".to_string()));
 }
+
+#[tokio::test]
+async fn test_search_integration() {
+    use gpui_hn_app::api::{SearchQuery, SearchTag};
+    use mockito::Matcher;
+
+    let mut server = mockito::Server::new_async().await;
+    let body = json!({
+        "hits": [
+            {
+                "objectID": "1",
+                "title": "Rust 2024 edition",
+                "url": "https://blog.rust-lang.org",
+                "author": "steveklabnik",
+                "points": 500,
+                "num_comments": 120,
+                "created_at_i": 1700000000,
+                "_tags": ["story", "author_steveklabnik", "story_1"]
+            },
+            {
+                "objectID": "2",
+                "author": "pg",
+                "comment_text": "I agree",
+                "story_id": 1,
+                "story_title": "Rust 2024 edition",
+                "created_at_i": 1700000100,
+                "_tags": ["comment", "author_pg", "story_1"]
+            }
+        ],
+        "nbHits": 42,
+        "page": 0,
+        "nbPages": 3,
+        "hitsPerPage": 20
+    });
+
+    let mock = server
+        .mock("GET", "/search")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("query".into(), "rust".into()),
+            Matcher::UrlEncoded("tags".into(), "(story,comment),author_pg".into()),
+            Matcher::UrlEncoded("page".into(), "0".into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(body.to_string())
+        .create();
+
+    let service = ApiService::new().with_algolia_base_url(format!("{}/", server.url()));
+    let mut query = SearchQuery::parse("rust author:pg");
+    query.tags = vec![SearchTag::Story, SearchTag::Comment];
+
+    let results = service.search(&query, None).await.unwrap();
+
+    mock.assert();
+    assert_eq!(results.total_hits, 42);
+    assert!(results.has_more());
+    let stories = results.stories();
+    assert_eq!(stories.len(), 2);
+    assert_eq!(stories[0].id, 1);
+    assert_eq!(stories[0].score, Some(500));
+    // Comment hits point at the story they belong to
+    assert_eq!(stories[1].id, 1);
+    assert_eq!(stories[1].by, Some("pg".to_string()));
+}
+
+#[tokio::test]
+async fn test_search_by_date_pagination_integration() {
+    use gpui_hn_app::api::SearchQuery;
+    use mockito::Matcher;
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/search_by_date")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("query".into(), "gpui".into()),
+            Matcher::UrlEncoded("numericFilters".into(), "created_at_i>=1704067200".into()),
+            Matcher::UrlEncoded("page".into(), "2".into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({ "hits": [], "nbHits": 40, "page": 2, "nbPages": 3, "hitsPerPage": 20 })
+                .to_string(),
+        )
+        .create();
+
+    let service = ApiService::new().with_algolia_base_url(format!("{}/", server.url()));
+    let mut query = SearchQuery::parse("gpui after:2024-01-01");
+    query.page = 2;
+
+    let results = service.search_by_date(&query, None).await.unwrap();
+
+    mock.assert();
+    assert_eq!(results.page, 2);
+    assert!(!results.has_more());
+    assert!(results.stories().is_empty());
+}
+
+#[tokio::test]
+async fn test_fixture_backend_serves_offline() {
+    use gpui_hn_app::api::{ApiError, FixtureBackend, NetworkConfig};
+    use std::sync::Arc;
+
+    let backend = FixtureBackend::new()
+        .with_story_list(StoryListType::Top, &[1, 2])
+        .with_item(json!({ "id": 1, "type": "story", "title": "First", "kids": [3] }))
+        .with_item(json!({ "id": 2, "type": "job", "title": "Hiring" }))
+        .with_item(json!({ "id": 3, "type": "comment", "parent": 1, "text": "Nice" }))
+        .with_user(json!({ "id": "pg", "created": 1160418092, "karma": 1 }));
+
+    let service = ApiService::with_backend(NetworkConfig::default(), Arc::new(backend));
+
+    let ids = service
+        .fetch_story_ids(StoryListType::Top, None)
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![1, 2]);
+
+    let stories = service.fetch_stories_concurrent(ids, None).await;
+    assert_eq!(stories.len(), 2);
+
+    let comments = service.fetch_comments_concurrent(vec![3], None).await;
+    assert_eq!(comments[0].text, Some("Nice".to_string()));
+
+    assert_eq!(service.fetch_user("pg").await.unwrap().karma, 1);
+
+    // Anything without a fixture answers 404
+    let err = service.fetch_item(99).await.unwrap_err();
+    assert_eq!(ApiError::find(&err), Some(&ApiError::Http { status: 404 }));
+}
+
+#[tokio::test]
+async fn test_user_submissions_mixing_comments_and_stories() {
+    use gpui_hn_app::api::{FixtureBackend, NetworkConfig};
+    use gpui_hn_app::internal::models::Item;
+    use std::sync::Arc;
+
+    let backend = FixtureBackend::new()
+        .with_user(
+            json!({ "id": "tptacek", "created": 1175289131, "karma": 400000,
+                           "submitted": [40, 31, 30, 12] }),
+        )
+        .with_item(json!({ "id": 40, "type": "comment", "parent": 12, "text": "Latest" }))
+        .with_item(json!({ "id": 31, "type": "story", "title": "Second story" }))
+        .with_item(json!({ "id": 30, "type": "comment", "parent": 31, "text": "Earlier" }))
+        .with_item(json!({ "id": 12, "type": "story", "title": "First story" }));
+
+    let service = ApiService::with_backend(NetworkConfig::default(), Arc::new(backend));
+    let user = service.fetch_user("tptacek").await.unwrap();
+
+    let mut items = service
+        .fetch_items_concurrent(user.submitted.clone(), None)
+        .await;
+    items.sort_by_key(|item| std::cmp::Reverse(item.id()));
+    let kinds: Vec<(u32, bool)> = items
+        .iter()
+        .map(|item| (item.id(), matches!(item, Item::Comment(_))))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![(40, true), (31, false), (30, true), (12, false)]
+    );
+
+    // The story loader still skips the comments
+    let stories = service.fetch_stories_concurrent(user.submitted, None).await;
+    assert_eq!(stories.len(), 2);
+}
+
+#[tokio::test]
+async fn test_record_then_replay_without_server() {
+    use gpui_hn_app::api::{LiveBackend, NetworkConfig, ReplayBackend};
+    use std::sync::Arc;
+
+    let dir = std::env::temp_dir().join(format!("hn_replay_test_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/item/100.json")
+        .with_status(200)
+        .with_body(json!({ "id": 100, "type": "story", "title": "Recorded" }).to_string())
+        .expect(1)
+        .create();
+    let config = NetworkConfig {
+        api_base_url: Some(server.url()),
+        ..NetworkConfig::default()
+    };
+
+    let live = Arc::new(LiveBackend::new(reqwest::Client::new()));
+    let recorder = ApiService::with_backend(
+        config.clone(),
+        Arc::new(ReplayBackend::record(live, dir.clone())),
+    );
+    let recorded = recorder.fetch_story_content(100).await.unwrap();
+    mock.assert();
+
+    // The replaying service never touches the server
+    drop(server);
+    let replayer = ApiService::with_backend(config, Arc::new(ReplayBackend::replay(dir.clone())));
+    let replayed = replayer.fetch_story_content(100).await.unwrap();
+
+    assert_eq!(recorded, replayed);
+    assert_eq!(replayed.title, Some("Recorded".to_string()));
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn test_recorded_pages_replay_with_bytes_type_and_url() {
+    use gpui_hn_app::api::{HnBackend, LiveBackend, ReplayBackend};
+    use std::sync::Arc;
+
+    let dir = std::env::temp_dir().join(format!("hn_replay_pages_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut server = mockito::Server::new_async().await;
+    let body: &[u8] = b"%PDF-1.4\n\xff\xfe\x00binary";
+    server
+        .mock("GET", "/old")
+        .with_status(301)
+        .with_header("location", "/paper.pdf")
+        .create();
+    server
+        .mock("GET", "/paper.pdf")
+        .with_status(200)
+        .with_header("content-type", "application/pdf")
+        .with_body(body)
+        .create();
+
+    let url = format!("{}/old", server.url());
+    let live = Arc::new(LiveBackend::new(reqwest::Client::new()));
+    let recorded = ReplayBackend::record(live, dir.clone())
+        .get_document(&url, 1024)
+        .await
+        .unwrap();
+    assert_eq!(recorded.url, format!("{}/paper.pdf", server.url()));
+
+    drop(server);
+    let replay = ReplayBackend::replay(dir.clone());
+    assert_eq!(replay.get_document(&url, 1024).await.unwrap(), recorded);
+    assert_eq!(recorded.body, body);
+    assert_eq!(recorded.content_type.as_deref(), Some("application/pdf"));
+    assert!(replay.get_document(&url, 8).await.is_err());
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn test_fetch_whole_thread_from_algolia_integration() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/items/8863")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({
+                "id": 8863, "type": "story", "author": "dhouston", "text": null,
+                "created_at_i": 1175714200, "parent_id": null,
+                "children": [
+                    { "id": 9224, "type": "comment", "author": "BrandonM", "text": "<p>First</p>",
+                      "created_at_i": 1175816820, "parent_id": 8863,
+                      "children": [
+                        { "id": 9272, "type": "comment", "author": "dhouston", "text": "Reply",
+                          "created_at_i": 1175820000, "parent_id": 9224, "children": [] }
+                      ] },
+                    { "id": 8917, "type": "comment", "author": "pg", "text": "Second",
+                      "created_at_i": 1175730000, "parent_id": 8863, "children": [] }
+                ]
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create();
+
+    let service = ApiService::new().with_algolia_base_url(format!("{}/", server.url()));
+    let comments = service
+        .fetch_thread_comments(8863, &[9224, 8917], None)
+        .await
+        .unwrap();
+
+    mock.assert();
+    let order: Vec<(u32, u32)> = comments.iter().map(|vm| (vm.id, vm.depth)).collect();
+    assert_eq!(order, vec![(9224, 0), (9272, 1), (8917, 0)]);
+    assert_eq!(comments[0].comment.by, Some("BrandonM".to_string()));
+}
+
+#[tokio::test]
+async fn test_fetch_item_story_walks_up_from_a_reply_integration() {
+    let mut server = mockito::Server::new_async().await;
+    let items = [
+        json!({ "id": 9272, "type": "comment", "by": "dhouston", "text": "Reply", "parent": 9224 }),
+        json!({ "id": 9224, "type": "comment", "by": "BrandonM", "text": "First", "parent": 8863 }),
+        json!({ "id": 8863, "type": "story", "by": "dhouston", "title": "My YC app: Dropbox", "kids": [9224] }),
+    ];
+    let mocks: Vec<_> = items
+        .iter()
+        .map(|item| {
+            server
+                .mock("GET", format!("/item/{}.json", item["id"]).as_str())
+                .with_status(200)
+                .with_header("content-type", "application/json")
+                .with_body(item.to_string())
+                .expect(1)
+                .create()
+        })
+        .collect();
+
+    let service = ApiService::with_base_url(format!("{}/", server.url()));
+    let story = service.fetch_item_story(9272).await.unwrap();
+
+    for mock in mocks {
+        mock.assert();
+    }
+    assert_eq!(story.id, 8863);
+    assert_eq!(story.title, Some("My YC app: Dropbox".to_string()));
+}
+
+#[tokio::test]
+async fn test_fetch_item_story_stops_at_a_parent_cycle_integration() {
+    use gpui_hn_app::api::{FixtureBackend, NetworkConfig};
+    use std::sync::Arc;
+
+    let backend = FixtureBackend::new()
+        .with_item(json!({ "id": 1, "type": "comment", "text": "A", "parent": 2 }))
+        .with_item(json!({ "id": 2, "type": "comment", "text": "B", "parent": 1 }));
+    let service = ApiService::with_backend(NetworkConfig::default(), Arc::new(backend));
+
+    let err = service.fetch_item_story(1).await.unwrap_err();
+    assert!(format!("{:#}", err).contains("item 1 is its own ancestor"));
+}
+
+#[tokio::test]
+async fn test_archived_article_from_wayback_integration() {
+    use gpui_hn_app::api::ArchiveSource;
+    use mockito::Matcher;
+
+    let mut server = mockito::Server::new_async().await;
+    let lookup = server
+        .mock("GET", "/wayback/available")
+        .match_query(Matcher::UrlEncoded(
+            "url".into(),
+            "https://example.com/post".into(),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({ "archived_snapshots": { "closest": {
+                "available": true, "status": "200", "timestamp": "20240305120000",
+                "url": format!("{}/web/20240305120000/https://example.com/post", server.url())
+            } } })
+            .to_string(),
+        )
+        .create();
+    let snapshot = server
+        .mock("GET", "/web/20240305120000id_/https://example.com/post")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<html><body><p>Archived text</p></body></html>")
+        .create();
+
+    let service = ApiService::new().with_archive_base_urls(
+        format!("{}/", server.url()),
+        format!("{}/today/", server.url()),
+    );
+    let archived = service
+        .fetch_archived_article("https://example.com/post")
+        .await
+        .unwrap();
+
+    lookup.assert();
+    snapshot.assert();
+    assert_eq!(archived.snapshot.source, ArchiveSource::Wayback);
+    assert_eq!(
+        archived.snapshot.url,
+        format!(
+            "{}/web/20240305120000/https://example.com/post",
+            server.url()
+        )
+    );
+    assert_eq!(
+        archived.snapshot.captured,
+        Some("2024-03-05T12:00:00Z".parse().unwrap())
+    );
+    assert!(archived.article.body.contains("Archived text"));
+}
+
+#[tokio::test]
+async fn test_archived_article_falls_back_in_configured_order_integration() {
+    use gpui_hn_app::api::{ApiError, ArchiveSource, NetworkConfig};
+    use mockito::Matcher;
+
+    let mut server = mockito::Server::new_async().await;
+    let wayback = server
+        .mock("GET", "/wayback/available")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_body(json!({ "archived_snapshots": {} }).to_string())
+        .expect(2)
+        .create();
+    let newest = server
+        .mock("GET", "/newest/https://example.com/gone")
+        .with_status(302)
+        .with_header("location", "/AbCd1")
+        .create();
+    let snapshot = server
+        .mock("GET", "/AbCd1")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<html><body><p>Kept by archive.today</p></body></html>")
+        .create();
+    let missing = server
+        .mock("GET", "/newest/https://example.com/never")
+        .with_status(404)
+        .create();
+
+    let config = NetworkConfig {
+        archive_sources: vec![ArchiveSource::Wayback, ArchiveSource::ArchiveToday],
+        max_retries: 0,
+        ..NetworkConfig::default()
+    };
+    let service = ApiService::with_config(config)
+        .with_archive_base_urls(format!("{}/", server.url()), format!("{}/", server.url()));
+
+    let archived = service
+        .fetch_archived_article("https://example.com/gone")
+        .await
+        .unwrap();
+    assert_eq!(archived.snapshot.source, ArchiveSource::ArchiveToday);
+    assert_eq!(archived.snapshot.url, format!("{}/AbCd1", server.url()));
+    assert_eq!(archived.snapshot.captured, None);
+    assert!(archived.article.body.contains("Kept by archive.today"));
+
+    let err = service
+        .fetch_archived_article("https://example.com/never")
+        .await
+        .unwrap_err();
+    assert!(matches!(ApiError::find(&err), Some(ApiError::NotFound(_))));
+
+    wayback.assert();
+    newest.assert();
+    snapshot.assert();
+    missing.assert();
+}
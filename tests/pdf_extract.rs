@@ -0,0 +1,60 @@
+use gpui_hn_app::utils::pdf::{OutlineEntry, extract_pdf};
+
+fn entry(title: &str, level: usize, page: usize) -> OutlineEntry {
+    OutlineEntry {
+        title: title.to_string(),
+        level,
+        page,
+    }
+}
+
+#[test]
+fn should_extract_pages_and_outline_of_a_paper() {
+    let pdf = extract_pdf(include_bytes!("fixtures/pdf/paper.pdf")).expect("paper is readable");
+
+    assert_eq!(pdf.pages.len(), 3);
+    assert!(pdf.pages[0].contains("We compare LRU and CLOCK on real traces."));
+    assert!(pdf.pages[1].contains("2.1 Traces"));
+    assert!(pdf.pages[2].contains("within two percent"));
+    assert_eq!(
+        pdf.outline,
+        vec![
+            entry("Introduction", 1, 1),
+            entry("Method", 1, 2),
+            entry("Traces", 2, 2),
+            entry("Results", 1, 3),
+        ]
+    );
+}
+
+#[test]
+fn should_render_outline_and_page_markers_as_markdown() {
+    let pdf = extract_pdf(include_bytes!("fixtures/pdf/paper.pdf")).expect("paper is readable");
+    let markdown = pdf.markdown();
+
+    assert!(markdown.starts_with(
+        "**Contents**\n\n- Introduction · p. 1\n- Method · p. 2\n  - Traces · p. 2\n- Results · p. 3"
+    ));
+    let pages: Vec<usize> = ["*Page 1 of 3*", "*Page 2 of 3*", "*Page 3 of 3*"]
+        .iter()
+        .map(|marker| markdown.find(marker).expect("every page has a marker"))
+        .collect();
+    assert!(pages.is_sorted(), "pages are in order");
+    assert!(markdown[pages[2]..].contains("CLOCK comes within two percent of LRU."));
+}
+
+#[test]
+fn should_extract_a_document_without_outline() {
+    let pdf = extract_pdf(include_bytes!("fixtures/pdf/slides.pdf")).expect("slides are readable");
+
+    assert!(pdf.outline.is_empty());
+    assert!(!pdf.is_empty());
+    assert_eq!(pdf.pages.len(), 2);
+    assert!(pdf.pages[1].contains("Memory safety without a GC"));
+    assert!(pdf.markdown().starts_with("*Page 1 of 2*"));
+}
+
+#[test]
+fn should_reject_data_that_is_not_a_pdf() {
+    assert!(extract_pdf(b"<html>not a pdf</html>").is_err());
+}
@@ -1,4 +1,4 @@
-use gpui_hn_app::internal::models::{Comment, Story};
+use gpui_hn_app::internal::models::{Comment, Item, PollOpt, Story, StoryKind, poll_vote_shares};
 use proptest::prelude::*;
 use serde_json::json;
 
@@ -74,6 +74,33 @@ fn comment_json_strategy() -> impl Strategy<Value = serde_json::Value> {
         })
 }
 
+// Strategy for generating item JSON of every type the API returns
+fn item_json_strategy() -> impl Strategy<Value = serde_json::Value> {
+    (
+        prop::sample::select(vec!["story", "comment", "job", "poll", "pollopt"]),
+        any::<u32>(),                               // id
+        prop::option::of("[a-zA-Z0-9 <>&]{0,200}"), // text
+        any::<bool>(),                              // dead
+        prop::collection::vec(any::<u32>(), 0..5),  // kids / parts
+    )
+        .prop_map(|(item_type, id, text, dead, ids)| {
+            let mut obj = json!({
+                "id": id,
+                "type": item_type,
+                "dead": dead
+            });
+            if let Some(t) = text {
+                obj["text"] = json!(t);
+            }
+            match item_type {
+                "poll" => obj["parts"] = json!(ids),
+                "pollopt" => obj["poll"] = json!(ids.first().copied().unwrap_or(0)),
+                _ => obj["kids"] = json!(ids),
+            }
+            obj
+        })
+}
+
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(100))]
 
@@ -117,4 +144,54 @@ proptest! {
         let _result: Result<Story, _> = serde_json::from_str(&garbage);
         let _result2: Result<Comment, _> = serde_json::from_str(&garbage);
     }
+
+    #[test]
+    fn item_type_selects_variant(item_json in item_json_strategy()) {
+        let item: Item = serde_json::from_str(&item_json.to_string()).unwrap();
+        prop_assert_eq!(item.id(), item_json["id"].as_u64().unwrap() as u32);
+        prop_assert_eq!(item.is_dead(), item_json["dead"].as_bool().unwrap());
+
+        let item_type = item_json["type"].as_str().unwrap();
+        let expected_kind = match item_type {
+            "story" => Some(StoryKind::Story),
+            "job" => Some(StoryKind::Job),
+            "poll" => Some(StoryKind::Poll),
+            _ => None,
+        };
+        prop_assert_eq!(item.clone().into_story().map(|s| s.kind), expected_kind);
+        prop_assert_eq!(item.into_comment().is_some(), item_type == "comment");
+    }
+
+    #[test]
+    fn unknown_item_type_is_rejected(item_type in "[a-z]{1,10}") {
+        prop_assume!(!["story", "comment", "job", "poll", "pollopt"].contains(&item_type.as_str()));
+        let item_json = json!({ "id": 1, "type": item_type });
+        prop_assert!(serde_json::from_str::<Item>(&item_json.to_string()).is_err());
+    }
+
+    #[test]
+    fn poll_vote_shares_sum_to_one(scores in prop::collection::vec(0u32..100_000, 1..10)) {
+        let options: Vec<PollOpt> = scores
+            .iter()
+            .enumerate()
+            .map(|(i, score)| PollOpt {
+                id: i as u32,
+                poll: 0,
+                by: None,
+                score: Some(*score),
+                text: None,
+                time: None,
+                dead: false,
+                deleted: false,
+            })
+            .collect();
+
+        let shares = poll_vote_shares(&options);
+        prop_assert_eq!(shares.len(), options.len());
+        let total: f32 = shares.iter().sum();
+        match scores.iter().sum::<u32>() {
+            0 => prop_assert_eq!(total, 0.0),
+            _ => prop_assert!((total - 1.0).abs() < 1e-3),
+        }
+    }
 }